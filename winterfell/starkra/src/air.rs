@@ -1,26 +1,741 @@
-use std::u64;
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "prover")]
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
+#[cfg(feature = "prover")]
+use core::time::Duration;
 
-use tracing::trace_span;
 use winterfell::{
-    Air, AirContext, Assertion, TraceTable, TransitionConstraintDegree,
+    Air, AirContext, Assertion, BatchingMethod, EvaluationFrame, FieldExtension, ProofOptions, Trace,
+    TraceInfo, TraceTable, TransitionConstraintDegree,
     math::{FieldElement, ToElements, fields::f64::BaseElement},
 };
 
 use crate::{
-    air,
-    cfg::Cfg,
-    exe_path::{JmpType, Step},
+    cfg::{Cfg, EntryPolicy, NodeId},
+    error::{PathError, StarkraError},
 };
+use crate::exe_path::Step;
+#[cfg(feature = "prover")]
+use crate::exe_path::{JmpType, ShadowStack, reverse_walk};
+/// Trace encoding of a node ID: `n + 1`. Cell value 0 is reserved for "no
+/// node" (empty stack, unused neighbor slot), so node 0 is a real, provable
+/// node and can never be confused with padding.
+pub fn encode_node(n: NodeId) -> BaseElement {
+    BaseElement::new(n + 1)
+}
+
+/// Inverse of [`encode_node`]; `None` for the "no node" value 0.
+pub fn decode_node(e: BaseElement) -> Option<NodeId> {
+    e.as_int().checked_sub(1)
+}
+
+#[cfg(feature = "prover")]
+fn encode_opt(n: Option<NodeId>) -> BaseElement {
+    n.map(encode_node).unwrap_or(BaseElement::ZERO)
+}
+
+/// How the AIR checks that each step moves to a neighbor of the node
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum NeighborEncoding {
+    /// Products over the neighbor slots (see `StarkraAir::transition_check`
+    /// and `taken_check`): no extra columns, but three constraints of
+    /// degree `max_succ + 2`, so the blowup and the composition polynomial
+    /// grow with the largest out-degree.
+    #[default]
+    Product,
+    /// One selector column per neighbor slot, 1 in the slot `taken` names
+    /// and 0 elsewhere (see `StarkraAir::selector_check`): every neighbor
+    /// constraint has degree at most 2, for `max_succ` more columns and
+    /// `max_succ` more constraints. On a single core, for an 8192-row path
+    /// through a hub with `k` successors, 27 queries and each encoding's
+    /// smallest blowup (at least 8): `k = 8` proved in 0.32 s product vs
+    /// 0.12 s one-hot, `k = 16` in 0.97 s vs 0.16 s, `k = 32` in 3.95 s
+    /// vs 0.24 s, with smaller proofs. The smaller blowup is also less
+    /// conjectured security per query, so match it with more queries.
+    OneHot,
+}
+
+impl NeighborEncoding {
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            Self::Product => 0,
+            Self::OneHot => 1,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Self::Product),
+            1 => Some(Self::OneHot),
+            _ => None,
+        }
+    }
+}
+
+/// Column indices of the execution trace:
+/// nonce, current, stack(top), neighbors..., valid, ret, call, forbidden_inv, taken, step, budget,
+/// entry_inv, segment, then, for [`NeighborEncoding::OneHot`], selectors...
+///
+/// `current`, `stack` and the neighbor columns hold [`encode_node`] values.
+///
+/// The neighbor columns cannot be replaced by periodic columns, even for
+/// mostly straight-line CFGs: a periodic column's value depends only on
+/// the row index, while the successor set depends on which node the path
+/// is at in that row, which differs from path to path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceLayout {
+    pub max_succ: usize,
+    pub nonce: usize,
+    pub current: usize,
+    pub stack: usize,
+    pub neighbors_start: usize,
+    pub valid: usize,
+    pub ret: usize,
+    pub call: usize,
+    /// inverse of ∏ (current - f) over the forbidden set
+    pub forbidden_inv: usize,
+    /// index of the neighbor slot holding the next row's `current`
+    pub taken: usize,
+    /// number of real rows before this one; constant over `valid = 0` rows
+    pub step: usize,
+    /// `step` plus the slack of a step bound: the slack starts at 0 and
+    /// grows by 0 or 1 per row (see [`PublicInputs::max_steps`])
+    pub budget: usize,
+    /// inverse of the entry-policy value of the step into this row (see
+    /// [`PublicInputs::entry_policies`])
+    pub entry_inv: usize,
+    /// number of segments started before this row: 0 through the first
+    /// segment, one more from each later segment's first row on
+    pub segment: usize,
+    /// first selector column; equal to `width` for `Product`
+    pub selectors_start: usize,
+    pub encoding: NeighborEncoding,
+    pub width: usize,
+}
+
+impl TraceLayout {
+    /// Number of columns that are not neighbor slots or selectors.
+    pub const FIXED_COLUMNS: usize = 12;
+
+    pub fn new(max_succ: usize) -> Self {
+        Self::with_encoding(max_succ, NeighborEncoding::Product)
+    }
+
+    pub fn with_encoding(max_succ: usize, encoding: NeighborEncoding) -> Self {
+        let neighbors_start = 3;
+        let valid = neighbors_start + max_succ;
+        let selectors = match encoding {
+            NeighborEncoding::Product => 0,
+            NeighborEncoding::OneHot => max_succ,
+        };
+        Self {
+            max_succ,
+            nonce: 0,
+            current: 1,
+            stack: 2,
+            neighbors_start,
+            valid,
+            ret: valid + 1,
+            call: valid + 2,
+            forbidden_inv: valid + 3,
+            taken: valid + 4,
+            step: valid + 5,
+            budget: valid + 6,
+            entry_inv: valid + 7,
+            segment: valid + 8,
+            selectors_start: valid + 9,
+            encoding,
+            width: valid + 9 + selectors,
+        }
+    }
+
+    /// Recover the layout from a trace width (as seen by the verifier).
+    /// Assumes the `Product` encoding; see [`TraceLayout::from_width_with`].
+    pub fn from_width(width: usize) -> Self {
+        Self::new(width - Self::FIXED_COLUMNS)
+    }
+
+    /// Recover the layout from a trace width under `encoding`.
+    pub fn from_width_with(width: usize, encoding: NeighborEncoding) -> Self {
+        let max_succ = match encoding {
+            NeighborEncoding::Product => width - Self::FIXED_COLUMNS,
+            NeighborEncoding::OneHot => (width - Self::FIXED_COLUMNS) / 2,
+        };
+        Self::with_encoding(max_succ, encoding)
+    }
+
+    /// `from_width_with`, or `None` if no layout under `encoding` is
+    /// `width` columns wide (e.g. the width of an untrusted proof).
+    pub fn try_from_width_with(width: usize, encoding: NeighborEncoding) -> Option<Self> {
+        let extra = width.checked_sub(Self::FIXED_COLUMNS)?;
+        match encoding {
+            NeighborEncoding::OneHot if extra % 2 != 0 => None,
+            _ => Some(Self::from_width_with(width, encoding)),
+        }
+    }
+
+    pub fn neighbors(&self) -> Range<usize> {
+        self.neighbors_start..self.valid
+    }
+
+    /// The selector columns; empty for `Product`.
+    pub fn selectors(&self) -> Range<usize> {
+        self.selectors_start..self.width
+    }
+
+    /// Index of `column` in this layout.
+    ///
+    /// # Panics
+    /// If a `Neighbor` slot is not below `max_succ`, or a `Selector` is not
+    /// a column of this layout.
+    pub fn index(&self, column: Column) -> usize {
+        match column {
+            Column::Nonce => self.nonce,
+            Column::Current => self.current,
+            Column::Stack => self.stack,
+            Column::Neighbor(i) => {
+                assert!(i < self.max_succ, "neighbor slot {} out of range (max_succ {})", i, self.max_succ);
+                self.neighbors_start + i
+            }
+            Column::Valid => self.valid,
+            Column::Ret => self.ret,
+            Column::Call => self.call,
+            Column::ForbiddenInv => self.forbidden_inv,
+            Column::Taken => self.taken,
+            Column::Step => self.step,
+            Column::Budget => self.budget,
+            Column::EntryInv => self.entry_inv,
+            Column::Segment => self.segment,
+            Column::Selector(i) => {
+                assert!(i < self.selectors().len(), "selector {} out of range ({:?} layout)", i, self.encoding);
+                self.selectors_start + i
+            }
+        }
+    }
+
+    /// Column names in layout order.
+    pub fn headers(&self) -> Vec<String> {
+        let mut headers = vec!["nonce".to_string(), "current".to_string(), "stack".to_string()];
+        for i in 0..self.max_succ {
+            headers.push(format!("nei{}", i));
+        }
+        headers.push("valid".to_string());
+        headers.push("ret".to_string());
+        headers.push("call".to_string());
+        headers.push("forb_inv".to_string());
+        headers.push("taken".to_string());
+        headers.push("step".to_string());
+        headers.push("budget".to_string());
+        headers.push("entry_inv".to_string());
+        headers.push("segment".to_string());
+        for i in 0..self.selectors().len() {
+            headers.push(format!("sel{}", i));
+        }
+        headers
+    }
+}
+
+/// A trace column by meaning; [`TraceLayout::index`] gives its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Nonce,
+    Current,
+    Stack,
+    /// Neighbor slot `i`, for `i` in `0..max_succ`.
+    Neighbor(usize),
+    Valid,
+    Ret,
+    Call,
+    ForbiddenInv,
+    Taken,
+    Step,
+    Budget,
+    EntryInv,
+    Segment,
+    /// Selector `i` of a [`NeighborEncoding::OneHot`] layout.
+    Selector(usize),
+}
+
+/// `column` at `row` of `trace`, laid out as `layout`. The width alone
+/// does not tell the encodings apart, so the caller says which it is.
+pub fn trace_get(trace: &TraceTable<BaseElement>, layout: &TraceLayout, column: Column, row: usize) -> BaseElement {
+    trace.get(layout.index(column), row)
+}
+
+impl From<&Cfg> for TraceLayout {
+    fn from(cfg: &Cfg) -> Self {
+        Self::new(cfg.max_successors())
+    }
+}
+
+/// What each transition constraint enforces, in `evaluate_transition` order.
+/// A `OneHot` AIR follows these with one [`SELECTOR_LABEL`] constraint per
+/// neighbor slot.
+pub const CONSTRAINT_LABELS: &[&str] =
+    &[
+        "nonce", "neighbors", "stack", "forbidden", "taken_range", "taken_edge", "step", "call_ret", "call_bool",
+        "ret_bool", "valid_bool", "budget", "padding", "stack_hold", "segment", "entry",
+    ];
+
+/// Number of transition constraints; `evaluate_transition` and
+/// `constraint_info_for` are checked against it at compile time.
+pub const NUM_CONSTRAINTS: usize = CONSTRAINT_LABELS.len();
+
+/// Index of the `entry` constraint, whose degree depends on the entry
+/// policies.
+const ENTRY_CONSTRAINT: usize = NUM_CONSTRAINTS - 1;
+
+/// Label of the per-slot constraints of a `OneHot` AIR.
+pub const SELECTOR_LABEL: &str = "selector";
+
+/// Label of transition constraint `i`.
+pub fn constraint_label(i: usize) -> &'static str {
+    CONSTRAINT_LABELS.get(i).copied().unwrap_or(SELECTOR_LABEL)
+}
+
+/// Transition-constraint degrees and assertion count of a `StarkraAir`,
+/// available without building one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintInfo {
+    /// Degree of each transition constraint, in `evaluate_transition` order.
+    pub degrees: Vec<usize>,
+    pub num_assertions: usize,
+}
+
+impl ConstraintInfo {
+    pub fn max_degree(&self) -> usize {
+        self.degrees.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Smallest blowup factor winterfell accepts for these degrees
+    /// (`AirContext::new` panics below it).
+    pub fn min_blowup(&self) -> usize {
+        self.degrees
+            .iter()
+            .map(|&d| TransitionConstraintDegree::new(d).min_blowup_factor())
+            .max()
+            .unwrap_or(ProofOptions::MIN_BLOWUP_FACTOR)
+    }
+}
+
+/// Largest blowup factor `ProofOptions::new` accepts.
+const MAX_BLOWUP_FACTOR: usize = 128;
+
+/// Smallest power-of-two blowup factor that satisfies both the degree
+/// requirement of a `max_succ` layout and `target_security_bits` of
+/// conjectured security at `num_queries` queries.
+///
+/// Conjectured security is `log2(blowup) * num_queries - 1` bits (grinding
+/// is ignored; it only ever adds to this). If even the largest blowup
+/// winterfell accepts falls short, that largest value is returned and the
+/// caller needs more queries.
+pub fn recommended_blowup(max_succ: usize, num_queries: usize, target_security_bits: u32) -> usize {
+    let mut blowup = StarkraAir::constraint_info(max_succ).min_blowup();
+    while blowup < MAX_BLOWUP_FACTOR
+        && (blowup.ilog2() as usize * num_queries) < target_security_bits as usize + 1
+    {
+        blowup *= 2;
+    }
+    blowup
+}
+
+/// Largest value any cell of `StarkraAir::build_trace(path, cfg, nonce)`
+/// holds, before reduction into the field: node cells hold `n + 1` (for
+/// the path's nodes, return addresses and their successors), and the
+/// nonce, `step`, `budget` and `taken` cells small counters. Computed
+/// from the inputs, without building the trace.
+///
+/// Compare it with the field modulus (`BaseElement::MODULUS`): a value at
+/// or above it wraps, so two nodes could share an encoding. Below it, the
+/// encoding is injective and nothing else can wrap in a harmful way: the
+/// constraints are polynomial identities over the field (or its extension,
+/// when the verifier evaluates them at random points), so their products
+/// of differences reduce modulo the prime by design and only need to be
+/// zero exactly when a factor is. `MAX_NODE_ID` is the largest ID that
+/// stays below the modulus.
+pub fn max_field_value(cfg: &Cfg, path: &[Step], nonce: u32) -> u64 {
+    let node = |n: NodeId| n.saturating_add(1);
+    let nodes = path.iter().flat_map(|s| {
+        let succ = s.addrs.first().map(|&n| cfg.successors(n)).unwrap_or(&[]);
+        s.addrs.iter().chain(succ).map(|&n| node(n))
+    });
+    let counters = [
+        nonce as u64,
+        // step and budget: the last real row's index
+        path.len().saturating_sub(1) as u64,
+        // taken: a neighbor slot index
+        cfg.max_successors().saturating_sub(1) as u64,
+        // valid, call, ret; forbidden_inv is 1 without forbidden nodes
+        (!path.is_empty()) as u64,
+    ];
+    nodes.chain(counters).max().unwrap_or(0)
+}
+
+/// The neighbor slot of `curr` that holds `next`: the first index `i` with
+/// `cfg.successors(curr)[i] == next`, i.e. the factor of the AIR's
+/// neighbor product that vanishes, and the value the trace builder writes
+/// to `taken`. `None` if `next` is not a successor, an invalid transition.
+pub fn matched_successor_slot(cfg: &Cfg, curr: NodeId, next: NodeId) -> Option<usize> {
+    cfg.successors(curr).iter().position(|&s| s == next)
+}
+
+/// Entry-policy nodes by the kind of step their policy rules out, as
+/// [`encode_node`] values: `call` holds the nodes a call may not enter,
+/// and so on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EntryClasses {
+    call: Vec<BaseElement>,
+    ret: Vec<BaseElement>,
+    jump: Vec<BaseElement>,
+}
+
+impl EntryClasses {
+    /// From `(plain node ID, policy)` pairs.
+    pub(crate) fn new<'a>(policies: impl IntoIterator<Item = (BaseElement, &'a EntryPolicy)>) -> Self {
+        let mut classes = Self::default();
+        for (n, p) in policies {
+            let n = n + BaseElement::ONE;
+            for (allowed, class) in [(p.call, &mut classes.call), (p.ret, &mut classes.ret), (p.jump, &mut classes.jump)] {
+                if !allowed {
+                    class.push(n);
+                }
+            }
+        }
+        classes
+    }
+
+    /// The classes of `cfg`'s entry policies.
+    #[cfg(feature = "prover")]
+    pub(crate) fn of(cfg: &Cfg) -> Self {
+        Self::new(cfg.entry_policies().iter().map(|(&n, p)| (BaseElement::new(n), p)))
+    }
+
+    /// Degree of the `entry` constraint: `(q · entry_inv - 1)` on two real
+    /// rows, where `q` has degree one more than the largest class (or is
+    /// the constant 1 without any restriction).
+    fn degree(&self) -> usize {
+        match self.call.len().max(self.ret.len()).max(self.jump.len()) {
+            0 => 3,
+            k => k + 4,
+        }
+    }
+
+    /// `q = call · ∏_call (x - n) + ret · ∏_ret (x - n) + (1 - call - ret) · ∏_jump (x - n)`
+    /// for a row entering node value `x` with the given flags: zero exactly
+    /// when the row's kind of step is ruled out for `x` (given `call_ret`,
+    /// so the flags pick one of the three terms).
+    pub(crate) fn value<E: FieldElement<BaseField = BaseElement>>(&self, x: E, call: E, ret: E) -> E {
+        let prod = |class: &[BaseElement]| class.iter().fold(E::ONE, |acc, &n| acc * (x - E::from(n)));
+        call * prod(&self.call) + ret * prod(&self.ret) + (E::ONE - call - ret) * prod(&self.jump)
+    }
+}
+
 //Public inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PublicInputs {
+    /// Plain node IDs; the AIR applies `encode_node` when asserting them.
+    /// `end` is asserted on the last row, and padding rows repeat the last
+    /// real row's node, so it is the node the path ends at.
     pub start: BaseElement,
     pub end: BaseElement,
+    /// Nonce of the first segment (row 0).
     pub nonce: BaseElement,
+    /// `(first_row, nonce)` of every further segment in a segmented trace;
+    /// empty for a single session. `valid` is asserted to be 0 on the row
+    /// before `first_row` and 1 on it, and these are the only rows where
+    /// it may step from 0 back to 1 (see [`PublicInputs::check_segments`]).
+    /// The nonce only changes on these rows, so each segment keeps its own.
+    pub segment_nonces: Vec<(usize, BaseElement)>,
+    /// Nodes the path must never visit. The verifier also rejects a
+    /// forbidden `start` or `end` (see
+    /// [`PublicInputs::check_forbidden_endpoints`]).
+    pub forbidden: Vec<BaseElement>,
+    /// Claimed number of real rows, over all segments. When set, the
+    /// `step` column is pinned to 0 on row 0 and to `n - 1` on the last
+    /// row, and `valid` to 0 on the row after the last real row (if the
+    /// trace has one), so the execution was exactly `n` steps long.
+    pub num_steps: Option<usize>,
+    /// Claimed bound on the number of real rows: the path took at most
+    /// this many steps, without saying how many. The `budget` column is
+    /// pinned to 0 on row 0 and to the bound's last step index on the last
+    /// row; since its slack over `step` only grows by 0 or 1 per row, it
+    /// ends in `0..trace_length`, which range-checks `max - 1 - step`.
+    /// Bounds of `trace_length` or more hold for any trace and are clamped.
+    pub max_steps: Option<usize>,
+    /// `(step, node)` pairs the path must pass through: `current` is
+    /// asserted to be `node` (a plain ID) on row `step`. Rows count across
+    /// segments, so for a single session the row is the step index. The
+    /// first and last rows are already pinned by `start` and `end`.
+    pub waypoints: Vec<(usize, BaseElement)>,
+    /// [`Cfg::commitment`] of the CFG the proof was made for. It is bound
+    /// into the proof's transcript, so it cannot be swapped afterwards, but
+    /// the AIR does not check the trace's neighbor columns against it: it
+    /// names the CFG the prover claims, for the verifier to pick and check.
+    /// It only ties the proof to a CFG when the verifier recomputes it from
+    /// a CFG it trusts, as `verify_path` does from its `cfg` argument; a
+    /// CFG supplied along with the proof matches it by construction.
+    pub cfg_commitment: Option<[u8; 32]>,
+    /// Root of a [`SequenceCommitment`](crate::sequence::SequenceCommitment)
+    /// to a full expected node sequence that `prefix` is claimed to start.
+    /// The AIR does not check it: `verifier::verify_prefix` opens `prefix`
+    /// against it, and the prefix assertions tie the trace to `prefix`.
+    pub sequence_root: Option<[u8; 32]>,
+    /// Plain node IDs of rows `0..prefix.len()`: `current` is asserted to
+    /// hold each one, as for waypoints. Rows 0 and the last row are already
+    /// pinned by `start` and `end`, which must agree with the prefix there.
+    pub prefix: Vec<BaseElement>,
+    /// How the trace encodes the step to a neighbor; a `OneHot` trace
+    /// carries selector columns (see [`NeighborEncoding`]).
+    pub neighbor_encoding: NeighborEncoding,
+    /// `(node, policy)` for every entry-policy node of the CFG (see
+    /// [`Cfg::with_entry_policies`]), by plain node ID. Between two real
+    /// rows the `entry` constraint rejects a step into one of these nodes
+    /// by a kind its policy rules out. The verifier requires them to equal
+    /// the CFG's, so a prover cannot leave a policy out.
+    ///
+    /// The kind is read from the entered row's `call` and `ret` flags, and
+    /// `1 - call - ret` selects a jump only because the flags are boolean
+    /// and exclusive. Nothing binds `call` to a call edge, and a call row
+    /// may set the stack top to anything, so a prover choosing the flags
+    /// can pass a jump off as a call, or push the node it later "returns"
+    /// to. Against such a prover a policy only binds where it rules out
+    /// every kind of step into its node.
+    /// Every node in the largest class raises the `entry` constraint's
+    /// degree by one (see [`PublicInputs::check_blowup`]).
+    pub entry_policies: Vec<(BaseElement, EntryPolicy)>,
+}
+
+impl PublicInputs {
+    /// The public inputs of proving `steps` over `cfg` with `nonce`: the
+    /// first node as `start`, the last step's node as `end`, the nonce,
+    /// and `cfg`'s commitment and entry policies. Nothing else is claimed
+    /// (one session, no forbidden nodes, step count or waypoints, `Product`
+    /// encoding). `prover::prove_path` and a prover set up with
+    /// `StarkraProver::with_path_inputs` claim exactly these, so inputs
+    /// built here for verification match the proof's.
+    ///
+    /// Fails on an empty path, or a first or last step without an address.
+    pub fn from_path(cfg: &Cfg, steps: &[Step], nonce: u32) -> Result<Self, StarkraError> {
+        let node = |i: usize| {
+            let step = &steps[i];
+            step.addrs.first().copied().ok_or(PathError::Arity {
+                step: i,
+                expected: step.jmp_type.arity(),
+                actual: 0,
+            })
+        };
+        let last = steps.len().checked_sub(1).ok_or(PathError::Empty)?;
+        Ok(Self {
+            start: BaseElement::new(node(0)?),
+            end: BaseElement::new(node(last)?),
+            nonce: BaseElement::new(nonce as u64),
+            segment_nonces: Vec::new(),
+            forbidden: Vec::new(),
+            num_steps: None,
+            max_steps: None,
+            waypoints: Vec::new(),
+            cfg_commitment: Some(cfg.commitment()),
+            sequence_root: None,
+            prefix: Vec::new(),
+            neighbor_encoding: NeighborEncoding::Product,
+            entry_policies: cfg.entry_policies().iter().map(|(&n, &p)| (BaseElement::new(n), p)).collect(),
+        })
+    }
+
+    /// Reject public inputs that bind the same nonce to more than one
+    /// segment, which would let segments be recombined across sessions.
+    pub fn check_distinct_nonces(&self) -> Result<(), StarkraError> {
+        let mut seen = vec![self.nonce];
+        for &(_, nonce) in &self.segment_nonces {
+            if seen.contains(&nonce) {
+                return Err(StarkraError::NonceReuse(nonce.as_int()));
+            }
+            seen.push(nonce);
+        }
+        Ok(())
+    }
+
+    /// Reject further segments whose first rows are not in increasing
+    /// order within a trace of `trace_length` rows, each after at least
+    /// one real row and a separator row. The AIR asserts `valid` on a
+    /// segment's first row and the row before it, which must be distinct
+    /// rows of the trace.
+    pub fn check_segments(&self, trace_length: usize) -> Result<(), StarkraError> {
+        let mut earliest = 2;
+        for &(row, _) in &self.segment_nonces {
+            if row < earliest || row >= trace_length {
+                return Err(StarkraError::SegmentRow { row, limit: trace_length });
+            }
+            earliest = row + 2;
+        }
+        Ok(())
+    }
+
+    /// Reject a forbidden `start` or `end`. Transition constraints only
+    /// reach the last row as the next row of a pair, so the forbidden
+    /// check never runs on it; where the path ends on that row, only this
+    /// keeps `end` from being forbidden.
+    pub fn check_forbidden_endpoints(&self) -> Result<(), StarkraError> {
+        match [self.start, self.end].into_iter().find(|n| self.forbidden.contains(n)) {
+            Some(n) => Err(StarkraError::ForbiddenEndpoint(n.as_int())),
+            None => Ok(()),
+        }
+    }
+
+    /// Reject a step-count claim or bound that cannot describe a trace of
+    /// `trace_length` rows: `n` real rows and a separator per further
+    /// segment must fit, with every segment starting on one of them.
+    pub fn check_step_count(&self, trace_length: usize) -> Result<(), StarkraError> {
+        let rows = |n: usize| n + self.segment_nonces.len();
+        let last_start = self.segment_nonces.last().map_or(0, |&(row, _)| row);
+        match (self.num_steps, self.max_steps) {
+            (Some(n), _) if n == 0 || rows(n) > trace_length || last_start >= rows(n) => {
+                Err(StarkraError::StepCount { claimed: n, trace_length })
+            }
+            (_, Some(0)) => Err(StarkraError::StepCount { claimed: 0, trace_length }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject waypoints that are not strictly between the first row and
+    /// the last real row (the claimed step count, if any, else the last
+    /// row of `trace_length`), or that share a step.
+    pub fn check_waypoints(&self, trace_length: usize) -> Result<(), StarkraError> {
+        let last = trace_length.saturating_sub(1);
+        let limit = self.num_steps.map_or(last, |n| n.min(last));
+        let mut seen = Vec::with_capacity(self.waypoints.len());
+        for &(step, _) in &self.waypoints {
+            if step == 0 || step >= limit {
+                return Err(StarkraError::Waypoint { step, limit });
+            }
+            if seen.contains(&step) {
+                return Err(StarkraError::DuplicateWaypoint(step));
+            }
+            seen.push(step);
+        }
+        Ok(())
+    }
+
+    /// Reject a prefix that is longer than the trace, disagrees with
+    /// `start` or `end`, or shares a row with a waypoint, or a sequence
+    /// root without a prefix.
+    pub fn check_prefix(&self, trace_length: usize) -> Result<(), StarkraError> {
+        let len = self.prefix.len();
+        if len > trace_length || (len == 0 && self.sequence_root.is_some()) {
+            return Err(StarkraError::PrefixLength { len, limit: trace_length });
+        }
+        if self.prefix.first().is_some_and(|&n| n != self.start) {
+            return Err(StarkraError::PrefixMismatch { row: 0 });
+        }
+        if len == trace_length && self.prefix.last().is_some_and(|&n| n != self.end) {
+            return Err(StarkraError::PrefixMismatch { row: len - 1 });
+        }
+        if let Some(&(step, _)) = self.waypoints.iter().find(|&&(step, _)| step < len) {
+            return Err(StarkraError::DuplicateWaypoint(step));
+        }
+        Ok(())
+    }
+
+    /// Reject public inputs whose constraints, over a trace laid out as
+    /// `layout`, need a larger blowup factor than `blowup`; winterfell
+    /// would panic building the AIR. Every entry-policy node of the
+    /// largest class raises the `entry` constraint's degree by one, so
+    /// enough of them need a blowup above any `ProofOptions` accepts.
+    pub fn check_blowup(&self, layout: &TraceLayout, blowup: usize) -> Result<(), StarkraError> {
+        let required = StarkraAir::constraint_info_of(layout, self).min_blowup();
+        if required > blowup {
+            return Err(StarkraError::BlowupTooSmall { required, actual: blowup });
+        }
+        Ok(())
+    }
+
+    /// `(row, node)` of every prefix assertion for a trace of
+    /// `trace_length` rows: the prefix rows other than the first and last.
+    fn prefix_rows(&self, trace_length: usize) -> impl Iterator<Item = (usize, BaseElement)> + '_ {
+        let last = trace_length.saturating_sub(1);
+        self.prefix.iter().copied().enumerate().take(trace_length).filter(move |&(row, _)| row != 0 && row != last)
+    }
+
+    /// BLAKE3 hash of [`ToElements::to_elements`], each element as 8
+    /// little-endian bytes: a stable key for caching or deduplicating
+    /// proofs by the statement they prove.
+    pub fn id(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        for e in self.to_elements() {
+            hasher.update(&e.as_int().to_le_bytes());
+        }
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Assertions the step-count claim and bound add on a trace of
+    /// `trace_length` rows: `step` on the first and last row, `valid` where
+    /// the real rows end (see [`valid_drop_row`]), and `budget` on the first
+    /// and last row (`step` on row 0 only once).
+    fn num_step_assertions(&self, trace_length: usize) -> usize {
+        let drop = valid_drop_row(self.num_steps, self.segment_nonces.len(), trace_length).is_some() as usize;
+        drop + match (self.num_steps.is_some(), self.max_steps.is_some()) {
+            (true, true) => 4,
+            (true, false) => 2,
+            (false, true) => 3,
+            (false, false) => 0,
+        }
+    }
+}
+
+/// Hashes the canonical values of [`ToElements::to_elements`], which
+/// `BaseElement` itself does not implement `Hash` for.
+impl Hash for PublicInputs {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for e in self.to_elements() {
+            e.as_int().hash(state);
+        }
+    }
 }
 
 impl ToElements<BaseElement> for PublicInputs {
     fn to_elements(&self) -> Vec<BaseElement> {
-        vec![self.start, self.end, self.nonce]
+        let mut elements = vec![self.start, self.end, self.nonce];
+        elements.push(BaseElement::new(self.segment_nonces.len() as u64));
+        for &(row, nonce) in &self.segment_nonces {
+            elements.push(BaseElement::new(row as u64));
+            elements.push(nonce);
+        }
+        elements.push(BaseElement::new(self.forbidden.len() as u64));
+        elements.extend_from_slice(&self.forbidden);
+        elements.push(BaseElement::new(self.num_steps.is_some() as u64));
+        elements.push(BaseElement::new(self.num_steps.unwrap_or(0) as u64));
+        elements.push(BaseElement::new(self.max_steps.is_some() as u64));
+        elements.push(BaseElement::new(self.max_steps.unwrap_or(0) as u64));
+        elements.push(BaseElement::new(self.neighbor_encoding.tag() as u64));
+        elements.push(BaseElement::new(self.waypoints.len() as u64));
+        for &(step, node) in &self.waypoints {
+            elements.push(BaseElement::new(step as u64));
+            elements.push(node);
+        }
+        // 4-byte limbs, so every element is canonical
+        for digest in [self.cfg_commitment, self.sequence_root] {
+            elements.push(BaseElement::new(digest.is_some() as u64));
+            for limb in digest.unwrap_or_default().chunks(4) {
+                elements.push(BaseElement::new(u32::from_le_bytes(limb.try_into().expect("4-byte limb")) as u64));
+            }
+        }
+        elements.push(BaseElement::new(self.prefix.len() as u64));
+        elements.extend_from_slice(&self.prefix);
+        elements.push(BaseElement::new(self.entry_policies.len() as u64));
+        for &(node, policy) in &self.entry_policies {
+            elements.push(node);
+            elements.push(BaseElement::new(policy.bits() as u64));
+        }
+        elements
     }
 }
 
@@ -29,122 +744,820 @@ pub struct StarkraAir {
     start: BaseElement,
     end: BaseElement,
     nonce: BaseElement,
+    segment_nonces: Vec<(usize, BaseElement)>,
+    forbidden: Vec<BaseElement>,
+    num_steps: Option<usize>,
+    max_steps: Option<usize>,
+    waypoints: Vec<(usize, BaseElement)>,
+    /// `(row, node)` of the prefix assertions
+    prefix: Vec<(usize, BaseElement)>,
+    entry_classes: EntryClasses,
+    layout: TraceLayout,
+}
+
+/// The row after the last real row of a trace of `trace_length` rows
+/// holding `num_steps` real rows and a separator before each of
+/// `segments` further segments, if there is one. `valid` is asserted to
+/// be 0 there: `valid` never steps back to 1 outside a segment start, so
+/// with the `step` assertions the claimed rows are exactly the real ones.
+fn valid_drop_row(num_steps: Option<usize>, segments: usize, trace_length: usize) -> Option<usize> {
+    num_steps.map(|n| n + segments).filter(|&row| row < trace_length)
+}
+
+/// Largest last-row step index allowed by a bound of `max_steps` steps on
+/// a trace of `length` rows. The slack can grow by at most `length - 1`,
+/// so the bound is clamped to the trace; every trace meets the clamped one.
+fn step_bound(max_steps: usize, length: usize) -> u64 {
+    (max_steps.min(length) as u64).saturating_sub(1)
+}
+
+/// Order in which the steps of a path are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// Execution order.
+    #[default]
+    Forward,
+    /// Reverse execution order: the last executed step first.
+    Reverse,
+}
+
+/// Time spent in each phase of a trace build; see
+/// [`StarkraAir::build_trace_profiled`].
+#[cfg(feature = "prover")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildProfile {
+    /// Shadow-stack simulation, plus the nonce, current and stack cells.
+    pub stack: Duration,
+    /// Successor lookups and the neighbor cells.
+    pub neighbors: Duration,
+    /// The valid, call/ret, forbidden_inv, taken, step, budget, entry_inv and segment cells.
+    pub flags: Duration,
+    /// Separator and padding rows.
+    pub padding: Duration,
+}
+
+#[cfg(feature = "prover")]
+impl BuildProfile {
+    pub fn total(&self) -> Duration {
+        self.stack + self.neighbors + self.flags + self.padding
+    }
+}
+
+/// One line per phase, with its share of the total.
+#[cfg(feature = "prover")]
+impl fmt::Display for BuildProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total().as_secs_f64();
+        for (name, d) in [
+            ("stack", self.stack),
+            ("neighbors", self.neighbors),
+            ("flags", self.flags),
+            ("padding", self.padding),
+        ] {
+            let share = if total > 0.0 { 100.0 * d.as_secs_f64() / total } else { 0.0 };
+            writeln!(f, "  {:<10} {:>12.3?} {:>5.1}%", name, d, share)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the time from `start` to `stop` to a phase. With the `profile`
+/// feature off it is zero-sized and both calls compile to nothing.
+#[cfg(feature = "prover")]
+struct PhaseTimer {
+    #[cfg(feature = "profile")]
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "prover")]
+impl PhaseTimer {
+    #[inline(always)]
+    fn start() -> Self {
+        Self {
+            #[cfg(feature = "profile")]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[inline(always)]
+    fn stop(self, _phase: &mut Duration) {
+        #[cfg(feature = "profile")]
+        {
+            *_phase += self.start.elapsed();
+        }
+    }
 }
 
+/// Trace building; only the prover needs it (`prover` feature).
+#[cfg(feature = "prover")]
 impl StarkraAir {
+    /// Lay `path` out as a trace over `cfg`. No validation is done here: the
+    /// trace is built as given, so an invalid path simply yields a trace
+    /// that fails the constraints (useful for inspecting why, see
+    /// `check_trace`). Validate once with `validate_path` or
+    /// `Cfg::is_valid_walk` before building, not on every build.
     pub fn build_trace(path: Vec<Step>, cfg: Cfg, nonce: u32) -> TraceTable<BaseElement> {
-        // columns: nonce, current, stack(top), neighbors..., valid, ret, call
-        let max_succ = cfg.max_successors();
-        let base_nei = 3;
-        let valid_idx = base_nei + max_succ;
-        let ret_idx = valid_idx + 1;
-        let call_idx = ret_idx + 1;
-        let width = call_idx + 1;
-
-        let steps: Vec<Step> = path;
-        let real_len = steps.len();
-        let length = real_len.max(1).next_power_of_two();
+        Self::build_segmented_trace(vec![(path, nonce)], cfg, &[])
+    }
 
-        let mut trace = TraceTable::new(width, length);
+    /// `build_trace` for steps in the given order. For
+    /// [`Direction::Reverse`] the trace walks `cfg.reversed()` (see
+    /// [`reverse_walk`]): its public inputs have start and end swapped, and
+    /// the proof must be verified against `cfg.reversed()`.
+    pub fn build_trace_in(path: Vec<Step>, cfg: Cfg, nonce: u32, direction: Direction) -> TraceTable<BaseElement> {
+        match direction {
+            Direction::Forward => Self::build_trace(path, cfg, nonce),
+            Direction::Reverse => Self::build_trace(reverse_walk(&path), cfg.reversed(), nonce),
+        }
+    }
+
+    /// Like `build_trace`, for a proof that the path never visits a node in
+    /// `forbidden`. A path that does visit one yields a trace that cannot
+    /// satisfy the forbidden-node constraint.
+    pub fn build_trace_avoiding(path: Vec<Step>, cfg: Cfg, nonce: u32, forbidden: &[NodeId]) -> TraceTable<BaseElement> {
+        Self::build_segmented_trace(vec![(path, nonce)], cfg, forbidden)
+    }
 
-        // shadow stack for CALL/RET integrity (stores return addresses)
-        let mut sstack: Vec<u32> = Vec::new();
+    /// Like `build_trace`, for a proof that the path took at most
+    /// `max_steps` steps (see [`PublicInputs::max_steps`]). A longer path
+    /// yields a trace that fails the `budget` assertion on the last row.
+    pub fn build_trace_bounded(path: Vec<Step>, cfg: Cfg, nonce: u32, max_steps: usize) -> TraceTable<BaseElement> {
+        let mut trace = Self::build_trace(path, cfg, nonce);
+        Self::fill_budget(&mut trace, max_steps);
+        trace
+    }
 
+    /// Rewrite the `budget` column of a built trace for a bound of
+    /// `max_steps`: the slack grows by one per row until it reaches the
+    /// headroom left by the last row's step index, and stays there.
+    pub fn fill_budget(trace: &mut TraceTable<BaseElement>, max_steps: usize) {
+        let layout = TraceLayout::from_width(trace.width());
+        let length = trace.length();
+        let bound = step_bound(max_steps, length);
+        let last_step = trace.get(layout.step, length - 1).as_int();
+        // no headroom if the path is over the bound; the trace then fails
+        let headroom = bound.saturating_sub(last_step);
         for r in 0..length {
-            let is_real = r < real_len;
-
-            // Current node for this row
-            let curr = if is_real {
-                steps[r].addrs.get(0).copied().unwrap_or(0)
-            } else {
-                // repeat last real node for padding
-                steps[real_len.saturating_sub(1)]
-                    .addrs
-                    .get(0)
-                    .copied()
-                    .unwrap_or(0)
-            };
-
-            // --- Apply CALL/RET effect to shadow stack (real rows only) ---
-            if is_real {
-                match steps[r].jmp_type {
-                    JmpType::Call => {
-                        // push return address (second addr if present)
-                        let ret_addr = steps[r].addrs.get(1).copied().unwrap_or(0);
-                        sstack.push(ret_addr);
-                    }
-                    JmpType::Ret => {
-                        // pop (empty -> ignore)
-                        let _ = sstack.pop();
-                    }
-                    _ => {}
+            let slack = (r as u64).min(headroom);
+            let step = trace.get(layout.step, r);
+            trace.set(layout.budget, r, step + BaseElement::new(slack));
+        }
+    }
+
+    /// Append the selector columns of [`NeighborEncoding::OneHot`] to a
+    /// built (`Product`) trace: selector `i` is 1 on a real row followed
+    /// by a real row whose `taken` is `i`, and 0 elsewhere. Apply it last,
+    /// after e.g. `fill_budget`, which reads the layout from the width.
+    pub fn add_selectors(trace: TraceTable<BaseElement>) -> TraceTable<BaseElement> {
+        let layout = TraceLayout::from_width(trace.width());
+        let length = trace.length();
+        let mut columns: Vec<Vec<BaseElement>> = (0..trace.width()).map(|c| trace.get_column(c).to_vec()).collect();
+        let mut selectors = vec![vec![BaseElement::ZERO; length]; layout.max_succ];
+        for r in 0..length - 1 {
+            if columns[layout.valid][r] == BaseElement::ONE && columns[layout.valid][r + 1] == BaseElement::ONE {
+                let slot = columns[layout.taken][r].as_int() as usize;
+                if let Some(column) = selectors.get_mut(slot) {
+                    column[r] = BaseElement::ONE;
                 }
             }
-            // top of stack after this step
-            let top = sstack.last().copied().unwrap_or(0);
-
-            // [0] nonce
-            trace.set(0, r, BaseElement::new(nonce as u64));
-            // [1] current
-            trace.set(1, r, BaseElement::new(curr as u64));
-            // [2] stack (shadow stack top AFTER this step)
-            trace.set(2, r, BaseElement::new(top as u64));
-
-            // neighbors: successors(curr) for real rows, else zeros
-            let succ = if is_real { cfg.successors(curr) } else { &[][..] };
-            for i in 0..max_succ {
-                let val = if i < succ.len() {
-                    BaseElement::new(succ[i] as u64)
-                } else {
-                    BaseElement::ZERO
-                };
-                trace.set(base_nei + i, r, val);
+        }
+        columns.extend(selectors);
+        TraceTable::init(columns)
+    }
+
+    /// `build_trace`, refusing with `TraceTooLong` (before allocating) a
+    /// path whose padded trace would exceed `max_rows` rows. `None` is no
+    /// limit.
+    pub fn build_trace_limited(
+        path: Vec<Step>,
+        cfg: Cfg,
+        nonce: u32,
+        max_rows: Option<usize>,
+    ) -> Result<TraceTable<BaseElement>, StarkraError> {
+        Self::build_segmented_trace_limited(vec![(path, nonce)], cfg, &[], max_rows)
+    }
+
+    /// Build one trace holding several independent sessions, each with its
+    /// own nonce. Segments are laid out back to back, separated by a single
+    /// `valid = 0` row, and the whole trace is padded to a power of two.
+    /// The shadow stack starts empty in every segment.
+    pub fn build_segmented_trace(
+        segments: Vec<(Vec<Step>, u32)>,
+        cfg: Cfg,
+        forbidden: &[NodeId],
+    ) -> TraceTable<BaseElement> {
+        Self::build_segmented_trace_limited(segments, cfg, forbidden, None).expect("no row limit")
+    }
+
+    /// `build_segmented_trace` with the row limit of `build_trace_limited`.
+    pub fn build_segmented_trace_limited(
+        segments: Vec<(Vec<Step>, u32)>,
+        cfg: Cfg,
+        forbidden: &[NodeId],
+        max_rows: Option<usize>,
+    ) -> Result<TraceTable<BaseElement>, StarkraError> {
+        Self::build_segments(segments, cfg, forbidden, max_rows, 1, &mut BuildProfile::default())
+    }
+
+    /// `build_trace_limited`, also returning how long each phase of the
+    /// build took. The phases are only timed with the `profile` feature;
+    /// without it every duration is zero and the build costs the same as
+    /// `build_trace_limited`.
+    pub fn build_trace_profiled(
+        path: Vec<Step>,
+        cfg: Cfg,
+        nonce: u32,
+        max_rows: Option<usize>,
+    ) -> Result<(TraceTable<BaseElement>, BuildProfile), StarkraError> {
+        let mut profile = BuildProfile::default();
+        let trace = Self::build_segments(vec![(path, nonce)], cfg, &[], max_rows, 1, &mut profile)?;
+        Ok((trace, profile))
+    }
+
+    /// `build_trace`, padded to at least `min_rows` rows (then to a power
+    /// of two), so traces of different paths can be laid side by side.
+    pub(crate) fn build_trace_min_rows(path: Vec<Step>, cfg: Cfg, nonce: u32, min_rows: usize) -> TraceTable<BaseElement> {
+        Self::build_segments(vec![(path, nonce)], cfg, &[], None, min_rows, &mut BuildProfile::default())
+            .expect("no row limit")
+    }
+
+    fn build_segments(
+        segments: Vec<(Vec<Step>, u32)>,
+        cfg: Cfg,
+        forbidden: &[NodeId],
+        max_rows: Option<usize>,
+        min_rows: usize,
+        profile: &mut BuildProfile,
+    ) -> Result<TraceTable<BaseElement>, StarkraError> {
+        let layout = TraceLayout::from(&cfg);
+        let max_succ = layout.max_succ;
+        let base_nei = layout.neighbors_start;
+        let valid_idx = layout.valid;
+        let ret_idx = layout.ret;
+        let call_idx = layout.call;
+        let width = layout.width;
+        let entry = EntryClasses::of(&cfg);
+
+        let real_len: usize = segments.iter().map(|(steps, _)| steps.len()).sum::<usize>()
+            + segments.len().saturating_sub(1);
+        // winterfell rejects traces shorter than `MIN_TRACE_LENGTH`, so
+        // paths of a few steps (e.g. a single node) are padded up to it
+        let length = real_len.max(min_rows).max(TraceInfo::MIN_TRACE_LENGTH).next_power_of_two();
+        if let Some(max) = max_rows
+            && length > max
+        {
+            return Err(StarkraError::TraceTooLong { real_len, max });
+        }
+
+        let mut trace = TraceTable::new(width, length);
+
+        // (current, stack top, nonce, step, segment) repeated on separator/padding rows
+        let mut last = (BaseElement::ZERO, BaseElement::ZERO, 0u32, BaseElement::ZERO, BaseElement::ZERO);
+        let mut r = 0;
+        let mut real_rows = 0u64;
+
+        for (seg, (steps, nonce)) in segments.iter().enumerate() {
+            if seg > 0 {
+                let t = PhaseTimer::start();
+                Self::write_padding_row(&mut trace, r, last);
+                t.stop(&mut profile.padding);
+                r += 1;
             }
 
-            // [valid]
-            trace.set(
-                valid_idx,
-                r,
-                if is_real { BaseElement::ONE } else { BaseElement::ZERO },
-            );
+            // shadow stack for CALL/RET integrity
+            let mut sstack = ShadowStack::new();
+
+            for (i, step) in steps.iter().enumerate() {
+                let t = PhaseTimer::start();
+                // Current node for this row ("no node" for a malformed step)
+                let curr = encode_opt(step.addrs.first().copied());
+
+                // --- Apply CALL/RET effect to shadow stack ---
+                sstack.apply(i, step);
+                // top of stack after this step
+                let top = encode_opt(sstack.top());
+
+                trace.set(layout.nonce, r, BaseElement::new(*nonce as u64));
+                trace.set(layout.current, r, curr);
+                // shadow stack top AFTER this step
+                trace.set(layout.stack, r, top);
+                t.stop(&mut profile.stack);
+
+                let t = PhaseTimer::start();
+                // neighbors: successors(curr)
+                let succ = step.addrs.first().map(|&n| cfg.successors(n)).unwrap_or(&[]);
+                for i in 0..max_succ {
+                    let val = if i < succ.len() {
+                        encode_node(succ[i])
+                    } else {
+                        BaseElement::ZERO
+                    };
+                    trace.set(base_nei + i, r, val);
+                }
+                t.stop(&mut profile.neighbors);
+
+                let t = PhaseTimer::start();
+                // [valid]
+                trace.set(valid_idx, r, BaseElement::ONE);
 
-            // [ret], [call] flags
-            let (ret_flag, call_flag) = if is_real {
-                match steps[r].jmp_type {
+                // [ret], [call] flags
+                let (ret_flag, call_flag) = match step.jmp_type {
                     JmpType::Ret => (BaseElement::ONE, BaseElement::ZERO),
                     JmpType::Call => (BaseElement::ZERO, BaseElement::ONE),
                     _ => (BaseElement::ZERO, BaseElement::ZERO),
-                }
-            } else {
-                (BaseElement::ZERO, BaseElement::ZERO)
-            };
-            trace.set(ret_idx, r, ret_flag);
-            trace.set(call_idx, r, call_flag);
+                };
+                trace.set(ret_idx, r, ret_flag);
+                trace.set(call_idx, r, call_flag);
+
+                // [forbidden_inv]: zero (unsatisfiable) if curr is forbidden
+                let prod = forbidden.iter().fold(BaseElement::ONE, |acc, &f| {
+                    acc * (curr - encode_node(f))
+                });
+                trace.set(layout.forbidden_inv, r, prod.inv());
+                // [entry_inv]: zero (unsatisfiable) if the entry policy rules the step out
+                trace.set(layout.entry_inv, r, entry.value(curr, call_flag, ret_flag).inv());
+
+                // [taken]: first slot holding the next node (0 on the last step)
+                let next = steps.get(i + 1).and_then(|s| s.addrs.first());
+                let taken = step
+                    .addrs
+                    .first()
+                    .zip(next)
+                    .and_then(|(&c, &n)| matched_successor_slot(&cfg, c, n))
+                    .unwrap_or(0);
+                trace.set(layout.taken, r, BaseElement::new(taken as u64));
+                let step = BaseElement::new(real_rows);
+                trace.set(layout.step, r, step);
+                // no slack unless a bound is filled in (`fill_budget`)
+                trace.set(layout.budget, r, step);
+                let segment = BaseElement::new(seg as u64);
+                trace.set(layout.segment, r, segment);
+                real_rows += 1;
+                t.stop(&mut profile.flags);
+
+                last = (curr, top, *nonce, step, segment);
+                r += 1;
+            }
         }
 
-        trace
+        // repeat last real node for padding
+        let t = PhaseTimer::start();
+        while r < length {
+            Self::write_padding_row(&mut trace, r, last);
+            r += 1;
+        }
+        t.stop(&mut profile.padding);
+
+        Ok(trace)
     }
 
-    pub fn transition_check<E: FieldElement>(current: &[E], next: &[E]) -> E {
-        let width = current.len();
-        debug_assert!(width >= 6, "expected: nonce, current, stack, neighbors..., valid, ret, call");
+    /// A `valid = 0` row: repeats `(current, stack, nonce, step, segment)`,
+    /// zero neighbors and flags, and `budget` equal to `step`.
+    fn write_padding_row(
+        trace: &mut TraceTable<BaseElement>,
+        r: usize,
+        (curr, top, nonce, step, segment): (BaseElement, BaseElement, u32, BaseElement, BaseElement),
+    ) {
+        let layout = TraceLayout::from_width(trace.width());
+        trace.set(layout.nonce, r, BaseElement::new(nonce as u64));
+        trace.set(layout.current, r, curr);
+        trace.set(layout.stack, r, top);
+        for c in layout.neighbors_start..layout.width {
+            trace.set(c, r, BaseElement::ZERO);
+        }
+        trace.set(layout.step, r, step);
+        trace.set(layout.budget, r, step);
+        trace.set(layout.segment, r, segment);
+    }
 
-        // indices per layout
-        let neighbors_start = 3;
-        let valid_idx = width - 3; // [valid]
-        // let _ret_idx = width - 2;
-        // let _call_idx = width - 1;
+}
+
+/// Builds a single-session trace one step at a time, for executions
+/// observed online. Each `push_step` fills the step's row and the
+/// previous row's `taken` cell, which depends on the step that follows; a
+/// [`TraceBuilder::snapshot`] or [`TraceBuilder::finish`] pads the rows so
+/// far to a power of two. Either gives exactly the trace `build_trace`
+/// (or, with forbidden nodes, `build_trace_avoiding`) builds from the same
+/// steps, so a prefix can be proven while the execution goes on.
+#[cfg(feature = "prover")]
+#[derive(Debug, Clone)]
+pub struct TraceBuilder {
+    cfg: Cfg,
+    layout: TraceLayout,
+    nonce: u32,
+    forbidden: Vec<NodeId>,
+    entry: EntryClasses,
+    /// one vector per column, one entry per real row
+    columns: Vec<Vec<BaseElement>>,
+    sstack: ShadowStack,
+    /// first address of the last step pushed
+    last_node: Option<NodeId>,
+}
+
+#[cfg(feature = "prover")]
+impl TraceBuilder {
+    pub fn new(cfg: Cfg, nonce: u32) -> Self {
+        let layout = TraceLayout::from(&cfg);
+        Self {
+            entry: EntryClasses::of(&cfg),
+            cfg,
+            layout,
+            nonce,
+            forbidden: Vec::new(),
+            columns: vec![Vec::new(); layout.width],
+            sstack: ShadowStack::new(),
+            last_node: None,
+        }
+    }
+
+    /// Fill `forbidden_inv` for these forbidden nodes, as
+    /// `build_trace_avoiding` does. Set it before the first step.
+    pub fn with_forbidden(mut self, forbidden: Vec<NodeId>) -> Self {
+        self.forbidden = forbidden;
+        self
+    }
+
+    /// Number of steps pushed so far.
+    pub fn len(&self) -> usize {
+        self.columns[self.layout.current].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append `step` as the next real row. Like `build_trace`, this does
+    /// no validation: an invalid step gives a row that fails the
+    /// constraints.
+    pub fn push_step(&mut self, step: Step) {
+        let l = self.layout;
+        let i = self.len();
+        let node = step.addrs.first().copied();
+        if i > 0 {
+            let taken = self.last_node.zip(node).and_then(|(c, n)| matched_successor_slot(&self.cfg, c, n)).unwrap_or(0);
+            self.columns[l.taken][i - 1] = BaseElement::new(taken as u64);
+        }
+
+        let curr = encode_opt(node);
+        self.sstack.apply(i, &step);
+        let top = encode_opt(self.sstack.top());
+        let succ = node.map(|n| self.cfg.successors(n)).unwrap_or(&[]);
+        let (ret, call) = match step.jmp_type {
+            JmpType::Ret => (BaseElement::ONE, BaseElement::ZERO),
+            JmpType::Call => (BaseElement::ZERO, BaseElement::ONE),
+            _ => (BaseElement::ZERO, BaseElement::ZERO),
+        };
+        let prod = self.forbidden.iter().fold(BaseElement::ONE, |acc, &f| acc * (curr - encode_node(f)));
+        let entry = self.entry.value(curr, call, ret);
+        let index = BaseElement::new(i as u64);
 
-        let next_jmp = next[1];
+        let c = &mut self.columns;
+        c[l.nonce].push(BaseElement::new(self.nonce as u64));
+        c[l.current].push(curr);
+        c[l.stack].push(top);
+        for (slot, col) in l.neighbors().enumerate() {
+            c[col].push(succ.get(slot).map(|&n| encode_node(n)).unwrap_or(BaseElement::ZERO));
+        }
+        c[l.valid].push(BaseElement::ONE);
+        c[l.ret].push(ret);
+        c[l.call].push(call);
+        c[l.forbidden_inv].push(prod.inv());
+        c[l.entry_inv].push(entry.inv());
+        // filled in by the next step; 0 if this one stays last
+        c[l.taken].push(BaseElement::ZERO);
+        c[l.step].push(index);
+        c[l.budget].push(index);
+        // a single session: every row is in segment 0
+        c[l.segment].push(BaseElement::ZERO);
+        self.last_node = node;
+    }
+
+    /// The trace of the steps so far, padded to a power of two.
+    pub fn snapshot(&self) -> TraceTable<BaseElement> {
+        self.clone().finish()
+    }
+
+    /// The trace of every pushed step, padded to the next power of two
+    /// (at least winterfell's minimum trace length) with rows repeating the last real row's node, stack, nonce and step.
+    pub fn finish(self) -> TraceTable<BaseElement> {
+        let l = self.layout;
+        let real_len = self.len();
+        let length = real_len.max(TraceInfo::MIN_TRACE_LENGTH).next_power_of_two();
+        let mut columns = self.columns;
+        let last = |c: usize| columns[c].last().copied().unwrap_or(BaseElement::ZERO);
+        // an empty path pads with nonce 0, as `build_trace` does
+        let repeated: Vec<(usize, BaseElement)> =
+            [l.nonce, l.current, l.stack, l.step, l.budget].into_iter().map(|c| (c, last(c))).collect();
+        for column in columns.iter_mut() {
+            column.resize(length, BaseElement::ZERO);
+        }
+        for (c, value) in repeated {
+            columns[c][real_len..].fill(value);
+        }
+        TraceTable::init(columns)
+    }
+}
 
-        // product over neighbors: ∏ (next[1] - current[neighbor_i])
+impl StarkraAir {
+    /// Constraint shape for a single-session trace with no forbidden nodes.
+    pub fn constraint_info(max_succ: usize) -> ConstraintInfo {
+        Self::constraint_info_for(max_succ, 0, 0)
+    }
+
+    /// Constraint shape for `num_segment_nonces` extra segments and
+    /// `num_forbidden` forbidden nodes. A step-count claim adds two or
+    /// three assertions on top of `num_assertions`, a step bound two or three,
+    /// and every waypoint one.
+    pub fn constraint_info_for(max_succ: usize, num_segment_nonces: usize, num_forbidden: usize) -> ConstraintInfo {
+        Self::constraint_info_with(max_succ, num_segment_nonces, num_forbidden, NeighborEncoding::Product)
+    }
+
+    /// `constraint_info_for` under `encoding`. Entry policies raise the
+    /// `entry` constraint's degree (see [`PublicInputs::entry_policies`]);
+    /// [`StarkraAir::constraint_info_of`] accounts for them.
+    pub fn constraint_info_with(
+        max_succ: usize,
+        num_segment_nonces: usize,
+        num_forbidden: usize,
+        encoding: NeighborEncoding,
+    ) -> ConstraintInfo {
+        // with a single segment `segment` is constant and the nonce
+        // constraint collapses to `next[0] - curr[0]`
+        let nonce_degree = if num_segment_nonces == 0 { 1 } else { 2 };
+        // an array, so a degree list out of step with the labels does not
+        // compile
+        // one-hot: Σ s_i (next - neighbor_i), Σ s_i - valid * next_valid
+        // and taken - Σ i s_i
+        let (neighbors, range, edge) = match encoding {
+            NeighborEncoding::Product => (max_succ + 2, max_succ + 2, max_succ + 2),
+            NeighborEncoding::OneHot => (2, 2, 1),
+        };
+        let degrees: [usize; NUM_CONSTRAINTS] = [
+            nonce_degree,
+            // ∏ over neighbors, times valid on both rows
+            neighbors,
+            2,
+            num_forbidden + 2,
+            // ∏ (taken - i), times valid on both rows
+            range,
+            // selector of degree max_succ - 1, times (next - neighbor) and valid twice
+            edge,
+            // next_step - step - next_valid
+            1,
+            // call * ret, then flag * (flag - 1) for call, ret and valid
+            2,
+            2,
+            2,
+            2,
+            // slack increment s times (s - 1)
+            2,
+            // (next - current) times (1 - next_valid)
+            2,
+            // stack difference times (1 - next_call - next_ret) and valid
+            3,
+            // next_valid (1 - valid), less the segment increment
+            2,
+            // without entry policies; see `EntryClasses::degree`
+            3,
+        ];
+        let mut degrees = degrees.to_vec();
+        if encoding == NeighborEncoding::OneHot {
+            // s_i (taken - i)
+            degrees.extend(core::iter::repeat_n(2, max_succ));
+        }
+        // nonce, start, end, `valid` on row 0 and `segment` on the first
+        // and last rows, then per further segment its nonce and the `valid`
+        // step from 0 to 1 into its first row
+        ConstraintInfo {
+            degrees,
+            num_assertions: 6 + 3 * num_segment_nonces,
+        }
+    }
+
+    /// Constraint shape of the AIR for `pub_inputs` over a trace laid out
+    /// as `layout`, entry policies included.
+    pub fn constraint_info_of(layout: &TraceLayout, pub_inputs: &PublicInputs) -> ConstraintInfo {
+        let mut info = Self::constraint_info_with(
+            layout.max_succ,
+            pub_inputs.segment_nonces.len(),
+            pub_inputs.forbidden.len(),
+            layout.encoding,
+        );
+        let classes = EntryClasses::new(pub_inputs.entry_policies.iter().map(|(n, p)| (*n, p)));
+        info.degrees[ENTRY_CONSTRAINT] = classes.degree();
+        info
+    }
+
+    /// Evaluate every transition constraint and assertion of the AIR for
+    /// `pub_inputs` directly on `trace`, without committing to anything.
+    /// Much faster than `prove`, and names the first failing row.
+    pub fn check_trace(trace: &TraceTable<BaseElement>, pub_inputs: &PublicInputs) -> Result<(), StarkraError> {
+        let air = Self::for_checking(trace, pub_inputs)?;
+        let length = trace.length();
+        for row in 0..length - 1 {
+            let result = air.evaluate_rows(trace, row);
+            if let Some(i) = result.iter().position(|&v| v != BaseElement::ZERO) {
+                return Err(StarkraError::Trace(format!(
+                    "transition constraint {} ({}) fails between rows {} and {}",
+                    i,
+                    constraint_label(i),
+                    row,
+                    row + 1
+                )));
+            }
+        }
+
+        let mut cells = BTreeMap::new();
+        for assertion in air.get_assertions() {
+            assertion.apply(length, |row, _| {
+                cells.insert((assertion.column(), row), trace.get(assertion.column(), row));
+            });
+        }
+        if let Some(r) = evaluate_assertions(&air, &cells).iter().find(|r| !r.passed()) {
+            return Err(StarkraError::Trace(format!(
+                "assertion on column {} ({}) fails at row {}: expected {}, found {}",
+                r.column,
+                r.label,
+                r.row,
+                r.expected,
+                r.actual.expect("read from the trace")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Every transition constraint evaluated between `row` and `row + 1`,
+    /// labelled with [`constraint_label`]. Zero means satisfied.
+    pub fn explain_row(
+        trace: &TraceTable<BaseElement>,
+        pub_inputs: &PublicInputs,
+        row: usize,
+    ) -> Result<Vec<(&'static str, BaseElement)>, StarkraError> {
+        if row + 1 >= trace.length() {
+            return Err(StarkraError::Trace(format!(
+                "row {} has no next row (trace length {})",
+                row,
+                trace.length()
+            )));
+        }
+        let air = Self::for_checking(trace, pub_inputs)?;
+        Ok(air.evaluate_rows(trace, row).into_iter().enumerate().map(|(i, v)| (constraint_label(i), v)).collect())
+    }
+
+    /// Check that every row's neighbor slots are in canonical order: node
+    /// values ascending (equal values allowed, for duplicate edges), then
+    /// zeros. The builder fills the slots in successor order, so traces of
+    /// a CFG that went through [`Cfg::canonicalize`] pass; use this to
+    /// confirm a trace was built that way.
+    ///
+    /// The order is deliberately not a transition constraint. Comparing
+    /// two field elements in-circuit takes a range check on their
+    /// difference, i.e. a bit decomposition: about 32 extra columns and as
+    /// many constraints per adjacent slot pair, more than the whole trace
+    /// for `max_succ = 3`. It would also buy nothing: the AIR does not
+    /// bind the neighbor columns to the CFG (see
+    /// [`PublicInputs::cfg_commitment`]), so a prover who may write any
+    /// values into them gains nothing from permuting them, and `taken`
+    /// already names the slot used whatever the order. Canonical order is
+    /// for reproducibility (identical traces and fingerprints for the same
+    /// edge set), not soundness.
+    pub fn check_neighbor_order(trace: &TraceTable<BaseElement>) -> Result<(), StarkraError> {
+        let layout = TraceLayout::from_width(trace.width());
+        for row in 0..trace.length() {
+            let slots: Vec<u64> = layout.neighbors().map(|c| trace.get(c, row).as_int()).collect();
+            let used = slots.iter().take_while(|&&v| v != 0).count();
+            if let Some(i) = (1..used).find(|&i| slots[i - 1] > slots[i]) {
+                return Err(StarkraError::Trace(format!(
+                    "row {}: neighbor slot {} holds {} after {}",
+                    row,
+                    i,
+                    slots[i],
+                    slots[i - 1]
+                )));
+            }
+            if let Some(i) = (used..slots.len()).find(|&i| slots[i] != 0) {
+                return Err(StarkraError::Trace(format!("row {}: neighbor slot {} is used after an empty slot", row, i)));
+            }
+        }
+        Ok(())
+    }
+
+    /// An AIR over `trace`'s shape for evaluating constraints directly.
+    fn for_checking(trace: &TraceTable<BaseElement>, pub_inputs: &PublicInputs) -> Result<Self, StarkraError> {
+        let length = trace.length();
+        if length < TraceInfo::MIN_TRACE_LENGTH {
+            return Err(StarkraError::Trace(format!(
+                "trace length {} is below the minimum {}",
+                length,
+                TraceInfo::MIN_TRACE_LENGTH
+            )));
+        }
+        let layout = TraceLayout::from_width_with(trace.width(), pub_inputs.neighbor_encoding);
+        let info = Self::constraint_info_of(&layout, pub_inputs);
+        // only the blowup matters to `AirContext::new`; nothing is committed
+        let options = ProofOptions::new(
+            1,
+            info.min_blowup(),
+            0,
+            FieldExtension::None,
+            2,
+            7,
+            BatchingMethod::Linear,
+            BatchingMethod::Linear,
+        );
+        Ok(Self::new(TraceInfo::new(trace.width(), length), pub_inputs.clone(), options))
+    }
+
+    fn evaluate_rows(&self, trace: &TraceTable<BaseElement>, row: usize) -> Vec<BaseElement> {
+        let mut current = vec![BaseElement::ZERO; trace.width()];
+        let mut next = vec![BaseElement::ZERO; trace.width()];
+        trace.read_row_into(row, &mut current);
+        trace.read_row_into(row + 1, &mut next);
+        let mut result = vec![BaseElement::ZERO; self.context.num_main_transition_constraints()];
+        self.evaluate_transition(&EvaluationFrame::from_rows(current, next), &[], &mut result);
+        result
+    }
+
+    /// `next[current]` is one of `current`'s neighbors on every pair of real
+    /// rows. A call row holds its target as `current`, so this is also what
+    /// keeps call targets among the caller's successors.
+    ///
+    /// Every column is looked up in `layout`, the same one the trace
+    /// builder writes with, so the two cannot disagree about where the
+    /// neighbor slots end.
+    ///
+    /// With no neighbor slots (a CFG without edges, `max_succ = 0`) the
+    /// product is empty and the constraint is `valid * next_valid` itself:
+    /// no pair of real rows satisfies it, so only a single-node path is
+    /// provable, as it should be. `taken_check`'s range constraint and the
+    /// one-hot selectors likewise have no slot to satisfy.
+    pub fn transition_check<E: FieldElement>(current: &[E], next: &[E], layout: &TraceLayout) -> E {
+        let next_jmp = next[layout.current];
+
+        // product over neighbors: ∏ (next[current] - current[neighbor_i])
         let mut acc = E::ONE;
-        for c in neighbors_start..valid_idx {
-            acc *= next_jmp - current[c];
+        for &nei in &current[layout.neighbors()] {
+            acc *= next_jmp - nei;
+        }
+
+        // only between two real rows
+        acc * current[layout.valid] * next[layout.valid]
+    }
+
+    /// `(range, edge)` constraints on the `taken` column between two real
+    /// rows: `range` forces `taken ∈ {0, .., max_succ - 1}`, and `edge`
+    /// forces the neighbor in slot `taken` to equal `next[current]`.
+    ///
+    /// `edge` is `Σ_i ∏_{j≠i} (taken - j) · (next[current] - neighbor_i)`:
+    /// for `taken` in range every term but `i = taken` vanishes, and that
+    /// term's coefficient is a non-zero constant, so no normalisation (and
+    /// no inverse) is needed.
+    pub fn taken_check<E: FieldElement>(current: &[E], next: &[E], layout: &TraceLayout) -> (E, E) {
+        let taken = current[layout.taken];
+        let next_jmp = next[layout.current];
+        let gate = current[layout.valid] * next[layout.valid];
+
+        let mut range = E::ONE;
+        let mut edge = E::ZERO;
+        for (i, &nei) in current[layout.neighbors()].iter().enumerate() {
+            range *= taken - E::from(i as u32);
+            let mut selector = E::ONE;
+            for j in (0..layout.max_succ).filter(|&j| j != i) {
+                selector *= taken - E::from(j as u32);
+            }
+            edge += selector * (next_jmp - nei);
         }
+        (range * gate, edge * gate)
+    }
+
+    /// `(neighbors, range, edge)` of a `OneHot` layout, in place of
+    /// `transition_check` and `taken_check`, plus `s_i · (taken - i)` for
+    /// every selector `s_i` into `slots`.
+    ///
+    /// The slot constraints leave only `s_taken` non-zero (all of them for
+    /// `taken` out of range); `range` makes the selectors sum to 1 between
+    /// two real rows and to 0 otherwise, so there `s_taken = 1` and `taken`
+    /// is in range. `neighbors` is then `next[current] - neighbor_taken`,
+    /// and `edge` ties `taken` to the selectors' index (so `taken` is 0
+    /// where no step follows, as the builder writes it). Every constraint
+    /// has degree at most 2, whatever `max_succ`.
+    pub fn selector_check<E: FieldElement>(current: &[E], next: &[E], layout: &TraceLayout, slots: &mut [E]) -> (E, E, E) {
+        let taken = current[layout.taken];
+        let next_jmp = next[layout.current];
+        let gate = current[layout.valid] * next[layout.valid];
 
-        // multiply by is_valid (current row)
-        acc * current[valid_idx] * next[valid_idx]
+        let mut neighbors = E::ZERO;
+        let mut sum = E::ZERO;
+        let mut index = E::ZERO;
+        let selectors = &current[layout.selectors()];
+        for (i, (&s, &nei)) in selectors.iter().zip(&current[layout.neighbors()]).enumerate() {
+            let slot = E::from(i as u32);
+            neighbors += s * (next_jmp - nei);
+            sum += s;
+            index += s * slot;
+            slots[i] = s * (taken - slot);
+        }
+        (neighbors, sum - gate, taken - index)
     }
 }
 
@@ -156,14 +1569,15 @@ impl Air for StarkraAir {
         pub_inputs: Self::PublicInputs,
         options: winterfell::ProofOptions,
     ) -> Self {
-        let transition_degree_constraint = trace_info.width() - 4;
-        let degrees = vec![
-            TransitionConstraintDegree::new(1),
-            TransitionConstraintDegree::new(transition_degree_constraint),
-            TransitionConstraintDegree::new(2),
-        ];
-
-        let num_assertions = 3;
+        let layout = TraceLayout::from_width_with(trace_info.width(), pub_inputs.neighbor_encoding);
+        let info = Self::constraint_info_of(&layout, &pub_inputs);
+        let degrees = info.degrees.iter().map(|&d| TransitionConstraintDegree::new(d)).collect();
+        let prefix: Vec<_> = pub_inputs.prefix_rows(trace_info.length()).collect();
+        let num_assertions =
+            info.num_assertions
+            + pub_inputs.num_step_assertions(trace_info.length())
+            + pub_inputs.waypoints.len()
+            + prefix.len();
 
         let context = AirContext::new(trace_info, degrees, num_assertions, options);
 
@@ -172,6 +1586,14 @@ impl Air for StarkraAir {
             start: pub_inputs.start,
             end: pub_inputs.end,
             nonce: pub_inputs.nonce,
+            segment_nonces: pub_inputs.segment_nonces,
+            forbidden: pub_inputs.forbidden,
+            num_steps: pub_inputs.num_steps,
+            max_steps: pub_inputs.max_steps,
+            waypoints: pub_inputs.waypoints,
+            prefix,
+            entry_classes: EntryClasses::new(pub_inputs.entry_policies.iter().map(|(n, p)| (*n, p))),
+            layout,
         }
     }
 
@@ -182,22 +1604,215 @@ impl Air for StarkraAir {
     fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
         &self,
         frame: &winterfell::EvaluationFrame<E>,
-        periodic_values: &[E],
+        _periodic_values: &[E],
         result: &mut [E],
     ) {
-        // Single-column trace example:
-        let curr = frame.current();
-        let next = frame.next();
-        let length = curr.len();
-        result[0] = curr[0] - next[0]; //Check nonce
-        result[1] = Self::transition_check(curr, next);
-        result[2] = (curr[2] - next[1])*next[length-2];
+        self.evaluate_slices(frame.current(), frame.next(), result);
     }
 
     fn get_assertions(&self) -> Vec<winterfell::Assertion<Self::BaseField>> {
+        let assertions = self.assertions();
+        assert_eq!(
+            assertions.len(),
+            self.context.num_assertions(),
+            "get_assertions is out of step with the assertion count given to AirContext"
+        );
+        assertions
+    }
+}
+
+impl StarkraAir {
+    pub fn layout(&self) -> &TraceLayout {
+        &self.layout
+    }
+
+    /// The transition constraints between rows `curr` and `next`, which
+    /// may be slices of a wider frame laid out as `self.layout`.
+    pub(crate) fn evaluate_slices<E: FieldElement<BaseField = BaseElement>>(
+        &self,
+        curr: &[E],
+        next: &[E],
+        result: &mut [E],
+    ) {
+        let l = &self.layout;
+        let valid = l.valid;
+        // nonce only changes into the first row of a segment, where
+        // `segment` steps up (see the `segment` constraint); separator and
+        // padding rows keep the previous segment's nonce
+        let nonce = (next[l.nonce] - curr[l.nonce]) * (E::ONE - (next[l.segment] - curr[l.segment]));
+        let (head, slots) = result.split_at_mut(NUM_CONSTRAINTS);
+        let (neighbors, range, edge) = match l.encoding {
+            NeighborEncoding::Product => {
+                let (range, edge) = Self::taken_check(curr, next, l);
+                (Self::transition_check(curr, next, l), range, edge)
+            }
+            NeighborEncoding::OneHot => Self::selector_check(curr, next, l, slots),
+        };
+        let stack = (curr[l.stack] - next[l.current]) * next[l.ret];
+
+        // current ∉ forbidden: ∏ (current - f) has an inverse on real rows;
+        // the last row is never `curr`, see `check_forbidden_endpoints`
+        let prod = self
+            .forbidden
+            .iter()
+            .fold(E::ONE, |acc, &f| acc * (curr[l.current] - E::from(f + BaseElement::ONE)));
+        let forbidden = (prod * curr[l.forbidden_inv] - E::ONE) * curr[valid];
+
+        // the step counter goes up by one into a real row and holds
+        // into a `valid = 0` row, so on the last row it counts every real
+        // row after row 0
+        let step = next[l.step] - curr[l.step] - next[valid];
+
+        // a row is a call, a ret, or neither, and each flag is 0 or 1.
+        // Checked on the next row: the first row's `call` and `ret` are
+        // never read, and its `valid` only scales the constraints it gates,
+        // which only 0 turns off
+        let call_ret = next[l.call] * next[l.ret];
+        let call_bool = next[l.call] * (next[l.call] - E::ONE);
+        let ret_bool = next[l.ret] * (next[l.ret] - E::ONE);
+        let valid_bool = next[valid] * (next[valid] - E::ONE);
+
+        // the slack `budget - step` grows by 0 or 1
+        let slack = (next[l.budget] - next[l.step]) - (curr[l.budget] - curr[l.step]);
+        let budget = slack * (slack - E::ONE);
+
+        // a `valid = 0` row repeats the current node, so padding carries
+        // the `end` assertion back to the last real row: the path ends at
+        // `end`, whatever row its last step is on
+        let padding = (next[l.current] - curr[l.current]) * (E::ONE - next[valid]);
+
+        // the stack top only moves on a call or ret row: `stack` holds the
+        // top after each row's step, so a step into a jump (or tail call)
+        // row keeps it. `1 - call - ret` is that row's jump selector only
+        // because `call_ret` and the flag booleanity constraints hold on the
+        // same row; `call = ret = 1/2` would zero it on a jump. Not across a
+        // segment boundary, where the next segment starts on an empty stack.
+        let stack_hold =
+            (next[l.stack] - curr[l.stack]) * (E::ONE - next[l.call] - next[l.ret]) * curr[valid];
+
+        // `valid` only steps from 0 back to 1 where `segment` counts a new
+        // segment, and `segment` counts nothing else. Its first and last
+        // rows are asserted, so with `valid` 1 on row 0 the real rows of
+        // each segment are contiguous and nothing between them is hidden
+        // by a `valid = 0` row
+        let segment = next[valid] * (E::ONE - curr[valid]) - (next[l.segment] - curr[l.segment]);
+
+        // a step into an entry-policy node is of a kind its policy allows:
+        // the next row's `q` has an inverse (see `EntryClasses::value`)
+        let q = self.entry_classes.value(next[l.current], next[l.call], next[l.ret]);
+        let entry = (q * next[l.entry_inv] - E::ONE) * curr[valid] * next[valid];
+
+        // in `CONSTRAINT_LABELS` order, before any selector constraints; a
+        // missing or extra constraint does not compile
+        let values: [E; NUM_CONSTRAINTS] = [
+            nonce, neighbors, stack, forbidden, range, edge, step, call_ret, call_bool, ret_bool, valid_bool, budget,
+            padding, stack_hold, segment, entry,
+        ];
+        head.copy_from_slice(&values);
+    }
+
+    /// Every assertion of `get_assertions`, without the count check.
+    pub(crate) fn assertions(&self) -> Vec<Assertion<BaseElement>> {
         let last = self.trace_length() - 1;
-        vec![Assertion::single(0, 0, self.nonce),
-             Assertion::single(1, 0, self.start),
-            Assertion::single(1, last, self.end)]
+        let l = &self.layout;
+        let mut assertions = vec![
+            Assertion::single(l.nonce, 0, self.nonce),
+            Assertion::single(l.current, 0, self.start + BaseElement::ONE),
+            Assertion::single(l.current, last, self.end + BaseElement::ONE),
+            Assertion::single(l.valid, 0, BaseElement::ONE),
+            Assertion::single(l.segment, 0, BaseElement::ZERO),
+            Assertion::single(l.segment, last, BaseElement::new(self.segment_nonces.len() as u64)),
+        ];
+        for &(row, nonce) in &self.segment_nonces {
+            assertions.push(Assertion::single(l.nonce, row, nonce));
+            assertions.push(Assertion::single(l.valid, row - 1, BaseElement::ZERO));
+            assertions.push(Assertion::single(l.valid, row, BaseElement::ONE));
+        }
+        if let Some(n) = self.num_steps {
+            assertions.push(Assertion::single(l.step, 0, BaseElement::ZERO));
+            assertions.push(Assertion::single(l.step, last, BaseElement::new(n.saturating_sub(1) as u64)));
+        }
+        if let Some(row) = valid_drop_row(self.num_steps, self.segment_nonces.len(), self.trace_length()) {
+            assertions.push(Assertion::single(l.valid, row, BaseElement::ZERO));
+        }
+        for &(step, node) in self.waypoints.iter().chain(&self.prefix) {
+            assertions.push(Assertion::single(l.current, step, node + BaseElement::ONE));
+        }
+        if let Some(max) = self.max_steps {
+            if self.num_steps.is_none() {
+                assertions.push(Assertion::single(l.step, 0, BaseElement::ZERO));
+            }
+            assertions.push(Assertion::single(l.budget, 0, BaseElement::ZERO));
+            let bound = step_bound(max, self.trace_length());
+            assertions.push(Assertion::single(l.budget, last, BaseElement::new(bound)));
+        }
+        assertions
+    }
+}
+
+/// One asserted cell of a [`StarkraAir`], checked by [`evaluate_assertions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssertionResult {
+    /// What the assertion pins: `nonce`, `segment_nonce`, `start`, `end`,
+    /// `waypoint`, `valid_start`, `steps_end`, `separator`, `segment_start`,
+    /// `segment_first`, `segment_count`, `step_start`, `num_steps`,
+    /// `budget_start` or `max_steps`.
+    pub label: &'static str,
+    pub column: usize,
+    pub row: usize,
+    pub expected: BaseElement,
+    /// The given value of the cell; `None` if it was not given.
+    pub actual: Option<BaseElement>,
+}
+
+impl AssertionResult {
+    pub fn passed(&self) -> bool {
+        self.actual == Some(self.expected)
+    }
+}
+
+/// Check every assertion of `air` against `boundary_values`, keyed by
+/// `(column, row)`, e.g. the asserted cells of a trace (as `check_trace`
+/// does) or the boundary a proof was expected to have: for a rejected
+/// proof, build `air` with `StarkraAir::new(proof.trace_info().clone(),
+/// claimed, proof.options().clone())` and pass the cells the claim was
+/// derived from. One result per asserted cell, in `get_assertions` order.
+pub fn evaluate_assertions(
+    air: &StarkraAir,
+    boundary_values: &BTreeMap<(usize, usize), BaseElement>,
+) -> Vec<AssertionResult> {
+    let l = air.layout();
+    let last = air.trace_length() - 1;
+    let drop = valid_drop_row(air.num_steps, air.segment_nonces.len(), air.trace_length());
+    let label = |column: usize, row: usize| match column {
+        c if c == l.nonce && row == 0 => "nonce",
+        c if c == l.nonce => "segment_nonce",
+        c if c == l.current && row == 0 => "start",
+        c if c == l.current && row == last => "end",
+        c if c == l.current => "waypoint",
+        c if c == l.valid && row == 0 => "valid_start",
+        c if c == l.valid && Some(row) == drop => "steps_end",
+        c if c == l.valid && air.segment_nonces.iter().any(|&(r, _)| r == row) => "segment_start",
+        c if c == l.valid => "separator",
+        c if c == l.segment && row == 0 => "segment_first",
+        c if c == l.segment => "segment_count",
+        c if c == l.step && row == 0 => "step_start",
+        c if c == l.step => "num_steps",
+        c if c == l.budget && row == 0 => "budget_start",
+        _ => "max_steps",
+    };
+    let mut results = Vec::new();
+    for assertion in air.get_assertions() {
+        let column = assertion.column();
+        assertion.apply(air.trace_length(), |row, expected| {
+            results.push(AssertionResult {
+                label: label(column, row),
+                column,
+                row,
+                expected,
+                actual: boundary_values.get(&(column, row)).copied(),
+            });
+        });
     }
+    results
 }
@@ -0,0 +1,374 @@
+// analysis.rs
+use alloc::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    vec,
+    vec::Vec,
+};
+
+use crate::cfg::{Cfg, NodeId};
+
+impl Cfg {
+    /// Immediate dominators of every node reachable from `entry`
+    /// (Cooper, Harvey & Kennedy, "A Simple, Fast Dominance Algorithm").
+    /// `entry` maps to itself; unreachable nodes are absent.
+    pub fn dominators(&self, entry: NodeId) -> BTreeMap<NodeId, NodeId> {
+        idoms(self, entry)
+    }
+
+    /// Whether some path (possibly empty) leads from `from` to `to`.
+    pub fn reaches(&self, from: NodeId, to: NodeId) -> bool {
+        if from == to {
+            return true;
+        }
+        let mut visited = BTreeSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(v) = queue.pop_front() {
+            for &w in self.successors(v) {
+                if w == to {
+                    return true;
+                }
+                if visited.insert(w) {
+                    queue.push_back(w);
+                }
+            }
+        }
+        false
+    }
+
+    /// A shortest walk of jumps from `from` to `to`, as its node sequence
+    /// including both ends (just `[from]` if they are equal), or `None` if
+    /// there is none. Unlike `reaches`, it never enters a node whose entry
+    /// policy rules out jumps, so the walk can be spliced into a path as
+    /// jump steps and still satisfy the AIR.
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        if from == to {
+            return Some(vec![from]);
+        }
+        // parent[w] = the node w was first reached from
+        let mut parent: BTreeMap<NodeId, NodeId> = BTreeMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(v) = queue.pop_front() {
+            for &w in self.successors(v) {
+                if w == from || parent.contains_key(&w) || self.entry_policy(w).is_some_and(|p| !p.jump) {
+                    continue;
+                }
+                parent.insert(w, v);
+                if w == to {
+                    let mut path = vec![to];
+                    while let Some(&u) = parent.get(path.last().unwrap()) {
+                        path.push(u);
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back(w);
+            }
+        }
+        None
+    }
+
+    /// All loop-free paths from `from` to `to` with at most `max_len` nodes,
+    /// as node sequences including both ends, in DFS order.
+    ///
+    /// Meant for generating fixtures on small graphs: the number of simple
+    /// paths grows exponentially, and `max_len` is the only bound.
+    pub fn simple_paths(&self, from: NodeId, to: NodeId, max_len: usize) -> Vec<Vec<NodeId>> {
+        let mut paths = Vec::new();
+        if max_len == 0 || !self.contains(from) {
+            return paths;
+        }
+        let mut on_path = BTreeSet::from([from]);
+        let mut path = vec![from];
+        self.extend_simple_paths(to, max_len, &mut path, &mut on_path, &mut paths);
+        paths
+    }
+
+    fn extend_simple_paths(
+        &self,
+        to: NodeId,
+        max_len: usize,
+        path: &mut Vec<NodeId>,
+        on_path: &mut BTreeSet<NodeId>,
+        paths: &mut Vec<Vec<NodeId>>,
+    ) {
+        let v = *path.last().expect("path starts non-empty");
+        if v == to {
+            paths.push(path.clone());
+            return;
+        }
+        if path.len() == max_len {
+            return;
+        }
+        for &w in self.successors(v) {
+            if !on_path.insert(w) {
+                continue;
+            }
+            path.push(w);
+            self.extend_simple_paths(to, max_len, path, on_path, paths);
+            path.pop();
+            on_path.remove(&w);
+        }
+    }
+
+    /// Strongly connected components of the nodes in the graph (Tarjan),
+    /// each sorted ascending, in reverse topological order: no component
+    /// has an edge into a later one.
+    pub fn sccs(&self) -> Vec<Vec<NodeId>> {
+        const UNVISITED: usize = usize::MAX;
+        // node IDs may be sparse, so the per-node state is indexed by rank
+        let dense: BTreeMap<NodeId, usize> = self.nodes().enumerate().map(|(i, v)| (v, i)).collect();
+        let n = dense.len();
+        let mut index = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack: Vec<NodeId> = Vec::new();
+        let mut sccs = Vec::new();
+        let mut next_index = 0;
+
+        for root in self.nodes() {
+            if index[dense[&root]] != UNVISITED {
+                continue;
+            }
+            // (node, position of the next successor to visit)
+            let mut work: Vec<(NodeId, usize)> = vec![(root, 0)];
+            while let Some(&(v, i)) = work.last() {
+                let vi = dense[&v];
+                if index[vi] == UNVISITED {
+                    index[vi] = next_index;
+                    low[vi] = next_index;
+                    next_index += 1;
+                    on_stack[vi] = true;
+                    stack.push(v);
+                }
+                if let Some(&w) = self.successors(v).get(i) {
+                    let top = work.len() - 1;
+                    work[top].1 += 1;
+                    let wi = dense[&w];
+                    if index[wi] == UNVISITED {
+                        work.push((w, 0));
+                    } else if on_stack[wi] {
+                        low[vi] = low[vi].min(index[wi]);
+                    }
+                    continue;
+                }
+
+                work.pop();
+                if let Some(&(u, _)) = work.last() {
+                    let ui = dense[&u];
+                    low[ui] = low[ui].min(low[vi]);
+                }
+                if low[vi] == index[vi] {
+                    let mut component = Vec::new();
+                    loop {
+                        let w = stack.pop().expect("v is on the stack");
+                        on_stack[dense[&w]] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    sccs.push(component);
+                }
+            }
+        }
+        sccs
+    }
+
+    /// Immediate post-dominators with respect to `exit`: dominators of the
+    /// reversed CFG rooted at `exit`. `exit` maps to itself; nodes that
+    /// cannot reach `exit` are absent.
+    pub fn post_dominators(&self, exit: NodeId) -> BTreeMap<NodeId, NodeId> {
+        self.reversed().dominators(exit)
+    }
+
+    /// Immediate post-dominators with respect to all `exits()` at once.
+    /// A virtual sink is added behind every exit; nodes whose only
+    /// post-dominator is that sink (e.g. the exits themselves) map to
+    /// themselves.
+    pub fn post_dominators_all(&self) -> BTreeMap<NodeId, NodeId> {
+        let sink = (0..).find(|&v| !self.contains(v)).expect("the graph has fewer than 2^64 nodes");
+        let mut adj: Vec<(NodeId, Vec<NodeId>)> =
+            self.nodes().map(|v| (v, self.predecessors(v).to_vec())).collect();
+        adj.push((sink, self.exits()));
+
+        Cfg::from_adjacency(adj)
+            .dominators(sink)
+            .into_iter()
+            .filter(|&(v, _)| v != sink)
+            .map(|(v, d)| if d == sink { (v, v) } else { (v, d) })
+            .collect()
+    }
+
+    /// Nodes of the graph (see [`Cfg::contains`]) that no path from any of
+    /// `entries` reaches, sorted ascending. Entries outside the graph reach
+    /// nothing.
+    pub fn unreachable_from(&self, entries: impl IntoIterator<Item = NodeId>) -> Vec<NodeId> {
+        let mut visited = BTreeSet::new();
+        let mut queue: VecDeque<NodeId> = VecDeque::new();
+        for e in entries {
+            if self.contains(e) && visited.insert(e) {
+                queue.push_back(e);
+            }
+        }
+        while let Some(v) = queue.pop_front() {
+            for &w in self.successors(v) {
+                if visited.insert(w) {
+                    queue.push_back(w);
+                }
+            }
+        }
+        self.nodes().filter(|v| !visited.contains(v)).collect()
+    }
+
+    /// Edges `(u, v)` of a DFS from `entry` whose target `v` is an ancestor
+    /// of `u` on the DFS stack (self-loops included), in the order the DFS
+    /// finds them; a duplicated edge is reported once. Every cycle reachable
+    /// from `entry` contains at least one of them.
+    ///
+    /// For reducible CFGs these are exactly the loop back-edges (`v`
+    /// dominates `u`). An irreducible loop has no such edge; which of its
+    /// edges is reported then depends on the successor order.
+    pub fn back_edges(&self, entry: NodeId) -> Vec<(NodeId, NodeId)> {
+        let mut back = Vec::new();
+        if !self.contains(entry) {
+            return back;
+        }
+        let mut seen = BTreeSet::new();
+        let mut visited = BTreeSet::from([entry]);
+        let mut on_stack = BTreeSet::from([entry]);
+
+        // (node, index of the next successor to explore)
+        let mut stack: Vec<(NodeId, usize)> = vec![(entry, 0)];
+        while let Some(&mut (v, ref mut i)) = stack.last_mut() {
+            let succ = self.successors(v);
+            if *i < succ.len() {
+                let w = succ[*i];
+                *i += 1;
+                if on_stack.contains(&w) {
+                    if seen.insert((v, w)) {
+                        back.push((v, w));
+                    }
+                } else if visited.insert(w) {
+                    on_stack.insert(w);
+                    stack.push((w, 0));
+                }
+            } else {
+                on_stack.remove(&v);
+                stack.pop();
+            }
+        }
+        back
+    }
+
+    /// Whether the graph has no cycles, self-loops included; `true` for
+    /// the empty graph. An iterative DFS from every unvisited node that
+    /// stops at the first edge back onto the DFS stack, so it is cheaper
+    /// than checking [`Cfg::sccs`] and safe on deep graphs.
+    pub fn is_dag(&self) -> bool {
+        // absent = unvisited, 1 = on the DFS stack, 2 = done
+        let mut state: BTreeMap<NodeId, u8> = BTreeMap::new();
+        let mut stack: Vec<(NodeId, usize)> = Vec::new();
+        for root in self.nodes() {
+            if state.contains_key(&root) {
+                continue;
+            }
+            state.insert(root, 1);
+            stack.push((root, 0));
+            while let Some(&mut (v, ref mut i)) = stack.last_mut() {
+                let succ = self.successors(v);
+                if *i < succ.len() {
+                    let w = succ[*i];
+                    *i += 1;
+                    match state.get(&w) {
+                        None => {
+                            state.insert(w, 1);
+                            stack.push((w, 0));
+                        }
+                        Some(1) => return false,
+                        Some(_) => {}
+                    }
+                } else {
+                    state.insert(v, 2);
+                    stack.pop();
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Reverse postorder of the nodes reachable from `entry` (iterative DFS).
+fn reverse_postorder(cfg: &Cfg, entry: NodeId) -> Vec<NodeId> {
+    let mut order = Vec::new();
+    if !cfg.contains(entry) {
+        return order;
+    }
+    let mut visited = BTreeSet::from([entry]);
+
+    // (node, index of the next successor to explore)
+    let mut stack: Vec<(NodeId, usize)> = vec![(entry, 0)];
+    while let Some(&mut (v, ref mut i)) = stack.last_mut() {
+        let succ = cfg.successors(v);
+        if *i < succ.len() {
+            let w = succ[*i];
+            *i += 1;
+            if visited.insert(w) {
+                stack.push((w, 0));
+            }
+        } else {
+            order.push(v);
+            stack.pop();
+        }
+    }
+    order.reverse();
+    order
+}
+
+fn idoms(cfg: &Cfg, entry: NodeId) -> BTreeMap<NodeId, NodeId> {
+    let mut idom: BTreeMap<NodeId, NodeId> = BTreeMap::new();
+    let rpo = reverse_postorder(cfg, entry);
+    if rpo.is_empty() {
+        return idom;
+    }
+
+    // position of each node in reverse postorder
+    let rank: BTreeMap<NodeId, usize> = rpo.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+
+    let intersect = |idom: &BTreeMap<NodeId, NodeId>, mut a: NodeId, mut b: NodeId| {
+        while a != b {
+            while rank[&a] > rank[&b] {
+                a = idom[&a];
+            }
+            while rank[&b] > rank[&a] {
+                b = idom[&b];
+            }
+        }
+        a
+    };
+
+    idom.insert(entry, entry);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &v in rpo.iter().skip(1) {
+            let mut new_idom: Option<NodeId> = None;
+            for &p in cfg.predecessors(v) {
+                if !idom.contains_key(&p) {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => p,
+                    Some(d) => intersect(&idom, p, d),
+                });
+            }
+            if let Some(d) = new_idom
+                && idom.get(&v) != Some(&d)
+            {
+                idom.insert(v, d);
+                changed = true;
+            }
+        }
+    }
+    idom
+}
@@ -0,0 +1,74 @@
+// starkra-verify: check a proof file against a CFG, with no prover code.
+//
+//   starkra-verify <cfg> <proof> [--min-security <bits>] [--max-rows <n>]
+//
+// The CFG is read as DOT if its name ends in `.dot` or `.gv`, as an
+// adjacency list otherwise (see `CfgFile`). `--max-rows` rejects proofs over
+// traces longer than n rows before the STARK check.
+//
+// A proof's CFG commitment is checked against the commitment of <cfg>, and
+// the AIR does not read the CFG otherwise, so <cfg> must be a CFG you trust,
+// not one shipped with the proof.
+//
+// Exits 0 if the proof verifies, 1 if the STARK check rejects it, 2 on bad
+// usage, 3 if the proof file is malformed and 4 if its public inputs are bad
+// (see `VerifyOutcome`).
+use std::env;
+use std::fs;
+use std::time::Instant;
+
+use starkra::source::{CfgFile, CfgSource};
+use starkra::verifier::{Acceptable, VerifyOutcome, verify_encoded};
+use winterfell::AcceptableOptions;
+
+fn usage() -> ! {
+    eprintln!("usage: starkra-verify <cfg> <proof> [--min-security <bits>] [--max-rows <n>]");
+    eprintln!("  <cfg> must be trusted: the proof is only checked against its commitment");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut positional = Vec::new();
+    let mut min_security = 128;
+    let mut max_rows = None;
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        if arg == "--min-security" {
+            min_security = it.next().and_then(|s| s.parse().ok()).unwrap_or_else(|| usage());
+        } else if arg == "--max-rows" {
+            max_rows = Some(it.next().and_then(|s| s.parse().ok()).unwrap_or_else(|| usage()));
+        } else {
+            positional.push(arg);
+        }
+    }
+    let [cfg_file, proof_file] = positional.as_slice() else { usage() };
+
+    let cfg = CfgFile(cfg_file).into_cfg().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    });
+    let bytes = fs::read(proof_file).unwrap_or_else(|e| {
+        eprintln!("error: Failed to read '{}': {}", proof_file, e);
+        std::process::exit(2);
+    });
+
+    let t_verify_start = Instant::now();
+    let options = AcceptableOptions::MinConjecturedSecurity(min_security);
+    let mut acceptable = Acceptable::new(&options);
+    if let Some(n) = max_rows {
+        acceptable = acceptable.with_max_verify_rows(n);
+    }
+    let outcome = verify_encoded(&bytes, &cfg, acceptable);
+    let verify_dur = t_verify_start.elapsed();
+    let code = match outcome {
+        VerifyOutcome::Valid => {
+            println!("Valid Proof ({:.3?})", verify_dur);
+            return;
+        }
+        VerifyOutcome::InvalidProof(_) => 1,
+        VerifyOutcome::MalformedProof(_) => 3,
+        VerifyOutcome::BadPublicInputs(_) => 4,
+    };
+    println!("Failed to verify proof: {}", outcome);
+    std::process::exit(code);
+}
@@ -0,0 +1,237 @@
+// bundle.rs
+//! A proof case in one file: the CFG, the path, the public inputs it is
+//! proven against and the proof options. Enough to rebuild the trace,
+//! prove and verify exactly what someone else ran, e.g. for a bug report.
+
+use alloc::{format, string::ToString, vec::Vec};
+#[cfg(feature = "std")]
+use std::fs;
+
+use winter_utils::{Deserializable, Serializable};
+use winterfell::ProofOptions;
+#[cfg(feature = "prover")]
+use winterfell::{AcceptableOptions, Proof, Prover};
+
+use crate::{
+    air::PublicInputs,
+    cfg::{Cfg, EntryPolicy, MAX_NODE_ID, NodeId},
+    error::StarkraError,
+    exe_path::{Step, parse_execution_path_binary, to_binary},
+    proof_io::Reader,
+};
+#[cfg(feature = "prover")]
+use crate::{
+    air::{NeighborEncoding, StarkraAir},
+    prover::StarkraProver,
+    verifier::verify_path,
+};
+
+// Bundle file layout (all integers little-endian):
+//   b"SKBN" | version: u8
+//   n_lines: u32 | (node: u64, n_succ: u32, succ: u64 * n_succ) * n_lines
+//   has_entries: u8 | n_entries: u32 | node: u64 * n_entries (only if has_entries = 1)
+//   n_policies: u32 | (node: u64, kinds: u8) * n_policies (as in the proof file)
+//   path_len: u32 | path (`exe_path::to_binary`, without start/end nodes)
+//   public inputs (as in the proof file, see `proof_io`)
+//   options_len: u32 | proof options (winterfell encoding)
+//
+// One line per node with its own adjacency entry, successors in CFG order,
+// so the bundle rebuilds the same neighbor columns.
+pub const BUNDLE_MAGIC: &[u8; 4] = b"SKBN";
+pub const BUNDLE_VERSION: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    pub cfg: Cfg,
+    pub steps: Vec<Step>,
+    pub public_inputs: PublicInputs,
+    pub options: ProofOptions,
+}
+
+impl Bundle {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(BUNDLE_MAGIC);
+        out.push(BUNDLE_VERSION);
+
+        let lines: Vec<NodeId> = self.cfg.nodes().filter(|&n| self.cfg.is_declared(n)).collect();
+        out.extend_from_slice(&(lines.len() as u32).to_le_bytes());
+        for n in lines {
+            let succ = self.cfg.successors(n);
+            out.extend_from_slice(&n.to_le_bytes());
+            out.extend_from_slice(&(succ.len() as u32).to_le_bytes());
+            for s in succ {
+                out.extend_from_slice(&s.to_le_bytes());
+            }
+        }
+        match self.cfg.entry_set() {
+            Some(entries) => {
+                out.push(1);
+                out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+                for e in entries {
+                    out.extend_from_slice(&e.to_le_bytes());
+                }
+            }
+            None => out.push(0),
+        }
+        let policies = self.cfg.entry_policies();
+        out.extend_from_slice(&(policies.len() as u32).to_le_bytes());
+        for (n, policy) in policies {
+            out.extend_from_slice(&n.to_le_bytes());
+            out.push(policy.bits());
+        }
+
+        let path = to_binary(&self.steps, None, None);
+        out.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        out.extend_from_slice(&path);
+        out.extend_from_slice(&self.public_inputs.to_bytes());
+        let options = self.options.to_bytes();
+        out.extend_from_slice(&(options.len() as u32).to_le_bytes());
+        out.extend_from_slice(&options);
+        out
+    }
+
+    /// Decode and validate a bundle: the header and every section must be
+    /// well formed with nothing left over, node IDs must be at most
+    /// [`MAX_NODE_ID`] and the path must be non-empty and only visit nodes
+    /// of the CFG. Whether the path is a valid walk, or proves the public
+    /// inputs, is for [`Bundle::replay`] to find out.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StarkraError> {
+        if bytes.len() < 5 || &bytes[..4] != BUNDLE_MAGIC {
+            return Err(StarkraError::Parse("missing bundle header".to_string()));
+        }
+        if bytes[4] != BUNDLE_VERSION {
+            return Err(StarkraError::Parse(format!("unsupported bundle version {}", bytes[4])));
+        }
+        fn node(r: &mut Reader) -> Result<NodeId, StarkraError> {
+            let at = r.pos;
+            let n = r.u64()?;
+            if n > MAX_NODE_ID {
+                return Err(StarkraError::Parse(format!("Offset {}: node ID {} is out of range", at, n)));
+            }
+            Ok(n)
+        }
+        let mut r = Reader::new(bytes);
+        r.pos = 5;
+
+        let n_lines = r.u32()? as usize;
+        let mut adj = Vec::with_capacity(n_lines.min(bytes.len()));
+        for _ in 0..n_lines {
+            let n = node(&mut r)?;
+            let n_succ = r.u32()? as usize;
+            let mut succ = Vec::with_capacity(n_succ.min(bytes.len()));
+            for _ in 0..n_succ {
+                succ.push(node(&mut r)?);
+            }
+            adj.push((n, succ));
+        }
+        let mut cfg = Cfg::from_adjacency(adj);
+        match r.u8()? {
+            0 => {}
+            1 => {
+                let n_entries = r.u32()? as usize;
+                let mut entries = Vec::with_capacity(n_entries.min(bytes.len()));
+                for _ in 0..n_entries {
+                    entries.push(node(&mut r)?);
+                }
+                cfg = cfg.with_entries(entries);
+            }
+            flag => return Err(StarkraError::Parse(format!("invalid entry flag {}", flag))),
+        }
+        let n_policies = r.u32()? as usize;
+        let mut policies = Vec::with_capacity(n_policies.min(bytes.len()));
+        for _ in 0..n_policies {
+            let n = node(&mut r)?;
+            let bits = r.u8()?;
+            let policy = EntryPolicy::from_bits(bits)
+                .ok_or_else(|| StarkraError::Parse(format!("invalid entry policy {:#x}", bits)))?;
+            policies.push((n, policy));
+        }
+        cfg = cfg.with_entry_policies(policies);
+
+        let path_len = r.u32()? as usize;
+        let (steps, _, _) = parse_execution_path_binary(r.slice(path_len)?)?;
+        if steps.is_empty() {
+            return Err(StarkraError::Parse("bundle path is empty".to_string()));
+        }
+        if let Some(n) = steps.iter().flat_map(|s| &s.addrs).find(|&&n| !cfg.contains(n)) {
+            return Err(StarkraError::Parse(format!("bundle path visits node {}, which is not in the CFG", n)));
+        }
+
+        let (public_inputs, used) = PublicInputs::read_from(&bytes[r.pos..])?;
+        r.pos += used;
+        let options_len = r.u32()? as usize;
+        let options = ProofOptions::read_from_bytes(r.slice(options_len)?)
+            .map_err(|e| StarkraError::Parse(format!("invalid proof options: {}", e)))?;
+        if r.pos != bytes.len() {
+            return Err(StarkraError::Parse(format!("Offset {}: trailing bytes after bundle", r.pos)));
+        }
+        Ok(Self { cfg, steps, public_inputs, options })
+    }
+
+    /// Build the trace of the bundle's path and prove it with the bundle's
+    /// options. The prover is set up from the public inputs (forbidden
+    /// nodes, step count and bound, waypoints, CFG
+    /// commitment, committed prefix, neighbor encoding, entry policies), so for a consistent bundle the proof's public inputs
+    /// are the bundle's; they are returned as derived from the trace.
+    /// Bundles hold one session, so segment nonces are rejected.
+    #[cfg(feature = "prover")]
+    pub fn prove(&self) -> Result<(Proof, PublicInputs), StarkraError> {
+        let pi = &self.public_inputs;
+        if !pi.segment_nonces.is_empty() {
+            return Err(StarkraError::Trace("bundles hold a single session; segment nonces are not supported".to_string()));
+        }
+        let nonce = u32::try_from(pi.nonce.as_int())
+            .map_err(|_| StarkraError::Trace(format!("nonce {} does not fit the trace builder's u32", pi.nonce.as_int())))?;
+        let forbidden: Vec<NodeId> = pi.forbidden.iter().map(|f| f.as_int()).collect();
+        let mut trace = StarkraAir::build_trace_avoiding(self.steps.clone(), self.cfg.clone(), nonce, &forbidden);
+
+        let mut prover = StarkraProver::new(self.options.clone())
+            .with_forbidden(forbidden)
+            .with_waypoints(pi.waypoints.iter().map(|&(step, node)| (step, node.as_int())).collect())
+            .with_entry_policies(pi.entry_policies.iter().map(|&(node, policy)| (node.as_int(), policy)).collect());
+        if pi.num_steps.is_some() {
+            prover = prover.with_step_count();
+        }
+        if let Some(max) = pi.max_steps {
+            StarkraAir::fill_budget(&mut trace, max);
+            prover = prover.with_max_steps(max);
+        }
+        if let Some(c) = pi.cfg_commitment {
+            prover = prover.with_cfg_commitment(c);
+        }
+        if let Some(root) = pi.sequence_root {
+            prover = prover.with_committed_prefix(root, pi.prefix.len());
+        }
+        if pi.neighbor_encoding == NeighborEncoding::OneHot {
+            trace = StarkraAir::add_selectors(trace);
+            prover = prover.with_neighbor_encoding(NeighborEncoding::OneHot);
+        }
+        let derived = prover.get_pub_inputs(&trace);
+        let proof = prover.prove(trace).map_err(StarkraError::Prove)?;
+        Ok((proof, derived))
+    }
+
+    /// [`Bundle::prove`], then verify the proof against the bundle's public
+    /// inputs and CFG under `acceptable`.
+    #[cfg(feature = "prover")]
+    pub fn replay(&self, acceptable: &AcceptableOptions) -> Result<Proof, StarkraError> {
+        let (proof, _) = self.prove()?;
+        verify_path(proof.clone(), self.public_inputs.clone(), &self.cfg, acceptable)?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn write_bundle(path: &str, bundle: &Bundle) -> Result<(), StarkraError> {
+    fs::write(path, bundle.to_bytes()).map_err(|e| StarkraError::Io(format!("Failed to write '{}': {}", path, e)))
+}
+
+#[cfg(feature = "std")]
+pub fn read_bundle(path: &str) -> Result<Bundle, StarkraError> {
+    let bytes = fs::read(path).map_err(|e| StarkraError::Io(format!("Failed to read '{}': {}", path, e)))?;
+    Bundle::from_bytes(&bytes).map_err(|e| match e {
+        StarkraError::Parse(msg) => StarkraError::Parse(format!("'{}': {}", path, msg)),
+        e => e,
+    })
+}
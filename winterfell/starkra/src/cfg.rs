@@ -1,63 +1,514 @@
 // cfg.rs
-use std::fs;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+use core::str::FromStr;
 
-/// Pure CFG stored as adjacency lists.
-/// Node IDs are u32, used as direct indices.
-/// succ[i] = successors of node i
-/// pred[i] = predecessors of node i
-#[derive(Debug, Clone)]
+use crate::exe_path::{JmpType, Step};
+
+/// Node identifier: a basic-block ID or a 64-bit address.
+///
+/// IDs are stored in trace cells as `id + 1`, so they must not exceed
+/// [`MAX_NODE_ID`].
+pub type NodeId = u64;
+
+/// Largest node ID the trace encoding can hold (f64 field modulus minus 2).
+pub const MAX_NODE_ID: NodeId = 0xFFFF_FFFF_0000_0001 - 2;
+
+/// Pure CFG stored as adjacency lists, keyed by node ID, so sparse IDs
+/// (e.g. 64-bit addresses) cost nothing for the gaps between them.
+/// succ[n] = successors of node n, for every node in the graph
+/// pred[n] = predecessors of node n, for the same nodes
+/// declared = nodes that had their own adjacency entry (possibly empty)
+/// entries = legal start nodes; `None` means any node may start a path
+/// kinds = declared edge types, for edges that have one
+/// policies = how each restricted node may be entered
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Cfg {
-    succ: Vec<Vec<u32>>,
-    pred: Vec<Vec<u32>>,
+    succ: BTreeMap<NodeId, Vec<NodeId>>,
+    pred: BTreeMap<NodeId, Vec<NodeId>>,
+    declared: BTreeSet<NodeId>,
+    entries: Option<BTreeSet<NodeId>>,
+    kinds: BTreeMap<(NodeId, NodeId), JmpType>,
+    policies: BTreeMap<NodeId, EntryPolicy>,
+}
+
+/// The steps allowed to enter a node (see [`Cfg::with_entry_policies`]):
+/// a call, a ret, and a jump or tail call. The trace's `call` and `ret`
+/// flags tell these three apart but not a jump from a tail call, so that
+/// is the grain the AIR enforces a policy at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryPolicy {
+    pub call: bool,
+    pub ret: bool,
+    /// Jumps and tail calls.
+    pub jump: bool,
+}
+
+impl EntryPolicy {
+    /// Entered only by a call, e.g. a function's first block.
+    pub const CALL_ONLY: Self = Self { call: true, ret: false, jump: false };
+
+    pub fn allows(&self, jmp_type: &JmpType) -> bool {
+        match jmp_type {
+            JmpType::Call => self.call,
+            JmpType::Ret => self.ret,
+            JmpType::Jump | JmpType::TailCall => self.jump,
+        }
+    }
+
+    /// `call | ret << 1 | jump << 2`.
+    pub(crate) fn bits(self) -> u8 {
+        self.call as u8 | (self.ret as u8) << 1 | (self.jump as u8) << 2
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Option<Self> {
+        (bits < 8).then_some(Self { call: bits & 1 != 0, ret: bits & 2 != 0, jump: bits & 4 != 0 })
+    }
+
+    /// Parse `call`, `ret` and `jump` tokens, e.g. the rest of an `enter`
+    /// line; at least one is needed.
+    fn parse<'a>(tokens: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut policy = Self { call: false, ret: false, jump: false };
+        for tok in tokens {
+            match tok {
+                "call" => policy.call = true,
+                "ret" => policy.ret = true,
+                "jump" => policy.jump = true,
+                _ => return Err(format!("unknown entry kind '{}' (expected call, ret or jump)", tok)),
+            }
+        }
+        if policy.bits() == 0 {
+            return Err("an entry policy needs at least one of call, ret, jump".into());
+        }
+        Ok(policy)
+    }
+}
+
+/// The allowed kinds joined by ` or `, e.g. `call or ret`.
+impl fmt::Display for EntryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<&str> = [(self.call, "call"), (self.ret, "ret"), (self.jump, "jump")]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect();
+        if kinds.is_empty() { f.write_str("nothing") } else { f.write_str(&kinds.join(" or ")) }
+    }
+}
+
+/// How [`Cfg::order_successors`] arranges each node's successors, i.e.
+/// which neighbor column each target lands in. With the one-hot encoding
+/// the selector index follows the column, so putting hot edges first keeps
+/// most selectors in the low columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuccessorOrder<'a> {
+    /// Keep the order the CFG was loaded in.
+    #[default]
+    AsGiven,
+    /// Ascending, as after `canonicalize`.
+    Sorted,
+    /// Most-taken first by how often these steps take each edge (see
+    /// `edge_multiplicity`); ties, including untaken edges, keep their
+    /// given order.
+    ByFrequency(&'a [Step]),
 }
 
 impl Cfg {
     /// Build a CFG from an adjacency list: iterator of `(node, successors)`.
-    /// Vectors are sized to (max_id + 1). Missing nodes are empty.
+    /// A node listed twice keeps its last successor list. Targets without
+    /// their own entry are in the graph with no successors.
     pub fn from_adjacency<I>(adj: I) -> Self
     where
-        I: IntoIterator<Item = (u32, Vec<u32>)>,
+        I: IntoIterator<Item = (NodeId, Vec<NodeId>)>,
     {
-        let mut raw: Vec<(u32, Vec<u32>)> = Vec::new();
-        let mut max_id: u32 = 0;
-
-        // First pass: find max node id
+        let mut succ: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+        let mut declared = BTreeSet::new();
         for (src, vs) in adj {
-            if src > max_id { max_id = src; }
-            for &v in &vs {
-                if v > max_id { max_id = v; }
+            succ.insert(src, vs);
+            declared.insert(src);
+        }
+        let pred = build_predecessors(&mut succ);
+
+        Cfg { succ, pred, declared, entries: None, kinds: BTreeMap::new(), policies: BTreeMap::new() }
+    }
+
+    /// Build a CFG from a flat edge list, grouping edges by source. Equal to
+    /// `from_adjacency` over the grouped form: each source's successors keep
+    /// their order in `edges`, and duplicate edges are kept, as everywhere
+    /// else (use `dedup` to drop them). Only sources count as declared.
+    pub fn from_edges(edges: impl IntoIterator<Item = (NodeId, NodeId)>) -> Self {
+        let mut grouped: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+        for (u, v) in edges {
+            grouped.entry(u).or_default().push(v);
+        }
+        Self::from_adjacency(grouped)
+    }
+
+    /// Restrict the legal start nodes to `entries` (added to any already set).
+    pub fn with_entries(mut self, entries: impl IntoIterator<Item = NodeId>) -> Self {
+        self.entries.get_or_insert_with(BTreeSet::new).extend(entries);
+        self
+    }
+
+    /// Declare how each of `kinds`' edges is taken (replacing any kind
+    /// already declared for it), e.g. from a disassembler's call/jump/ret
+    /// labels. Kinds are host-side metadata, checked by `check_edge_kinds`;
+    /// they are not part of the commitment or the AIR, and CFGs rebuilt
+    /// from this one (`reversed`, `induced_subgraph`, ...) drop them.
+    pub fn with_edge_kinds(mut self, kinds: impl IntoIterator<Item = ((NodeId, NodeId), JmpType)>) -> Self {
+        self.kinds.extend(kinds);
+        self
+    }
+
+    /// The declared kind of the edge `from -> to`, if it has one.
+    pub fn edge_kind(&self, from: NodeId, to: NodeId) -> Option<&JmpType> {
+        self.kinds.get(&(from, to))
+    }
+
+    /// Restrict how each of `policies`' nodes may be entered (replacing any
+    /// policy already set for it). Unlike edge kinds, policies are part of
+    /// [`Cfg::commitment`] and checked in-circuit: the public inputs carry
+    /// them (`PublicInputs::entry_policies`), the verifier requires them to
+    /// be this CFG's, and the AIR rejects a step into a restricted node by
+    /// a kind its policy rules out. The first row is not entered by any
+    /// step and is not checked. CFGs rebuilt from this one (`reversed`,
+    /// `induced_subgraph`, ...) drop them.
+    pub fn with_entry_policies(mut self, policies: impl IntoIterator<Item = (NodeId, EntryPolicy)>) -> Self {
+        self.policies.extend(policies);
+        self
+    }
+
+    /// The policy restricting how `n` may be entered, if it has one.
+    pub fn entry_policy(&self, n: NodeId) -> Option<&EntryPolicy> {
+        self.policies.get(&n)
+    }
+
+    /// Every entry policy, by node.
+    pub fn entry_policies(&self) -> &BTreeMap<NodeId, EntryPolicy> {
+        &self.policies
+    }
+
+    /// The declared entry points, if any were declared.
+    pub fn entry_set(&self) -> Option<&BTreeSet<NodeId>> {
+        self.entries.as_ref()
+    }
+
+    /// Whether a path may start at `n`: it is a declared entry point, or no
+    /// entry points were declared at all.
+    pub fn is_legal_entry(&self, n: NodeId) -> bool {
+        self.entries.as_ref().is_none_or(|e| e.contains(&n))
+    }
+
+    /// Build a CFG from a whitespace-separated adjacency list file
+    /// (format as for the `FromStr` impl). With the `gzip` feature, files
+    /// named `*.gz` or starting with the gzip magic are decompressed first.
+    #[cfg(feature = "std")]
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        Self::from_bytes(path, crate::input::read_input(path)?)
+    }
+
+    /// `from_file` with non-blocking IO for Tokio runtimes. Only the read is
+    /// async; parsing runs synchronously afterwards.
+    #[cfg(feature = "tokio")]
+    pub async fn from_file_async(path: &str) -> Result<Self, String> {
+        let contents = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        Self::from_bytes(path, crate::input::decompress(path, contents)?)
+    }
+
+    #[cfg(feature = "std")]
+    fn from_bytes(path: &str, contents: Vec<u8>) -> Result<Self, String> {
+        String::from_utf8(contents)
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))?
+            .parse()
+    }
+
+    /// Build a CFG from a Graphviz `digraph`. Node IDs are decimal or `0x`
+    /// hex numbers, optionally quoted; every node named in a statement is
+    /// declared, and `a -> b -> c` chains add one edge per arrow, in order.
+    /// A node statement with `entry=true` declares a legal start node, and
+    /// an edge statement with `kind=call` (`jump`, `ret`, `tailcall`) the
+    /// kind of each of its edges (see `with_edge_kinds`).
+    /// Attributes are otherwise ignored; subgraphs and ports are rejected.
+    pub fn from_dot(text: &str) -> Result<Self, String> {
+        crate::source::parse_dot(text)
+    }
+
+    /// `from_dot` over a file, decompressed like in `from_file`.
+    #[cfg(feature = "std")]
+    pub fn from_dot_file(path: &str) -> Result<Self, String> {
+        let contents = String::from_utf8(crate::input::read_input(path)?)
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        Self::from_dot(&contents).map_err(|e| format!("'{}': {}", path, e))
+    }
+
+    /// Like `from_file`, but rejects CFGs with dangling successors
+    /// (see `dangling_targets`).
+    #[cfg(feature = "std")]
+    pub fn from_file_strict(path: &str) -> Result<Self, String> {
+        let cfg = Self::from_file(path)?;
+        let dangling = cfg.dangling_targets();
+        if !dangling.is_empty() {
+            let ids: Vec<String> = dangling.iter().map(|v| v.to_string()).collect();
+            return Err(format!(
+                "'{}': successors without an adjacency line: {}",
+                path,
+                ids.join(", ")
+            ));
+        }
+        Ok(cfg)
+    }
+
+    /// Number of nodes in the graph (see [`Cfg::contains`]).
+    pub fn len(&self) -> usize { self.succ.len() }
+    pub fn is_empty(&self) -> bool { self.succ.is_empty() }
+
+    /// The nodes in the graph, in ascending order.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.succ.keys().copied()
+    }
+
+    pub fn successors(&self, n: NodeId) -> &[NodeId] {
+        self.succ.get(&n).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn predecessors(&self, n: NodeId) -> &[NodeId] {
+        self.pred.get(&n).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.succ.iter().flat_map(|(&u, vs)| vs.iter().copied().map(move |v| (u, v)))
+    }
+
+    /// Sort every successor list ascending. The successor order decides
+    /// which neighbor column each target lands in, so after this the same
+    /// edge set always yields the same trace (and, with the same path and
+    /// options, byte-identical proofs) regardless of input line order.
+    pub fn canonicalize(&mut self) {
+        for vs in self.succ.values_mut() {
+            vs.sort_unstable();
+        }
+    }
+
+    /// Whether every successor list is sorted ascending, as after
+    /// `canonicalize` (see `StarkraAir::check_neighbor_order`).
+    pub fn is_canonical(&self) -> bool {
+        self.succ.values().all(|vs| vs.is_sorted())
+    }
+
+    /// Rearrange every successor list as `order` says. Only the neighbor
+    /// column each target lands in changes: the edge set, and with it
+    /// [`Cfg::commitment`] (taken over the sorted edge table), stays the
+    /// same, so a proof over the reordered CFG verifies against this one.
+    pub fn order_successors(&mut self, order: SuccessorOrder<'_>) {
+        match order {
+            SuccessorOrder::AsGiven => {}
+            SuccessorOrder::Sorted => self.canonicalize(),
+            SuccessorOrder::ByFrequency(steps) => {
+                let counts = self.edge_multiplicity(steps);
+                for (&u, vs) in self.succ.iter_mut() {
+                    // stable, so ties keep their given order
+                    vs.sort_by_key(|&v| core::cmp::Reverse(counts.get(&(u, v)).copied().unwrap_or(0)));
+                }
+            }
+        }
+    }
+
+    /// Canonicalize and drop duplicate edges.
+    pub fn dedup(&mut self) {
+        self.canonicalize();
+        for vs in self.succ.values_mut() {
+            vs.dedup();
+        }
+        self.pred = build_predecessors(&mut self.succ);
+    }
+
+    /// Whether `n` appears in the graph: it has its own adjacency line or
+    /// is the target of some edge. IDs in the gaps between used IDs do not.
+    pub fn contains(&self, n: NodeId) -> bool {
+        self.succ.contains_key(&n)
+    }
+
+    /// Nodes with at least one edge, as source or target, in ascending
+    /// order. Unlike [`Cfg::nodes`] this skips declared nodes with no
+    /// edges; these are the nodes [`Cfg::commitment`] covers.
+    pub fn active_node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes().filter(|&v| !self.successors(v).is_empty() || !self.predecessors(v).is_empty())
+    }
+
+    /// Number of [`Cfg::active_node_ids`]; at most `len()`.
+    pub fn active_nodes(&self) -> usize {
+        self.active_node_ids().count()
+    }
+
+    /// Whether `n` has its own adjacency line, possibly with no successors.
+    pub fn is_declared(&self, n: NodeId) -> bool {
+        self.declared.contains(&n)
+    }
+
+    /// Nodes that appear in the graph but have no predecessors.
+    /// Independent of the declared entry points (see `entry_set`).
+    pub fn entries(&self) -> Vec<NodeId> {
+        self.nodes().filter(|&v| self.predecessors(v).is_empty()).collect()
+    }
+
+    /// Nodes that appear in the graph (own adjacency line or edge target)
+    /// but have no successors.
+    pub fn exits(&self) -> Vec<NodeId> {
+        self.nodes().filter(|&v| self.successors(v).is_empty()).collect()
+    }
+
+    /// `hist[d]` = number of nodes in the graph with out-degree `d`;
+    /// the last entry is non-zero.
+    pub fn out_degree_histogram(&self) -> Vec<usize> {
+        let mut hist = Vec::new();
+        for v in self.nodes() {
+            let d = self.successors(v).len();
+            if hist.len() <= d {
+                hist.resize(d + 1, 0);
             }
-            raw.push((src, vs));
+            hist[d] += 1;
+        }
+        hist
+    }
+
+    /// The same graph with every edge flipped.
+    pub fn reversed(&self) -> Self {
+        let mut rev = Self::from_adjacency(
+            self.nodes().map(|v| (v, self.predecessors(v).to_vec())),
+        );
+        rev.declared = self.declared.clone();
+        rev
+    }
+
+    /// The subgraph induced by `nodes`: only edges with both endpoints in
+    /// `nodes` are kept, and node IDs are renumbered densely as `0..k` in
+    /// ascending order of the original IDs. Returns the subgraph and the
+    /// original-to-new ID mapping.
+    ///
+    /// Every node of `nodes` gets its own adjacency line, so one left with
+    /// no edges is an exit. Declared entry points are not carried over.
+    /// Paths must be remapped with [`remap_path`](crate::exe_path::remap_path),
+    /// which rejects steps that leave the subgraph.
+    pub fn induced_subgraph(&self, nodes: &BTreeSet<NodeId>) -> (Cfg, BTreeMap<NodeId, NodeId>) {
+        let mapping: BTreeMap<NodeId, NodeId> =
+            nodes.iter().enumerate().map(|(i, &n)| (n, i as NodeId)).collect();
+        let sub = Cfg::from_adjacency(mapping.iter().map(|(&old, &new)| {
+            let succ = self.successors(old).iter().filter_map(|v| mapping.get(v).copied()).collect();
+            (new, succ)
+        }));
+        (sub, mapping)
+    }
+
+    /// The part of the graph reachable from `entry`, as an
+    /// [`induced_subgraph`](Cfg::induced_subgraph): nodes renumbered
+    /// densely, with the original-to-new mapping. Dead regions then add
+    /// nothing to the committed edge table. Declared entry points that
+    /// survive are carried over, renumbered. An `entry` outside the graph
+    /// reaches nothing and leaves an empty graph.
+    ///
+    /// Paths must be remapped with [`remap_path`](crate::exe_path::remap_path)
+    /// before proving.
+    pub fn remove_unreachable(&self, entry: NodeId) -> (Cfg, BTreeMap<NodeId, NodeId>) {
+        let dead: BTreeSet<NodeId> = self.unreachable_from([entry]).into_iter().collect();
+        let live: BTreeSet<NodeId> = self.nodes().filter(|n| !dead.contains(n)).collect();
+        let (mut sub, mapping) = self.induced_subgraph(&live);
+        if let Some(entries) = &self.entries {
+            sub = sub.with_entries(entries.iter().filter_map(|e| mapping.get(e).copied()));
         }
+        (sub, mapping)
+    }
 
-        let n = (max_id as usize) + 1;
-        let mut succ: Vec<Vec<u32>> = vec![Vec::new(); n];
+    /// Collapse every maximal straight-line chain `v0 -> v1 -> .. -> vk`
+    /// into one super-node. A node joins its predecessor's chain when it is
+    /// that predecessor's only successor and the predecessor is its only
+    /// predecessor. Super-nodes are numbered densely in ascending order of
+    /// their first original node; the returned map gives each one's
+    /// original sequence. A cycle made only of such nodes has no first node
+    /// and stays uncontracted. Declared entry points are not carried over.
+    ///
+    /// Soundness: a proof over the contracted CFG attests to super-node
+    /// transitions only. It speaks for the original CFG because each chain
+    /// has one way in (at `v0`) and one way out (from `vk`), so a walk of
+    /// super-nodes expands to exactly one walk of original edges, by
+    /// replacing each super-node with its sequence. Paths must be converted
+    /// with [`contract_path`](crate::exe_path::contract_path) and expanded
+    /// back through the same map.
+    pub fn contract_chains(&self) -> (Cfg, BTreeMap<NodeId, Vec<NodeId>>) {
+        // `v` extends the chain of its only predecessor
+        let continues = |v: NodeId| {
+            let p = self.predecessors(v);
+            p.len() == 1 && p[0] != v && self.successors(p[0]).len() == 1
+        };
 
-        // Fill successors
-        for (src, vs) in raw.into_iter() {
-            succ[src as usize] = vs;
+        let mut in_chain = BTreeSet::new();
+        let mut chains: Vec<Vec<NodeId>> = Vec::new();
+        for head in self.nodes().filter(|&v| !continues(v)) {
+            let mut chain = vec![head];
+            in_chain.insert(head);
+            while let &[next] = self.successors(*chain.last().expect("chain starts non-empty")) {
+                if !continues(next) || !in_chain.insert(next) {
+                    break;
+                }
+                chain.push(next);
+            }
+            chains.push(chain);
         }
+        // nodes on cycles with no chain head
+        chains.extend(self.nodes().filter(|v| !in_chain.contains(v)).map(|v| vec![v]));
+        chains.sort_unstable_by_key(|c| c[0]);
 
-        // Build predecessors
-        let mut pred: Vec<Vec<u32>> = vec![Vec::new(); n];
-        for (u, vs) in succ.iter().enumerate() {
-            let u32u = u as u32;
-            for &v in vs {
-                pred[v as usize].push(u32u);
+        let mut super_of = BTreeMap::new();
+        for (s, chain) in chains.iter().enumerate() {
+            for &v in chain {
+                super_of.insert(v, s as NodeId);
             }
         }
+        let contracted = Cfg::from_adjacency(chains.iter().enumerate().map(|(s, chain)| {
+            let last = *chain.last().expect("chain starts non-empty");
+            let succ = self.successors(last).iter().map(|w| super_of[w]).collect();
+            (s as NodeId, succ)
+        }));
+        let mapping = chains.into_iter().enumerate().map(|(s, chain)| (s as NodeId, chain)).collect();
+        (contracted, mapping)
+    }
 
-        Cfg { succ, pred }
+    /// Nodes that are the target of some edge but never appear as a source
+    /// line. Usually a typo in the adjacency file: the edge leads to an
+    /// empty node that can never continue the path. Sorted ascending.
+    pub fn dangling_targets(&self) -> Vec<NodeId> {
+        self.nodes().filter(|v| !self.declared.contains(v)).collect()
     }
 
-    /// Build a CFG from a whitespace-separated adjacency list file.
-    /// Each non-empty line: `src dst0 dst1 ...`
-    /// Inline comments after '#' allowed.
-    pub fn from_file(path: &str) -> Result<Self, String> {
-        let contents = fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    /// Nodes with an edge to themselves, sorted ascending.
+    pub fn self_loops(&self) -> Vec<NodeId> {
+        self.nodes().filter(|&v| self.successors(v).contains(&v)).collect()
+    }
+
+    /// Maximum number of successors among all nodes (out-degree)
+    pub fn max_successors(&self) -> usize {
+        self.succ.values().map(|v| v.len()).max().unwrap_or(0)
+    }
+}
 
-        let mut adj: Vec<(u32, Vec<u32>)> = Vec::new();
+/// Parse adjacency list text without touching the filesystem.
+/// Each non-empty line: `src dst0 dst1 ...`, `entry n0 n1 ...` to
+/// declare legal start nodes (may repeat), or `enter n call ret jump` with
+/// one or more of the kinds to set `n`'s entry policy.
+/// Inline comments after '#' allowed.
+impl FromStr for Cfg {
+    type Err = String;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut adj: Vec<(NodeId, Vec<NodeId>)> = Vec::new();
+        let mut entries: Option<BTreeSet<NodeId>> = None;
+        let mut policies = BTreeMap::new();
 
         for (lineno, raw) in contents.lines().enumerate() {
             let mut line = raw.trim();
@@ -65,52 +516,86 @@ impl Cfg {
                 continue;
             }
             if let Some(i) = line.find('#') {
-                line = &line[..i].trim();
+                line = line[..i].trim();
                 if line.is_empty() { continue; }
             }
 
             let parts: Vec<&str> = line.split_whitespace().collect();
             if parts.is_empty() { continue; }
 
-            let src: u32 = parts[0].parse()
-                .map_err(|_| format!("Line {}: invalid node '{}'", lineno + 1, parts[0]))?;
+            if parts[0] == "entry" {
+                if parts.len() == 1 {
+                    return Err(format!("Line {}: 'entry' expects at least one node", lineno + 1));
+                }
+                let set = entries.get_or_insert_with(BTreeSet::new);
+                for tok in &parts[1..] {
+                    let v = parse_node(tok)
+                        .ok_or_else(|| format!("Line {}: invalid entry node '{}'", lineno + 1, tok))?;
+                    set.insert(v);
+                }
+                continue;
+            }
+            if parts[0] == "enter" {
+                let node = parts
+                    .get(1)
+                    .and_then(|tok| parse_node(tok))
+                    .ok_or_else(|| format!("Line {}: 'enter' expects a node and entry kinds", lineno + 1))?;
+                let policy = EntryPolicy::parse(parts[2..].iter().copied())
+                    .map_err(|e| format!("Line {}: {}", lineno + 1, e))?;
+                policies.insert(node, policy);
+                continue;
+            }
+
+            let src = parse_node(parts[0])
+                .ok_or_else(|| format!("Line {}: invalid node '{}'", lineno + 1, parts[0]))?;
 
             let mut succs = Vec::new();
             for tok in parts.iter().skip(1) {
-                let v = tok.parse::<u32>()
-                    .map_err(|_| format!("Line {}: invalid successor '{}'", lineno + 1, tok))?;
+                let v = parse_node(tok)
+                    .ok_or_else(|| format!("Line {}: invalid successor '{}'", lineno + 1, tok))?;
                 succs.push(v);
             }
 
             adj.push((src, succs));
         }
 
-        Ok(Self::from_adjacency(adj))
-    }
-
-    pub fn len(&self) -> usize { self.succ.len() }
-    pub fn is_empty(&self) -> bool { self.succ.is_empty() }
-
-    pub fn nodes(&self) -> impl Iterator<Item = u32> + '_ {
-        (0..self.succ.len()).map(|i| i as u32)
+        let mut cfg = Self::from_adjacency(adj);
+        cfg.entries = entries;
+        cfg.policies = policies;
+        Ok(cfg)
     }
+}
 
-    pub fn successors(&self, n: u32) -> &[u32] {
-        self.succ.get(n as usize).map(|v| v.as_slice()).unwrap_or(&[])
-    }
+/// A decimal node ID no larger than [`MAX_NODE_ID`].
+fn parse_node(tok: &str) -> Option<NodeId> {
+    tok.parse::<NodeId>().ok().filter(|&n| n <= MAX_NODE_ID)
+}
 
-    pub fn predecessors(&self, n: u32) -> &[u32] {
-        self.pred.get(n as usize).map(|v| v.as_slice()).unwrap_or(&[])
+/// Add an empty successor list for every edge target without one, so
+/// `succ` holds every node, and return the predecessor lists of its nodes.
+fn build_predecessors(succ: &mut BTreeMap<NodeId, Vec<NodeId>>) -> BTreeMap<NodeId, Vec<NodeId>> {
+    let targets: BTreeSet<NodeId> = succ.values().flatten().copied().collect();
+    for v in targets {
+        succ.entry(v).or_default();
     }
-
-    pub fn edges(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
-        self.succ.iter().enumerate().flat_map(|(u, vs)| {
-            vs.iter().copied().map(move |v| (u as u32, v))
-        })
+    let mut pred: BTreeMap<NodeId, Vec<NodeId>> = succ.keys().map(|&v| (v, Vec::new())).collect();
+    for (&u, vs) in succ.iter() {
+        for v in vs {
+            pred.get_mut(v).expect("every target has an entry").push(u);
+        }
     }
+    pred
+}
 
-    /// Maximum number of successors among all nodes (out-degree)
-    pub fn max_successors(&self) -> usize {
-        self.succ.iter().map(|v| v.len()).max().unwrap_or(0)
+/// Compact summary, e.g. `Cfg { nodes: 10, edges: 12, max_out: 2 }`.
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cfg {{ nodes: {}, edges: {}, max_out: {} }}",
+            self.len(),
+            self.edges().count(),
+            self.max_successors()
+        )
     }
 }
@@ -0,0 +1,86 @@
+// commitment.rs
+use alloc::{vec, vec::Vec};
+
+use winterfell::{
+    Trace, TraceTable,
+    crypto::ElementHasher,
+    math::{FieldElement, fields::f64::BaseElement},
+};
+
+use crate::{air::encode_node, cfg::Cfg};
+
+impl Cfg {
+    /// Every edge as `(encode_node(src), encode_node(dst))`, sorted by
+    /// `(src, dst)` with duplicates removed, so equal edge sets give equal
+    /// tables regardless of adjacency order. This is the input to
+    /// [`Cfg::commitment`].
+    pub fn edge_table(&self) -> Vec<(BaseElement, BaseElement)> {
+        let mut edges: Vec<_> = self.edges().collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges.into_iter().map(|(u, v)| (encode_node(u), encode_node(v))).collect()
+    }
+
+    /// BLAKE3 hash of [`Cfg::edge_table`], each element as 8 little-endian
+    /// bytes in table order, followed by [`Cfg::policy_table`] the same way
+    /// if the CFG has entry policies (so one without keeps its commitment).
+    ///
+    /// This is the CFG commitment carried by `PublicInputs::cfg_commitment`
+    /// and is always plain BLAKE3, whatever hash the prover uses for its
+    /// commitments and Fiat-Shamir (`verifier::HashFn`), so tools outside the
+    /// prover can recompute it from the edge list (and policies) alone.
+    pub fn commitment(&self) -> [u8; 32] {
+        blake3::hash(&self.commitment_bytes()).into()
+    }
+
+    /// [`Cfg::edge_table`], then [`Cfg::policy_table`], flattened to
+    /// `u0, v0, u1, v1, ..` and hashed as field elements with `H`, for
+    /// tooling that wants the digest in a field-native hash such as Rescue. Even for BLAKE3 this differs from
+    /// [`Cfg::commitment`], and it is not accepted as `cfg_commitment`.
+    pub fn commitment_with<H: ElementHasher<BaseField = BaseElement>>(&self) -> H::Digest {
+        let elements: Vec<BaseElement> =
+            self.edge_table().into_iter().chain(self.policy_table()).flat_map(|(u, v)| [u, v]).collect();
+        H::hash_elements(&elements)
+    }
+
+    /// Every entry policy as `(encode_node(n), EntryPolicy bits)`, by
+    /// node; the part of [`Cfg::commitment`] after the edge table. The bits
+    /// are `call | ret << 1 | jump << 2`.
+    pub fn policy_table(&self) -> Vec<(BaseElement, BaseElement)> {
+        self.entry_policies()
+            .iter()
+            .map(|(&n, p)| (encode_node(n), BaseElement::new(p.bits() as u64)))
+            .collect()
+    }
+
+    fn commitment_bytes(&self) -> Vec<u8> {
+        let table = self.edge_table();
+        let policies = self.policy_table();
+        let mut bytes = Vec::with_capacity((table.len() + policies.len()) * 16);
+        for (u, v) in table.into_iter().chain(policies) {
+            bytes.extend_from_slice(&u.as_int().to_le_bytes());
+            bytes.extend_from_slice(&v.as_int().to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// BLAKE3 hash of every cell of `trace`, row by row, each as 8 little-endian
+/// bytes: a fingerprint of exactly what was proven, for audit logs.
+///
+/// The trace is a deterministic function of the path, the nonce, the
+/// forbidden set and the CFG, including the order of each successor list
+/// (it picks the neighbor columns). Fingerprints of the same execution
+/// therefore only agree if the CFG was loaded with the same successor
+/// order, e.g. after `Cfg::canonicalize`. Proof options play no part.
+pub fn trace_fingerprint(trace: &TraceTable<BaseElement>) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    let mut row = vec![BaseElement::ZERO; trace.width()];
+    for r in 0..trace.length() {
+        trace.read_row_into(r, &mut row);
+        for e in &row {
+            hasher.update(&e.as_int().to_le_bytes());
+        }
+    }
+    *hasher.finalize().as_bytes()
+}
@@ -0,0 +1,164 @@
+// config.rs
+//! Run settings from a TOML file, so repeated runs (CI in particular) can
+//! keep their proof parameters pinned in one place:
+//!
+//! ```toml
+//! cfg = "graph.cfg"
+//! path = "exec.path"
+//! num_queries = 32
+//! blowup = 16
+//! grinding = 0
+//! field_ext = "cubic"
+//! fri_folding = 4
+//! fri_max_remainder = 255
+//! nonce = 123
+//! deterministic = true
+//! ```
+//!
+//! Every key is optional; a missing one falls back to the CLI default.
+
+use std::fs;
+
+use serde::Deserialize;
+use winterfell::{BatchingMethod, FieldExtension, ProofOptions};
+
+use crate::error::StarkraError;
+
+pub const DEFAULT_NUM_QUERIES: usize = 20;
+pub const DEFAULT_BLOWUP: usize = 64;
+pub const DEFAULT_FRI_FOLDING: usize = 4;
+pub const DEFAULT_FRI_MAX_REMAINDER: usize = 255;
+pub const DEFAULT_NONCE: u32 = 123;
+
+/// Field extension used for the composition and DEEP polynomials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldExt {
+    None,
+    Quadratic,
+    Cubic,
+}
+
+impl FieldExt {
+    pub fn parse(s: &str) -> Result<Self, StarkraError> {
+        match s {
+            "none" => Ok(FieldExt::None),
+            "quadratic" => Ok(FieldExt::Quadratic),
+            "cubic" => Ok(FieldExt::Cubic),
+            _ => Err(StarkraError::Parse(format!("field_ext must be none, quadratic or cubic, got '{}'", s))),
+        }
+    }
+}
+
+impl From<FieldExt> for FieldExtension {
+    fn from(e: FieldExt) -> Self {
+        match e {
+            FieldExt::None => FieldExtension::None,
+            FieldExt::Quadratic => FieldExtension::Quadratic,
+            FieldExt::Cubic => FieldExtension::Cubic,
+        }
+    }
+}
+
+/// Settings for a prove-and-verify run; `None` means "not set here".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StarkraConfig {
+    pub num_queries: Option<usize>,
+    pub blowup: Option<usize>,
+    pub grinding: Option<u32>,
+    pub field_ext: Option<FieldExt>,
+    pub fri_folding: Option<usize>,
+    pub fri_max_remainder: Option<usize>,
+    pub nonce: Option<u32>,
+    /// Prove with `prover::prove_deterministic`, for byte-identical
+    /// proofs across runs.
+    pub deterministic: Option<bool>,
+    /// CFG file.
+    pub cfg: Option<String>,
+    /// Execution path file.
+    pub path: Option<String>,
+}
+
+impl StarkraConfig {
+    pub fn from_toml(text: &str) -> Result<Self, StarkraError> {
+        toml::from_str(text).map_err(|e| StarkraError::Parse(e.to_string()))
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, StarkraError> {
+        let text = fs::read_to_string(path).map_err(|e| StarkraError::Io(format!("Failed to read '{}': {}", path, e)))?;
+        Self::from_toml(&text).map_err(|e| match e {
+            StarkraError::Parse(msg) => StarkraError::Parse(format!("'{}': {}", path, msg)),
+            e => e,
+        })
+    }
+
+    /// `self` with every field `over` sets replaced by `over`'s value.
+    pub fn merge(self, over: StarkraConfig) -> Self {
+        StarkraConfig {
+            num_queries: over.num_queries.or(self.num_queries),
+            blowup: over.blowup.or(self.blowup),
+            grinding: over.grinding.or(self.grinding),
+            field_ext: over.field_ext.or(self.field_ext),
+            fri_folding: over.fri_folding.or(self.fri_folding),
+            fri_max_remainder: over.fri_max_remainder.or(self.fri_max_remainder),
+            nonce: over.nonce.or(self.nonce),
+            deterministic: over.deterministic.or(self.deterministic),
+            cfg: over.cfg.or(self.cfg),
+            path: over.path.or(self.path),
+        }
+    }
+
+    pub fn num_queries(&self) -> usize {
+        self.num_queries.unwrap_or(DEFAULT_NUM_QUERIES)
+    }
+
+    pub fn blowup(&self) -> usize {
+        self.blowup.unwrap_or(DEFAULT_BLOWUP)
+    }
+
+    pub fn grinding(&self) -> u32 {
+        self.grinding.unwrap_or(0)
+    }
+
+    pub fn nonce(&self) -> u32 {
+        self.nonce.unwrap_or(DEFAULT_NONCE)
+    }
+
+    pub fn deterministic(&self) -> bool {
+        self.deterministic.unwrap_or(false)
+    }
+
+    /// `(folding_factor, max_remainder_degree)`, checked against what
+    /// winterfell accepts: folding 2, 4, 8 or 16 and a remainder degree of
+    /// the form 2^k - 1, at most 255.
+    pub fn fri_options(&self) -> Result<(usize, usize), StarkraError> {
+        let folding = self.fri_folding.unwrap_or(DEFAULT_FRI_FOLDING);
+        if !matches!(folding, 2 | 4 | 8 | 16) {
+            return Err(StarkraError::Parse(format!("fri_folding must be 2, 4, 8 or 16, got {}", folding)));
+        }
+        let remainder = self.fri_max_remainder.unwrap_or(DEFAULT_FRI_MAX_REMAINDER);
+        if remainder > 255 || !(remainder + 1).is_power_of_two() {
+            return Err(StarkraError::Parse(format!(
+                "fri_max_remainder must be 2^k - 1 and at most 255, got {}",
+                remainder
+            )));
+        }
+        Ok((folding, remainder))
+    }
+
+    /// The proof options these settings describe, with defaults filled in.
+    pub fn proof_options(&self) -> Result<ProofOptions, StarkraError> {
+        let (folding, remainder) = self.fri_options()?;
+        Ok(ProofOptions::new(
+            self.num_queries(),
+            self.blowup(),
+            self.grinding(),
+            self.field_ext.unwrap_or(FieldExt::Cubic).into(),
+            folding,
+            remainder,
+            BatchingMethod::Linear,
+            BatchingMethod::Linear,
+        ))
+    }
+}
@@ -0,0 +1,407 @@
+// divergence.rs
+//! Proofs that two paths over one CFG agree up to a public step and then
+//! part ways: the same start, the same nodes through step `d`, and
+//! different nodes at step `d + 1`.
+//!
+//! Both paths go in one trace, side by side:
+//!
+//! | columns | contents |
+//! |---|---|
+//! | `0..w` | path A, in the usual [`TraceLayout`] for the CFG (`w` wide) |
+//! | `w..2w` | path B, in the same layout |
+//! | `2w` | `prefix`: 1 on rows `0..=d`, 0 after |
+//! | `2w + 1` | `diff_inv`: on row `d + 1`, `1 / (a_current - b_current)` |
+//!
+//! Each half is checked by the constraints and assertions of its own
+//! [`PublicInputs`], so A and B are each a valid path with their own
+//! endpoints, nonce and options. Three constraints tie them together:
+//!
+//! - `(p - p') * (p - p' - 1)`: `prefix` stays or drops by one per row;
+//! - `p * (a_current - b_current)`: the paths agree wherever `prefix` is
+//!   non-zero;
+//! - `(p - p') * ((a_current' - b_current') * diff_inv' - 1)`: on the row
+//!   after a drop the paths differ.
+//!
+//! The divergence assertion pins `prefix` to 1 on row `d` and to 0 on
+//! row `d + 1`. Stepping back from row `d`, `prefix` can only count up
+//! (2, 3, ...), which is never 0, so rows `0..=d` agree; the drop at
+//! `d + 1` forces different nodes there. `valid` is also asserted to be 1
+//! on row `d + 1` in both halves, so the difference is between real steps
+//! and not between one path's end and the other's next step. Rows after
+//! `d + 1` are unconstrained: the paths may meet again, e.g. at a common
+//! exit.
+
+#[cfg(feature = "prover")]
+use alloc::{format, vec};
+use alloc::vec::Vec;
+
+use winterfell::{
+    AcceptableOptions, Air, AirContext, Assertion, EvaluationFrame, Proof, ProofOptions, TraceInfo,
+    TransitionConstraintDegree,
+    crypto::VectorCommitment,
+    math::{FieldElement, ToElements, fields::f64::BaseElement},
+    verify,
+};
+#[cfg(feature = "prover")]
+use winterfell::{
+    CompositionPoly, CompositionPolyTrace, DefaultConstraintCommitment, DefaultConstraintEvaluator,
+    DefaultTraceLde, PartitionOptions, Prover, StarkDomain, Trace, TracePolyTable, TraceTable,
+    matrix::ColMatrix,
+};
+
+use crate::{
+    air::{PublicInputs, StarkraAir, TraceLayout},
+    cfg::Cfg,
+    error::StarkraError,
+    verifier::{DefaultVC, HashFn, RandCoin},
+};
+#[cfg(feature = "prover")]
+use crate::{air::NeighborEncoding, exe_path::Step, prover::pub_inputs_from};
+
+/// Columns after the two halves: `prefix` and `diff_inv`.
+pub const DIVERGENCE_COLUMNS: usize = 2;
+
+/// Public inputs of a divergence proof: those of each path, and the last
+/// step `d` on which they agree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivergenceInputs {
+    pub a: PublicInputs,
+    pub b: PublicInputs,
+    pub divergence_step: usize,
+}
+
+impl DivergenceInputs {
+    /// The step after the divergence step must be a row of a trace of
+    /// `trace_length` rows, and a real row of either path with a step
+    /// count.
+    pub fn check_divergence_step(&self, trace_length: usize) -> Result<(), StarkraError> {
+        let limit = [&self.a, &self.b]
+            .iter()
+            .filter_map(|pi| pi.num_steps)
+            .fold(trace_length, usize::min)
+            .saturating_sub(1);
+        if self.divergence_step >= limit {
+            return Err(StarkraError::Divergence { step: self.divergence_step, limit });
+        }
+        Ok(())
+    }
+}
+
+impl ToElements<BaseElement> for DivergenceInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = self.a.to_elements();
+        elements.extend(self.b.to_elements());
+        elements.push(BaseElement::new(self.divergence_step as u64));
+        elements
+    }
+}
+
+/// Column indices of a divergence trace for halves of `half` columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DivergenceLayout {
+    pub half: TraceLayout,
+    pub prefix: usize,
+    pub diff_inv: usize,
+    pub width: usize,
+}
+
+impl DivergenceLayout {
+    pub fn new(half: TraceLayout) -> Self {
+        let prefix = 2 * half.width;
+        Self { half, prefix, diff_inv: prefix + 1, width: prefix + DIVERGENCE_COLUMNS }
+    }
+
+    /// First column of path B.
+    pub fn b_offset(&self) -> usize {
+        self.half.width
+    }
+}
+
+impl From<&Cfg> for DivergenceLayout {
+    fn from(cfg: &Cfg) -> Self {
+        Self::new(TraceLayout::from(cfg))
+    }
+}
+
+pub struct DivergenceAir {
+    context: AirContext<BaseElement>,
+    a: StarkraAir,
+    b: StarkraAir,
+    divergence_step: usize,
+    layout: DivergenceLayout,
+}
+
+impl DivergenceAir {
+    /// Constraints per half.
+    fn half_constraints(&self) -> usize {
+        self.a.context().num_main_transition_constraints()
+    }
+}
+
+impl Air for DivergenceAir {
+    type BaseField = BaseElement;
+    type PublicInputs = DivergenceInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        let width = trace_info.width() - DIVERGENCE_COLUMNS;
+        let layout = DivergenceLayout::new(TraceLayout::from_width(width / 2));
+        let half_info = TraceInfo::new(layout.half.width, trace_info.length());
+        let half = |pi: PublicInputs| {
+            let info = StarkraAir::constraint_info_for(
+                layout.half.max_succ,
+                pi.segment_nonces.len(),
+                pi.forbidden.len(),
+            );
+            let air = StarkraAir::new(half_info.clone(), pi, options.clone());
+            (info.degrees, air)
+        };
+        let (a_degrees, a) = half(pub_inputs.a);
+        let (b_degrees, b) = half(pub_inputs.b);
+
+        let degrees = a_degrees
+            .into_iter()
+            .chain(b_degrees)
+            .chain([2, 2, 3])
+            .map(TransitionConstraintDegree::new)
+            .collect();
+        let num_assertions = a.context().num_assertions() + b.context().num_assertions() + 4;
+        let context = AirContext::new(trace_info, degrees, num_assertions, options);
+        Self { context, a, b, divergence_step: pub_inputs.divergence_step, layout }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let curr = frame.current();
+        let next = frame.next();
+        let l = &self.layout;
+        let (w, n) = (l.half.width, self.half_constraints());
+        self.a.evaluate_slices(&curr[..w], &next[..w], &mut result[..n]);
+        self.b.evaluate_slices(&curr[w..2 * w], &next[w..2 * w], &mut result[n..2 * n]);
+
+        let cur = l.half.current;
+        let drop = curr[l.prefix] - next[l.prefix];
+        result[2 * n] = drop * (drop - E::ONE);
+        result[2 * n + 1] = curr[l.prefix] * (curr[cur] - curr[w + cur]);
+        result[2 * n + 2] = drop * ((next[cur] - next[w + cur]) * next[l.diff_inv] - E::ONE);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let l = &self.layout;
+        let d = self.divergence_step;
+        let offset = |a: Assertion<BaseElement>, by: usize| {
+            Assertion::single(a.column() + by, a.first_step(), a.values()[0])
+        };
+        let mut assertions: Vec<_> = self.a.assertions();
+        assertions.extend(self.b.assertions().into_iter().map(|a| offset(a, l.b_offset())));
+        assertions.extend([
+            Assertion::single(l.prefix, d, BaseElement::ONE),
+            Assertion::single(l.prefix, d + 1, BaseElement::ZERO),
+            Assertion::single(l.half.valid, d + 1, BaseElement::ONE),
+            Assertion::single(l.b_offset() + l.half.valid, d + 1, BaseElement::ONE),
+        ]);
+        assert_eq!(
+            assertions.len(),
+            self.context.num_assertions(),
+            "get_assertions is out of step with the assertion count given to AirContext"
+        );
+        assertions
+    }
+}
+
+/// Verify a proof that two paths over `cfg` diverge after
+/// `inputs.divergence_step`. Each half's public inputs are checked as
+/// `verify_path` checks a single path's, except for CFG commitments,
+/// which are not carried. Halves with segment nonces are rejected with
+/// `SegmentRow`: both paths are single sessions.
+pub fn verify_divergence(
+    proof: Proof,
+    inputs: DivergenceInputs,
+    cfg: &Cfg,
+    acceptable: &AcceptableOptions,
+) -> Result<(), StarkraError> {
+    verify_divergence_with_vc::<DefaultVC>(proof, inputs, cfg, acceptable)
+}
+
+/// `verify_divergence` for a proof committed with a non-default `VC`.
+pub fn verify_divergence_with_vc<VC: VectorCommitment<HashFn>>(
+    proof: Proof,
+    inputs: DivergenceInputs,
+    cfg: &Cfg,
+    acceptable: &AcceptableOptions,
+) -> Result<(), StarkraError> {
+    let expected = DivergenceLayout::from(cfg).width;
+    let actual = proof.trace_info().width();
+    if actual != expected {
+        return Err(StarkraError::WidthMismatch { expected, actual });
+    }
+    let length = proof.trace_info().length();
+    for pi in [&inputs.a, &inputs.b] {
+        // each half is a single session; a segment's `valid` assertions
+        // could land on the divergence row's
+        if let Some(&(row, _)) = pi.segment_nonces.first() {
+            return Err(StarkraError::SegmentRow { row, limit: length });
+        }
+        if !cfg.reaches(pi.start.as_int(), pi.end.as_int()) {
+            return Err(StarkraError::UnreachableEndpoints { start: pi.start.as_int(), end: pi.end.as_int() });
+        }
+        pi.check_distinct_nonces()?;
+        pi.check_segments(length)?;
+        pi.check_forbidden_endpoints()?;
+        pi.check_step_count(length)?;
+        pi.check_waypoints(length)?;
+    }
+    inputs.check_divergence_step(length)?;
+    verify::<DivergenceAir, HashFn, RandCoin, VC>(proof, inputs, acceptable).map_err(StarkraError::Verification)
+}
+
+/// Build the divergence trace of paths `a` and `b` over `cfg`, both
+/// padded to the same length, for divergence step `divergence_step`.
+/// Fails with `Trace` if the paths do not agree on steps
+/// `0..=divergence_step` and differ on the next one.
+#[cfg(feature = "prover")]
+pub fn build_divergence_trace(
+    a: Vec<Step>,
+    b: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    divergence_step: usize,
+) -> Result<TraceTable<BaseElement>, StarkraError> {
+    let node = |path: &[Step], i: usize| path.get(i).and_then(|s| s.addrs.first()).copied();
+    let agrees = (0..=divergence_step).all(|i| node(&a, i).is_some() && node(&a, i) == node(&b, i));
+    let (na, nb) = (node(&a, divergence_step + 1), node(&b, divergence_step + 1));
+    if !agrees || na.is_none() || nb.is_none() || na == nb {
+        return Err(StarkraError::Trace(format!("the paths do not diverge after step {}", divergence_step)));
+    }
+
+    let rows = a.len().max(b.len());
+    let ta = StarkraAir::build_trace_min_rows(a, cfg.clone(), nonce, rows);
+    let tb = StarkraAir::build_trace_min_rows(b, cfg.clone(), nonce, rows);
+    let layout = DivergenceLayout::from(cfg);
+    let length = ta.length();
+
+    let mut columns = Vec::with_capacity(layout.width);
+    columns.extend((0..ta.width()).map(|c| ta.get_column(c).to_vec()));
+    columns.extend((0..tb.width()).map(|c| tb.get_column(c).to_vec()));
+    let mut prefix = vec![BaseElement::ZERO; length];
+    prefix[..=divergence_step].fill(BaseElement::ONE);
+    let cur = layout.half.current;
+    let mut diff_inv = vec![BaseElement::ZERO; length];
+    let r = divergence_step + 1;
+    diff_inv[r] = (ta.get(cur, r) - tb.get(cur, r)).inv();
+    columns.push(prefix);
+    columns.push(diff_inv);
+    Ok(TraceTable::init(columns))
+}
+
+/// Proves the trace of [`build_divergence_trace`].
+#[cfg(feature = "prover")]
+pub struct DivergenceProver<VC = DefaultVC> {
+    options: ProofOptions,
+    divergence_step: usize,
+    _vc: core::marker::PhantomData<fn() -> VC>,
+}
+
+#[cfg(feature = "prover")]
+impl DivergenceProver {
+    pub fn new(options: ProofOptions, divergence_step: usize) -> Self {
+        Self::with_vector_commitment(options, divergence_step)
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<VC> DivergenceProver<VC> {
+    pub fn with_vector_commitment(options: ProofOptions, divergence_step: usize) -> Self {
+        Self { options, divergence_step, _vc: core::marker::PhantomData }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<VC> Prover for DivergenceProver<VC>
+where
+    VC: VectorCommitment<HashFn> + Sync,
+{
+    type BaseField = BaseElement;
+    type Air = DivergenceAir;
+    type Trace = TraceTable<Self::BaseField>;
+    type HashFn = HashFn;
+    type VC = VC;
+    type RandomCoin = RandCoin;
+    type TraceLde<E: FieldElement<BaseField = Self::BaseField>> = DefaultTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> DivergenceInputs {
+        let layout = DivergenceLayout::new(TraceLayout::from_width((trace.width() - DIVERGENCE_COLUMNS) / 2));
+        let w = layout.half.width;
+        let half = |from: usize| {
+            let columns = (from..from + w).map(|c| trace.get_column(c).to_vec()).collect();
+            pub_inputs_from(&ColMatrix::new(columns), &[], false, NeighborEncoding::Product)
+        };
+        DivergenceInputs { a: half(0), b: half(w), divergence_step: self.divergence_step }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<Self::BaseField>,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        DefaultTraceLde::new(trace_info, main_trace, domain, partition_options)
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<winterfell::AuxRandElements<E>>,
+        composition_coefficients: winterfell::ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        DefaultConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+}
+
+/// Build the divergence trace of `a` and `b` and prove it.
+#[cfg(feature = "prover")]
+pub fn prove_divergence(
+    a: Vec<Step>,
+    b: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    divergence_step: usize,
+    options: ProofOptions,
+) -> Result<(Proof, DivergenceInputs), StarkraError> {
+    let trace = build_divergence_trace(a, b, cfg, nonce, divergence_step)?;
+    let prover = DivergenceProver::new(options, divergence_step);
+    let inputs = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).map_err(StarkraError::Prove)?;
+    Ok((proof, inputs))
+}
@@ -0,0 +1,280 @@
+use alloc::string::String;
+use core::fmt;
+
+use winterfell::{ProverError, TraceInfo, VerifierError};
+
+use crate::cfg::{EntryPolicy, NodeId};
+use crate::exe_path::JmpType;
+
+/// Errors returned by the library API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StarkraError {
+    /// Malformed input (path or CFG encoding).
+    Parse(String),
+    /// Reading or writing a file failed.
+    Io(String),
+    /// The same nonce is bound to more than one segment.
+    NonceReuse(u64),
+    /// A further segment's first row is not a row of a trace of `limit`
+    /// rows, or leaves no real row and separator row before it.
+    SegmentRow { row: usize, limit: usize },
+    /// The claimed start or end is one of the forbidden nodes.
+    ForbiddenEndpoint(u64),
+    /// The proof's trace width does not match the layout of the CFG.
+    WidthMismatch { expected: usize, actual: usize },
+    /// The proof's trace width is not the width of any layout under its
+    /// claimed neighbor encoding.
+    InvalidWidth(usize),
+    /// The constraints of the public inputs (e.g. the `entry` constraint
+    /// of many entry-policy nodes) need a blowup factor of `required`,
+    /// above the proof options' `actual`.
+    BlowupTooSmall { required: usize, actual: usize },
+    /// The path starts at a node that is not a declared entry point of the CFG.
+    IllegalEntry(NodeId),
+    /// The claimed start/end are not nodes of the CFG, or no path connects them.
+    UnreachableEndpoints { start: u64, end: u64 },
+    /// The proof names a CFG (by commitment) other than the one given, or
+    /// none of the candidates.
+    CfgMismatch,
+    /// The proof carries no CFG commitment to match candidates against.
+    MissingCfgCommitment,
+    /// The proof's entry policies are not the CFG's.
+    EntryPolicyMismatch,
+    /// The public inputs stored with a proof are not the ones the caller
+    /// claims it proves.
+    PublicInputMismatch,
+    /// The trace for a path of `real_len` rows, padded to a power of two,
+    /// would be longer than the configured `max` rows. From the verifier,
+    /// `real_len` is the proof's trace length (see
+    /// `Acceptable::max_verify_rows`).
+    TraceTooLong { real_len: usize, max: usize },
+    /// The claimed step count is zero, does not fit the proof's trace with
+    /// its segment separators, or ends before the last segment starts.
+    StepCount { claimed: usize, trace_length: usize },
+    /// A waypoint is not on a row strictly between the first row and the
+    /// last real row (`limit`), where it would restate or overrun the
+    /// start/end assertions.
+    Waypoint { step: usize, limit: usize },
+    /// Two waypoints name the same step.
+    DuplicateWaypoint(usize),
+    /// A committed prefix of `len` nodes that is empty or longer than
+    /// `limit` (the committed sequence, or the proof's trace).
+    PrefixLength { len: usize, limit: usize },
+    /// The prefix node of `row` is not the claimed start or end node on
+    /// that row.
+    PrefixMismatch { row: usize },
+    /// The prefix does not open against the sequence root, or the public
+    /// inputs carry no root.
+    PrefixOpening,
+    /// A divergence step whose next row is not a row of the trace.
+    Divergence { step: usize, limit: usize },
+    /// `exe_path::cover_paths` cannot go on from `from`: no walk of jumps
+    /// reaches `to`, the start of input path `path`, nor the start of any
+    /// other path left to cover.
+    CoverGap { path: usize, from: NodeId, to: NodeId },
+    /// The STARK prover failed.
+    Prove(ProverError),
+    /// The proof was rejected by the STARK verifier.
+    Verification(VerifierError),
+    /// The trace does not satisfy the AIR (found without proving).
+    Trace(String),
+    /// The execution path is not a walk the AIR accepts over the CFG.
+    Path(PathError),
+}
+
+/// Why an execution path cannot be proven over a CFG. `step` indexes the
+/// parsed steps, including the leading `jump initial_node`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The path has no steps.
+    Empty,
+    /// The step carries the wrong number of addresses for its opcode.
+    Arity { step: usize, expected: usize, actual: usize },
+    /// A jump moves to a node that is not a successor of the previous one.
+    IllegalEdge { step: usize, from: NodeId, to: NodeId },
+    /// A ret returns to a node that is not a successor of the returning node,
+    /// even if it matches the shadow stack.
+    IllegalReturnEdge { step: usize, from: NodeId, to: NodeId },
+    /// A call lands on a node that is not a successor of the caller.
+    IllegalCallTarget { step: usize, caller: NodeId, target: NodeId },
+    /// A call's return address is not a successor of the caller.
+    IllegalReturnAddress { step: usize, caller: NodeId, ret_addr: NodeId },
+    /// A ret does not go back to the address on top of the shadow stack
+    /// (`None` when the stack is empty).
+    ReturnMismatch { step: usize, expected: Option<NodeId>, actual: NodeId },
+    /// The call at `step` never returns before the path ends.
+    UnreturnedCall { step: usize, ret_addr: NodeId },
+    /// The step names a node outside the subgraph the path is remapped to.
+    OutsideSubgraph { step: usize, node: NodeId },
+    /// The path enters or leaves a contracted chain somewhere other than its
+    /// ends, or takes a call or ret inside it.
+    PartialChain { step: usize, node: NodeId },
+}
+
+/// A step taken with a different jump type than the CFG declares for its
+/// edge (see `Cfg::check_edge_kinds`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeKindMismatch {
+    pub step: usize,
+    pub from: NodeId,
+    pub to: NodeId,
+    /// The kind declared for `from -> to`.
+    pub expected: JmpType,
+    /// The step's own jump type.
+    pub actual: JmpType,
+}
+
+/// A step into a node by a kind of step its entry policy rules out (see
+/// `Cfg::check_entry_policy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPolicyViolation {
+    pub step: usize,
+    pub node: NodeId,
+    /// The step's own jump type.
+    pub actual: JmpType,
+    pub allowed: EntryPolicy,
+}
+
+/// Why a path breaks a node-set policy (see `Cfg::check_policy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The path visits a node it must avoid, first at `step`.
+    Forbidden { step: usize, node: NodeId },
+    /// The path never visits a node it must pass through.
+    Missed { node: NodeId },
+}
+
+impl fmt::Display for StarkraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StarkraError::Parse(msg) => write!(f, "parse error: {}", msg),
+            StarkraError::Io(msg) => f.write_str(msg),
+            StarkraError::NonceReuse(n) => write!(f, "nonce {} is bound to more than one segment", n),
+            StarkraError::SegmentRow { row, limit } => {
+                write!(f, "a segment cannot start on row {} of a trace of {} rows", row, limit)
+            }
+            StarkraError::ForbiddenEndpoint(n) => write!(f, "endpoint {} is a forbidden node", n),
+            StarkraError::WidthMismatch { expected, actual } => {
+                write!(f, "proof trace width {} does not match the CFG layout width {}", actual, expected)
+            }
+            StarkraError::InvalidWidth(w) => {
+                write!(f, "proof trace width {} is not a layout of its neighbor encoding", w)
+            }
+            StarkraError::BlowupTooSmall { required, actual } => {
+                write!(f, "the constraints need a blowup factor of {}, above the {} in use", required, actual)
+            }
+            StarkraError::IllegalEntry(n) => write!(f, "node {} is not a declared entry point", n),
+            StarkraError::UnreachableEndpoints { start, end } => {
+                write!(f, "no path in the CFG leads from {} to {}", start, end)
+            }
+            StarkraError::CfgMismatch => f.write_str("the proof was made for a different CFG"),
+            StarkraError::MissingCfgCommitment => f.write_str("the proof carries no CFG commitment"),
+            StarkraError::EntryPolicyMismatch => f.write_str("the proof's entry policies are not the CFG's"),
+            StarkraError::PublicInputMismatch => {
+                f.write_str("the proof's public inputs are not the claimed public inputs")
+            }
+            StarkraError::TraceTooLong { real_len, max } => write!(
+                f,
+                "a path of {} rows needs a trace of {} rows, above the limit of {}",
+                real_len,
+                (*real_len).max(TraceInfo::MIN_TRACE_LENGTH).next_power_of_two(),
+                max
+            ),
+            StarkraError::StepCount { claimed, trace_length } => {
+                write!(f, "claimed step count {} does not fit a trace of {} rows", claimed, trace_length)
+            }
+            StarkraError::Waypoint { step, limit } => {
+                write!(f, "waypoint step {} is outside 1..{}", step, limit)
+            }
+            StarkraError::DuplicateWaypoint(step) => write!(f, "more than one waypoint at step {}", step),
+            StarkraError::PrefixLength { len, limit } => {
+                write!(f, "a prefix of {} nodes is outside 1..={}", len, limit)
+            }
+            StarkraError::PrefixMismatch { row } => {
+                write!(f, "the prefix node of row {} contradicts the claimed start or end", row)
+            }
+            StarkraError::PrefixOpening => f.write_str("the prefix does not open against the sequence root"),
+            StarkraError::Divergence { step, limit } => {
+                write!(f, "divergence step {} is outside 0..{}", step, limit)
+            }
+            StarkraError::CoverGap { path, from, to } => {
+                write!(f, "no walk of jumps leads from {} to {}, the start of path {}", from, to, path)
+            }
+            StarkraError::Prove(e) => write!(f, "proving failed: {}", e),
+            StarkraError::Verification(e) => write!(f, "verification failed: {}", e),
+            StarkraError::Trace(msg) => write!(f, "invalid trace: {}", msg),
+            StarkraError::Path(e) => write!(f, "invalid path: {}", e),
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => f.write_str("the path has no steps"),
+            PathError::Arity { step, expected, actual } => {
+                write!(f, "step {}: expected {} addresses, got {}", step, expected, actual)
+            }
+            PathError::IllegalEdge { step, from, to } => {
+                write!(f, "step {}: {} is not a successor of {}", step, to, from)
+            }
+            PathError::IllegalReturnEdge { step, from, to } => {
+                write!(f, "step {}: ret target {} is not a successor of {}", step, to, from)
+            }
+            PathError::IllegalCallTarget { step, caller, target } => {
+                write!(f, "step {}: call target {} is not a successor of {}", step, target, caller)
+            }
+            PathError::IllegalReturnAddress { step, caller, ret_addr } => {
+                write!(f, "step {}: return address {} is not a successor of {}", step, ret_addr, caller)
+            }
+            PathError::ReturnMismatch { step, expected: Some(e), actual } => {
+                write!(f, "step {}: ret to {} but the return address is {}", step, actual, e)
+            }
+            PathError::ReturnMismatch { step, expected: None, actual } => {
+                write!(f, "step {}: ret to {} with an empty call stack", step, actual)
+            }
+            PathError::UnreturnedCall { step, ret_addr } => {
+                write!(f, "step {}: call never returns to {}", step, ret_addr)
+            }
+            PathError::OutsideSubgraph { step, node } => {
+                write!(f, "step {}: node {} is outside the subgraph", step, node)
+            }
+            PathError::PartialChain { step, node } => {
+                write!(f, "step {}: node {} does not follow its contracted chain end to end", step, node)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::Forbidden { step, node } => write!(f, "step {}: visits forbidden node {}", step, node),
+            PolicyViolation::Missed { node } => write!(f, "never visits required node {}", node),
+        }
+    }
+}
+
+impl fmt::Display for EdgeKindMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "step {}: takes {} -> {} as {}, but the CFG declares it {}",
+            self.step, self.from, self.to, self.actual, self.expected
+        )
+    }
+}
+
+impl fmt::Display for EntryPolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {}: enters {} by {}, but its policy allows only {}", self.step, self.node, self.actual, self.allowed)
+    }
+}
+
+impl From<PathError> for StarkraError {
+    fn from(e: PathError) -> Self {
+        StarkraError::Path(e)
+    }
+}
+
+impl core::error::Error for StarkraError {}
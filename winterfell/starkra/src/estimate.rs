@@ -0,0 +1,130 @@
+// estimate.rs
+//! Rough proving cost from the trace shape and proof options alone, for
+//! deciding where to run a proof before building its trace.
+//!
+//! **The estimate is heuristic.** It counts FFT butterflies over the LDE
+//! domain and hash compressions for the commitments and queries, and turns
+//! them into a time with a single nanoseconds-per-operation constant. It
+//! ignores constraint evaluation, memory bandwidth, thread count and the
+//! field extension's exact cost, so expect it to be right to within a
+//! small factor, not to the second. Recalibrate with [`calibrate`] on the
+//! machine that will do the proving.
+
+use core::{fmt, time::Duration};
+
+use winterfell::{FieldExtension, ProofOptions};
+
+/// Nanoseconds per operation of [`ProveEstimate::ops`], measured with
+/// [`calibrate`] on a single core (2^12 rows, 12 columns, blowup 8,
+/// 32 queries, cubic extension, release build).
+pub const NS_PER_OP: f64 = 25.0;
+
+/// A hash compression (one 64-byte BLAKE3 block) counts as this many
+/// butterflies.
+const HASH_WEIGHT: u64 = 4;
+
+/// Operation counts behind a proving-time estimate; see
+/// [`estimate_prove_cost`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProveEstimate {
+    /// `trace_rows * blowup_factor`, the size of the domain everything is
+    /// committed over.
+    pub lde_rows: usize,
+    /// Butterflies of the trace and composition interpolations and
+    /// extensions.
+    pub fft_ops: u64,
+    /// Hash compressions building the trace, constraint and FRI Merkle
+    /// trees.
+    pub hash_ops: u64,
+    /// Hash compressions for the `num_queries` authentication paths.
+    pub query_ops: u64,
+    /// [`ProveEstimate::ops`] at [`NS_PER_OP`].
+    pub time: Duration,
+}
+
+impl ProveEstimate {
+    /// Weighted total: butterflies plus hash compressions, a compression
+    /// counting as several butterflies.
+    pub fn ops(&self) -> u64 {
+        self.fft_ops + HASH_WEIGHT * (self.hash_ops + self.query_ops)
+    }
+
+    /// The estimated time at a calibration of `ns_per_op` (see [`calibrate`]).
+    pub fn time_at(&self, ns_per_op: f64) -> Duration {
+        Duration::from_secs_f64(self.ops() as f64 * ns_per_op / 1e9)
+    }
+}
+
+/// `~2.3 s (1048576 LDE rows, 12345678 ops)`.
+impl fmt::Display for ProveEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~{:.1?} ({} LDE rows, {} ops)", self.time, self.lde_rows, self.ops())
+    }
+}
+
+/// Estimated work of proving a `trace_rows` x `trace_cols` trace with
+/// `options`, without building anything. `trace_rows` is rounded up to
+/// the power of two the trace would be padded to.
+///
+/// Each trace column is interpolated over the trace domain and extended
+/// over the `trace_rows * blowup_factor` LDE domain; the composition
+/// polynomial (taken as one column per degree of field extension) goes
+/// through the same. Every LDE row is then hashed into the trace and
+/// constraint trees, FRI commits to layers shrinking by the folding
+/// factor, and each of the `num_queries` queries opens a path in every
+/// tree. Heuristic; see the module docs.
+pub fn estimate_prove_cost(trace_rows: usize, trace_cols: usize, options: &ProofOptions) -> ProveEstimate {
+    let n = trace_rows.next_power_of_two().max(8) as u64;
+    let lde = n * options.blowup_factor() as u64;
+    let log = |x: u64| x.ilog2() as u64;
+    let ext = match options.field_extension() {
+        FieldExtension::None => 1,
+        FieldExtension::Quadratic => 2,
+        FieldExtension::Cubic => 3,
+    };
+    let columns = trace_cols as u64 + ext;
+    let fft_ops = columns * (n / 2 * log(n) + lde / 2 * log(lde));
+
+    // leaves: 8 field elements per 64-byte block; internal nodes: one block each
+    let blocks = |width: u64| width.div_ceil(8);
+    let folding = options.to_fri_options().folding_factor() as u64;
+    let mut fri_leaves = 0;
+    let mut layer = lde / folding;
+    let mut fri_layers = 0;
+    while layer > options.to_fri_options().remainder_max_degree() as u64 + 1 {
+        fri_leaves += layer;
+        layer /= folding;
+        fri_layers += 1;
+    }
+    let hash_ops = lde * blocks(trace_cols as u64) + lde * blocks(ext) + 2 * lde + 2 * fri_leaves;
+
+    let query_ops = options.num_queries() as u64 * (2 + fri_layers) * log(lde);
+    let mut estimate = ProveEstimate { lde_rows: lde as usize, fft_ops, hash_ops, query_ops, time: Duration::ZERO };
+    estimate.time = estimate.time_at(NS_PER_OP);
+    estimate
+}
+
+/// Nanoseconds per [`ProveEstimate::ops`] on this machine: proves a
+/// 2^12-row path over a two-way branching chain with `options` and divides
+/// the elapsed time by its estimate. Takes about as long as that proof.
+#[cfg(all(feature = "std", feature = "prover"))]
+pub fn calibrate(options: &ProofOptions) -> f64 {
+    use winterfell::{Prover, Trace};
+
+    use crate::{
+        air::StarkraAir,
+        cfg::Cfg,
+        exe_path::{JmpType, Step},
+        prover::StarkraProver,
+    };
+
+    const NODES: u64 = 64;
+    const STEPS: u64 = 4000;
+    let cfg = Cfg::from_adjacency((0..NODES).map(|n| (n, alloc::vec![(n + 1) % NODES, (n + 2) % NODES])));
+    let path = (0..STEPS).map(|i| Step { jmp_type: JmpType::Jump, addrs: alloc::vec![i % NODES] }).collect();
+    let trace = StarkraAir::build_trace(path, cfg, 1);
+    let estimate = estimate_prove_cost(trace.length(), trace.width(), options);
+    let start = std::time::Instant::now();
+    StarkraProver::new(options.clone()).prove(trace).expect("calibration trace is valid");
+    start.elapsed().as_nanos() as f64 / estimate.ops() as f64
+}
@@ -1,10 +1,24 @@
-use std::fs;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::cfg::{Cfg, MAX_NODE_ID, NodeId};
+use crate::error::{EdgeKindMismatch, EntryPolicyViolation, PathError, PolicyViolation, StarkraError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JmpType {
     Call,
     Jump,
     Ret,
+    /// A call that replaces the current frame: it moves like a jump and
+    /// the callee returns straight to the current frame's return address.
+    TailCall,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -13,15 +27,89 @@ pub struct Step {
     /// call -> [jmp_addr, ret_addr]
     /// jump -> [addr]
     /// ret  -> [addr]
-    pub addrs: Vec<u32>,
+    /// tailcall -> [jmp_addr]
+    pub addrs: Vec<NodeId>,
+}
+
+impl fmt::Display for JmpType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            JmpType::Call => "call",
+            JmpType::Jump => "jump",
+            JmpType::Ret => "ret",
+            JmpType::TailCall => "tailcall",
+        };
+        f.write_str(op)
+    }
+}
+
+/// Parses an opcode token; the inverse of `Display`.
+impl FromStr for JmpType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "call" => Ok(JmpType::Call),
+            "jump" => Ok(JmpType::Jump),
+            "ret" => Ok(JmpType::Ret),
+            "tailcall" => Ok(JmpType::TailCall),
+            _ => Err(format!("unknown opcode '{}'", s)),
+        }
+    }
+}
+
+/// Formats the step as a line of the execution path text, e.g. `call 10 14`.
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.jmp_type)?;
+        for a in &self.addrs {
+            write!(f, " {}", a)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses one instruction line, e.g. `call 10 14`; the inverse of `Display`.
+impl FromStr for Step {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut it = s.split_whitespace();
+        let jmp_type: JmpType = it.next().unwrap_or_default().parse()?;
+        let addrs: Vec<NodeId> = it
+            .map(|t| t.parse().ok().filter(|&n| n <= MAX_NODE_ID).ok_or_else(|| format!("invalid node '{}'", t)))
+            .collect::<Result<_, _>>()?;
+        if addrs.len() != jmp_type.arity() {
+            return Err(format!("'{}' expects {} addresses, got {}", jmp_type, jmp_type.arity(), addrs.len()));
+        }
+        Ok(Step { jmp_type, addrs })
+    }
 }
 
+/// (steps, initial_node, final_node)
+pub type ParsedPath = (Vec<Step>, Option<NodeId>, Option<NodeId>);
+
 /// Parse the execution path text.
 /// Returns (steps, initial_node, final_node)
-pub fn parse_execution_path(input: &str) -> Result<(Vec<Step>, Option<u32>, Option<u32>), String> {
+pub fn parse_execution_path(input: &str) -> Result<ParsedPath, String> {
+    parse_execution_path_with(input, None)
+}
+
+/// Like `parse_execution_path`, but lines with an unknown opcode or the
+/// wrong number of addresses are skipped and reported as warnings instead
+/// of aborting the parse. Malformed headers and numbers are still errors.
+pub fn parse_execution_path_lenient(input: &str) -> Result<(ParsedPath, Vec<StarkraError>), String> {
+    let mut warnings = Vec::new();
+    let parsed = parse_execution_path_with(input, Some(&mut warnings))?;
+    Ok((parsed, warnings))
+}
+
+/// Strict parse without `warnings`; with it, recoverable line errors are
+/// pushed there and the line is skipped.
+fn parse_execution_path_with(input: &str, mut warnings: Option<&mut Vec<StarkraError>>) -> Result<ParsedPath, String> {
     let mut steps: Vec<Step> = Vec::new();
-    let mut initial_node: Option<u32> = None;
-    let mut final_node: Option<u32> = None;
+    let mut initial_node: Option<NodeId> = None;
+    let mut final_node: Option<NodeId> = None;
 
     for (lineno, raw_line) in input.lines().enumerate() {
         let line = raw_line.trim();
@@ -31,11 +119,11 @@ pub fn parse_execution_path(input: &str) -> Result<(Vec<Step>, Option<u32>, Opti
         let mut header_found = false;
         for tok in line.split_whitespace() {
             if let Some(v) = tok.strip_prefix("initial_node=") {
-                initial_node = Some(parse_u32(v, lineno + 1)?);
+                initial_node = Some(parse_u64(v, lineno + 1)?);
                 header_found = true;
                 steps.push(Step { jmp_type: JmpType::Jump, addrs: vec![initial_node.expect("Bad initial node format")] });
             } else if let Some(v) = tok.strip_prefix("final_node=") {
-                final_node = Some(parse_u32(v, lineno + 1)?);
+                final_node = Some(parse_u64(v, lineno + 1)?);
                 header_found = true;
             }
         }
@@ -49,55 +137,674 @@ pub fn parse_execution_path(input: &str) -> Result<(Vec<Step>, Option<u32>, Opti
         // Parse instructions
         let mut it = line.split_whitespace();
         let Some(op) = it.next() else { continue };
-        let numbers: Vec<u32> = it
-            .map(|t| parse_u32_token(t, lineno + 1))
+        // an unknown line is skipped before its operands are looked at
+        if let Some(w) = warnings.as_deref_mut()
+            && let Err(e) = op.parse::<JmpType>()
+        {
+            w.push(StarkraError::Parse(format!("Line {}: {}", lineno + 1, e)));
+            continue;
+        }
+        let numbers: Vec<NodeId> = it
+            .map(|t| parse_u64_token(t, lineno + 1))
             .collect::<Result<_, _>>()?;
 
-        match op {
-            "call" => {
-                if numbers.len() != 2 {
-                    return Err(format!("Line {}: 'call' expects 2 numbers", lineno + 1));
+        let jmp_type: JmpType = op.parse().map_err(|e| format!("Line {}: {}", lineno + 1, e))?;
+        let arity = jmp_type.arity();
+        if numbers.len() != arity {
+            let noun = if arity == 1 { "number" } else { "numbers" };
+            let msg = format!("Line {}: '{}' expects {} {}", lineno + 1, jmp_type, arity, noun);
+            match warnings.as_deref_mut() {
+                Some(w) => {
+                    w.push(StarkraError::Parse(msg));
+                    continue;
                 }
-                steps.push(Step { jmp_type: JmpType::Call, addrs: numbers });
+                None => return Err(msg),
+            }
+        }
+        steps.push(Step { jmp_type, addrs: numbers });
+    }
+
+    Ok((steps, initial_node, final_node))
+}
+
+/// Check that `steps` is a walk the AIR accepts over `cfg`: every step's
+/// node (for a call, its target; for a ret, the address it returns to) is
+/// a successor of the previous step's node, and every ret goes back to the
+/// innermost pending return address. A ret must satisfy both.
+///
+/// This mirrors the transition constraints, so a path that passes yields
+/// a provable trace; it is much cheaper than finding out from `prove`.
+pub fn validate_path(steps: &[Step], cfg: &Cfg) -> Result<(), PathError> {
+    walk(steps, cfg).map(|_| ())
+}
+
+/// What replaying a path over a CFG ends with, as found by [`simulate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimResult {
+    pub start: NodeId,
+    pub end: NodeId,
+    /// Calls still pending when the path ends.
+    pub depth: usize,
+}
+
+impl SimResult {
+    /// Whether the path proves the claim `start -> end`.
+    pub fn holds(&self, start: NodeId, end: NodeId) -> bool {
+        self.start == start && self.end == end
+    }
+}
+
+/// Replay `steps` over `cfg` with the same checks as `validate_path` and
+/// the same shadow stack as the trace builder, without building a trace.
+/// A path that simulates is a path `prove_path` can prove, for the start
+/// and end returned here.
+pub fn simulate(cfg: &Cfg, steps: &[Step]) -> Result<SimResult, PathError> {
+    let (Some(first), Some(last)) = (steps.first(), steps.last()) else {
+        return Err(PathError::Empty);
+    };
+    let stack = walk(steps, cfg)?;
+    Ok(SimResult { start: first.addrs[0], end: last.addrs[0], depth: stack.depth() })
+}
+
+impl Cfg {
+    /// `validate_path`, and additionally every call has returned by the end
+    /// of the path. The AIR does not require this (a proof may stop inside
+    /// a call), but a complete run of a program does.
+    pub fn is_valid_walk(&self, steps: &[Step]) -> Result<(), PathError> {
+        match walk(steps, self)?.pending().next() {
+            Some((step, ret_addr)) => Err(PathError::UnreturnedCall { step, ret_addr }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Cfg {
+    /// Build a CFG from observed execution paths: the union of every
+    /// transition between consecutive steps' nodes (the same nodes
+    /// `validate_path` checks), deduplicated and canonicalized, so every
+    /// input path is a valid walk over the result as far as edges go.
+    /// A node seen only in a single-step path has no edge and is left out.
+    pub fn from_paths(paths: impl IntoIterator<Item = Vec<Step>>) -> Cfg {
+        let mut edges = BTreeSet::new();
+        for steps in paths {
+            let nodes: Vec<NodeId> = steps.iter().filter_map(|s| s.addrs.first().copied()).collect();
+            edges.extend(nodes.windows(2).map(|w| (w[0], w[1])));
+        }
+        Cfg::from_edges(edges)
+    }
+
+    /// Check `steps` against a policy of node sets: every node of
+    /// `must_visit` is visited and none of `must_avoid` is. A step visits
+    /// its first address (a call's target, not its return address).
+    ///
+    /// Host-side only, and independent of the CFG's edges; validate the
+    /// walk separately. The first forbidden visit in path order is reported,
+    /// otherwise the smallest required node that was missed. Proving the
+    /// avoidance half needs `StarkraAir::build_trace_avoiding`.
+    pub fn check_policy(
+        &self,
+        steps: &[Step],
+        must_visit: &BTreeSet<NodeId>,
+        must_avoid: &BTreeSet<NodeId>,
+    ) -> Result<(), PolicyViolation> {
+        let mut missed = must_visit.clone();
+        for (i, node) in steps.iter().enumerate().filter_map(|(i, s)| s.addrs.first().map(|&n| (i, n))) {
+            if must_avoid.contains(&node) {
+                return Err(PolicyViolation::Forbidden { step: i, node });
+            }
+            missed.remove(&node);
+        }
+        match missed.first() {
+            Some(&node) => Err(PolicyViolation::Missed { node }),
+            None => Ok(()),
+        }
+    }
+
+    /// How many times `steps` traverses each edge: the number of
+    /// consecutive steps whose nodes are `(from, to)`, i.e. of pairs of
+    /// consecutive real rows in the trace with those `current` values. A
+    /// counter column incremented on the real-row pairs of a tagged edge
+    /// would end on the same count, e.g. a loop's back edge counts how
+    /// many times the loop went round.
+    ///
+    /// Host-side only: edges the path takes are counted whether or not
+    /// they are in the CFG; validate the walk separately.
+    pub fn edge_multiplicity(&self, steps: &[Step]) -> BTreeMap<(NodeId, NodeId), usize> {
+        let nodes: Vec<NodeId> = steps.iter().filter_map(|s| s.addrs.first().copied()).collect();
+        let mut counts = BTreeMap::new();
+        for w in nodes.windows(2) {
+            *counts.entry((w[0], w[1])).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Every step whose jump type differs from the kind declared for the
+    /// edge it takes (see `with_edge_kinds`): step `i` takes the edge from
+    /// step `i - 1`'s node to its own. Edges without a declared kind, and
+    /// edges missing from the CFG, are not checked here; validate the walk
+    /// separately. Mismatches come in path order.
+    ///
+    /// Host-side only: the AIR does not see edge kinds.
+    pub fn check_edge_kinds(&self, steps: &[Step]) -> Result<(), Vec<EdgeKindMismatch>> {
+        let mut mismatches = Vec::new();
+        for (i, pair) in steps.windows(2).enumerate() {
+            let (Some(&from), Some(&to)) = (pair[0].addrs.first(), pair[1].addrs.first()) else {
+                continue;
+            };
+            if let Some(expected) = self.edge_kind(from, to)
+                && *expected != pair[1].jmp_type
+            {
+                mismatches.push(EdgeKindMismatch {
+                    step: i + 1,
+                    from,
+                    to,
+                    expected: expected.clone(),
+                    actual: pair[1].jmp_type.clone(),
+                });
+            }
+        }
+        if mismatches.is_empty() { Ok(()) } else { Err(mismatches) }
+    }
+
+    /// Every step into an entry-policy node (see `with_entry_policies`) by
+    /// a kind of step the policy rules out, in path order. A tail call
+    /// counts as a jump. Step 0 is where the path starts, not a step into
+    /// its node, so it is not checked.
+    ///
+    /// The AIR's `entry` constraint rejects exactly these steps when the
+    /// trace's `call` and `ret` flags are the path's; this finds them
+    /// without building a trace. The AIR does not tie `call` to a call
+    /// edge, so a prover may flag a jump as a call; a policy ruling out
+    /// jumps but allowing calls does not stop that. See
+    /// [`crate::air::PublicInputs::entry_policies`].
+    pub fn check_entry_policy(&self, steps: &[Step]) -> Result<(), Vec<EntryPolicyViolation>> {
+        let violations: Vec<_> = steps
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(i, step)| {
+                let node = *step.addrs.first()?;
+                let allowed = *self.entry_policy(node)?;
+                (!allowed.allows(&step.jmp_type)).then(|| EntryPolicyViolation {
+                    step: i,
+                    node,
+                    actual: step.jmp_type.clone(),
+                    allowed,
+                })
+            })
+            .collect();
+        if violations.is_empty() { Ok(()) } else { Err(violations) }
+    }
+}
+
+/// Rename every address in `steps` through `mapping`, as returned by
+/// [`Cfg::induced_subgraph`]. An address with no entry (the path leaves
+/// the subgraph) is an error; the path cannot be proven over it.
+pub fn remap_path(steps: &[Step], mapping: &BTreeMap<NodeId, NodeId>) -> Result<Vec<Step>, PathError> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let addrs = step
+                .addrs
+                .iter()
+                .map(|&n| mapping.get(&n).copied().ok_or(PathError::OutsideSubgraph { step: i, node: n }))
+                .collect::<Result<_, _>>()?;
+            Ok(Step { jmp_type: step.jmp_type.clone(), addrs })
+        })
+        .collect()
+}
+
+/// Rewrite `steps` over the CFG returned by [`Cfg::contract_chains`],
+/// given its super-node map: each run through a chain becomes the single
+/// step that entered it. A path must cover every chain it touches from
+/// first to last node, and only with jumps after the first; anything else
+/// (including starting or ending mid-chain) is a `PartialChain` error.
+pub fn contract_path(steps: &[Step], chains: &BTreeMap<NodeId, Vec<NodeId>>) -> Result<Vec<Step>, PathError> {
+    // original node -> (super-node, position in its chain)
+    let position: BTreeMap<NodeId, (NodeId, usize)> = chains
+        .iter()
+        .flat_map(|(&s, chain)| chain.iter().enumerate().map(move |(k, &v)| (v, (s, k))))
+        .collect();
+    let head_of = |i: usize, n: NodeId| match position.get(&n) {
+        Some(&(s, 0)) => Ok(s),
+        Some(_) => Err(PathError::PartialChain { step: i, node: n }),
+        None => Err(PathError::OutsideSubgraph { step: i, node: n }),
+    };
+
+    let mut out = Vec::new();
+    // remaining nodes of the chain being walked
+    let mut rest: &[NodeId] = &[];
+    for (i, step) in steps.iter().enumerate() {
+        let node = step.addrs.first().copied();
+        if let Some((&expected, tail)) = rest.split_first() {
+            if step.jmp_type != JmpType::Jump || node != Some(expected) {
+                return Err(PathError::PartialChain { step: i, node: node.unwrap_or(expected) });
+            }
+            rest = tail;
+            continue;
+        }
+        let addrs = step.addrs.iter().map(|&n| head_of(i, n)).collect::<Result<Vec<_>, _>>()?;
+        if let Some(&s) = addrs.first() {
+            rest = &chains[&s][1..];
+        }
+        out.push(Step { jmp_type: step.jmp_type.clone(), addrs });
+    }
+    if !rest.is_empty() {
+        let step = steps.len() - 1;
+        return Err(PathError::PartialChain { step, node: steps[step].addrs[0] });
+    }
+    Ok(out)
+}
+
+/// Stitch `paths` into one walk over `cfg` that takes every edge any of
+/// them takes, so a single proof of the result attests the coverage of all
+/// of them (`Cfg::edge_multiplicity` reads the edges back off it).
+///
+/// The walk starts as the first non-empty path. From wherever it ends it
+/// goes on with the nearest path it has not covered yet (one with an edge,
+/// or for a single step its node, not yet on the walk), ties going to the
+/// earlier path, reaching that path's start by a [`Cfg::shortest_path`] of
+/// jump steps; paths already covered along the way are left out. A joined
+/// path's own first step takes the place of the connector's last jump, so
+/// a path starting with a call still pushes its return address; where the
+/// walk already stands on the path's start, that step is dropped. Calls a
+/// path leaves pending stay on the stack, so the walk may end inside
+/// calls, which the AIR allows.
+///
+/// Every path must be a walk over `cfg` by itself; the first that is not
+/// fails with its `PathError`. All empty is `PathError::Empty`. Stitching
+/// fails with `CoverGap` when no path left to cover can be reached from
+/// where the walk ends: the paths lie in parts of the graph that do not
+/// connect that way (separate functions, a path ending at an exit), or
+/// only through nodes whose entry policy rules out jumps. The choice of
+/// next path is greedy, so over a graph that is one-way between the paths
+/// the order they are given in can matter; put first a path that can
+/// reach the rest. The stitched walk is validated as a whole, and one the
+/// joins break (a ret whose call was dropped, say) fails with its
+/// `PathError`.
+pub fn cover_paths(cfg: &Cfg, paths: &[Vec<Step>]) -> Result<Vec<Step>, StarkraError> {
+    for steps in paths {
+        validate_path(steps, cfg)?;
+    }
+    // (input index, nodes, steps) of the paths not yet covered
+    let mut remaining: Vec<(usize, Vec<NodeId>, &[Step])> = paths
+        .iter()
+        .enumerate()
+        .filter(|(_, steps)| !steps.is_empty())
+        .map(|(i, steps)| (i, steps.iter().map(|s| s.addrs[0]).collect(), steps.as_slice()))
+        .collect();
+    if remaining.is_empty() {
+        return Err(PathError::Empty.into());
+    }
+    let (_, first_nodes, first) = remaining.remove(0);
+    let mut walk = first.to_vec();
+    let mut taken: BTreeSet<(NodeId, NodeId)> = first_nodes.windows(2).map(|w| (w[0], w[1])).collect();
+    let mut visited: BTreeSet<NodeId> = first_nodes.into_iter().collect();
+    loop {
+        remaining.retain(|(_, nodes, _)| {
+            !(visited.contains(&nodes[0]) && nodes.windows(2).all(|w| taken.contains(&(w[0], w[1]))))
+        });
+        let Some(&(index, ref nodes, _)) = remaining.first() else {
+            validate_path(&walk, cfg)?;
+            return Ok(walk);
+        };
+        let end = walk[walk.len() - 1].addrs[0];
+        let Some((k, connector)) = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(k, (_, nodes, _))| cfg.shortest_path(end, nodes[0]).map(|c| (k, c)))
+            .min_by_key(|(_, c)| c.len())
+        else {
+            return Err(StarkraError::CoverGap { path: index, from: end, to: nodes[0] });
+        };
+        let (_, nodes, steps) = remaining.remove(k);
+        walk.extend(connector[1..].iter().map(|&n| Step { jmp_type: JmpType::Jump, addrs: vec![n] }));
+        if connector.len() > 1
+            && let Some(last) = walk.last_mut()
+        {
+            *last = steps[0].clone();
+        }
+        walk.extend_from_slice(&steps[1..]);
+        taken.extend(connector.windows(2).chain(nodes.windows(2)).map(|w| (w[0], w[1])));
+        visited.extend(connector.into_iter().chain(nodes));
+    }
+}
+
+/// The shadow stack of return addresses the AIR's stack column follows.
+/// Both the trace builder and the path checks drive it through
+/// [`ShadowStack::apply`], so they cannot disagree on a step's effect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShadowStack {
+    /// `(step, return address)`, outermost first. The address is `None`
+    /// for a call without one, which the trace encodes as "no node".
+    frames: Vec<(usize, Option<NodeId>)>,
+}
+
+impl ShadowStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply step `index`: a call pushes its return address, a ret pops
+    /// (an empty stack is left alone), and a tail call replaces the top
+    /// frame with its own, keeping that frame's return address, so the
+    /// depth does not change. Returns the popped return address for a ret,
+    /// `None` otherwise.
+    pub fn apply(&mut self, index: usize, step: &Step) -> Option<NodeId> {
+        match step.jmp_type {
+            JmpType::Call => {
+                self.frames.push((index, step.addrs.get(1).copied()));
+                None
             }
-            "jump" => {
-                if numbers.len() != 1 {
-                    return Err(format!("Line {}: 'jump' expects 1 number", lineno + 1));
+            JmpType::Ret => self.frames.pop().and_then(|(_, addr)| addr),
+            JmpType::TailCall => {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.0 = index;
                 }
-                steps.push(Step { jmp_type: JmpType::Jump, addrs: numbers });
+                None
             }
-            "ret" => {
-                if numbers.len() != 1 {
-                    return Err(format!("Line {}: 'ret' expects 1 number", lineno + 1));
+            JmpType::Jump => None,
+        }
+    }
+
+    /// Return address on top of the stack, `None` if it is empty.
+    pub fn top(&self) -> Option<NodeId> {
+        self.frames.last().and_then(|&(_, addr)| addr)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The calls still pending as `(step, return address)`, outermost first.
+    pub fn pending(&self) -> impl Iterator<Item = (usize, NodeId)> + '_ {
+        self.frames.iter().filter_map(|&(i, addr)| addr.map(|a| (i, a)))
+    }
+}
+
+/// Simulate the shadow stack along `steps`, checking every transition;
+/// returns the stack as it is at the end.
+fn walk(steps: &[Step], cfg: &Cfg) -> Result<ShadowStack, PathError> {
+    let mut stack = ShadowStack::new();
+    let mut prev: Option<NodeId> = None;
+    for (i, step) in steps.iter().enumerate() {
+        let expected = step.jmp_type.arity();
+        if step.addrs.len() != expected {
+            return Err(PathError::Arity { step: i, expected, actual: step.addrs.len() });
+        }
+        let to = step.addrs[0];
+        if let Some(from) = prev
+            && !cfg.successors(from).contains(&to)
+        {
+            return Err(match step.jmp_type {
+                JmpType::Call | JmpType::TailCall => PathError::IllegalCallTarget { step: i, caller: from, target: to },
+                JmpType::Ret => PathError::IllegalReturnEdge { step: i, from, to },
+                JmpType::Jump => PathError::IllegalEdge { step: i, from, to },
+            });
+        }
+        let popped = stack.apply(i, step);
+        if step.jmp_type == JmpType::Ret && popped != Some(to) {
+            return Err(PathError::ReturnMismatch { step: i, expected: popped, actual: to });
+        }
+        prev = Some(to);
+    }
+    Ok(stack)
+}
+
+/// Stricter companion to `validate_path`: every call's return address must
+/// also be a successor of the caller, i.e. the CFG has the fall-through
+/// edge from the call site. Not enforced by the AIR, since many CFGs model
+/// calls without that edge.
+pub fn validate_return_sites(steps: &[Step], cfg: &Cfg) -> Result<(), PathError> {
+    for (i, pair) in steps.windows(2).enumerate() {
+        let (caller, call) = (&pair[0], &pair[1]);
+        if call.jmp_type != JmpType::Call {
+            continue;
+        }
+        let (Some(&from), Some(&ret_addr)) = (caller.addrs.first(), call.addrs.get(1)) else {
+            continue;
+        };
+        if !cfg.successors(from).contains(&ret_addr) {
+            return Err(PathError::IllegalReturnAddress { step: i + 1, caller: from, ret_addr });
+        }
+    }
+    Ok(())
+}
+
+/// Turn steps listed in reverse execution order (last executed first) into
+/// a forward walk over `cfg.reversed()` from the last node back to the
+/// first, so a trace built from it proves the same reachability fact with
+/// start and end swapped.
+///
+/// With `n_0 .. n_k` the nodes in execution order, the result visits
+/// `n_k .. n_0`, starting with `jump n_k`. The step entering `n_{j-1}` is
+/// derived from the original step `j` that left it:
+/// - `jump` stays a `jump`, and so does a `tailcall`, which leaves the
+///   stack as it was;
+/// - a `ret` becomes a `call n_{j-1} c`, where `c` is the node just before
+///   the matching `call`, so the stack now holds the caller;
+/// - that matching `call` becomes `ret n_{j-1}`, returning to `c`.
+///
+/// Calls that never return and rets without a call in the path have no
+/// partner; they become plain jumps, since the forward AIR does not check
+/// those either.
+pub fn reverse_walk(reversed: &[Step]) -> Vec<Step> {
+    let forward: Vec<&Step> = reversed.iter().rev().collect();
+    let node = |j: usize| forward[j].addrs.first().copied();
+
+    // pair every ret with its call (by index in execution order)
+    let mut caller_of_ret: Vec<Option<NodeId>> = vec![None; forward.len()];
+    let mut returns = vec![false; forward.len()];
+    let mut pending: Vec<usize> = Vec::new();
+    for (j, step) in forward.iter().enumerate() {
+        match step.jmp_type {
+            JmpType::Call => pending.push(j),
+            JmpType::Ret => {
+                if let Some(c) = pending.pop()
+                    && c > 0
+                {
+                    returns[c] = true;
+                    caller_of_ret[j] = node(c - 1);
                 }
-                steps.push(Step { jmp_type: JmpType::Ret, addrs: numbers });
             }
-            _ => return Err(format!("Line {}: unknown opcode '{}'", lineno + 1, op)),
+            // the frame, and so the ret pairing, is unchanged
+            JmpType::Jump | JmpType::TailCall => {}
         }
     }
 
-    Ok((steps, initial_node, final_node))
+    let mut out = Vec::with_capacity(forward.len());
+    if let Some(last) = forward.len().checked_sub(1) {
+        out.push(Step { jmp_type: JmpType::Jump, addrs: node(last).into_iter().collect() });
+    }
+    for j in (1..forward.len()).rev() {
+        let to = node(j - 1);
+        let (jmp_type, addrs) = match (&forward[j].jmp_type, to, caller_of_ret[j]) {
+            (JmpType::Call, Some(to), _) if returns[j] => (JmpType::Ret, vec![to]),
+            (JmpType::Ret, Some(to), Some(c)) => (JmpType::Call, vec![to, c]),
+            _ => (JmpType::Jump, to.into_iter().collect()),
+        };
+        out.push(Step { jmp_type, addrs });
+    }
+    out
 }
 
-fn parse_u32(s: &str, lineno: usize) -> Result<u32, String> {
-    s.trim().parse::<u32>().map_err(|e| {
-        format!("Line {}: invalid number '{}': {}", lineno, s.trim(), e)
-    })
+fn parse_u64(s: &str, lineno: usize) -> Result<NodeId, String> {
+    parse_u64_token(s.trim(), lineno)
 }
 
-fn parse_u32_token(tok: &str, lineno: usize) -> Result<u32, String> {
-    tok.parse::<u32>().map_err(|e| {
+fn parse_u64_token(tok: &str, lineno: usize) -> Result<NodeId, String> {
+    let n = tok.parse::<NodeId>().map_err(|e| {
         format!("Line {}: invalid number '{}': {}", lineno, tok, e)
-    })
+    })?;
+    if n > MAX_NODE_ID {
+        return Err(format!("Line {}: node {} exceeds the maximum {}", lineno, n, MAX_NODE_ID));
+    }
+    Ok(n)
+}
+
+// Binary layout (all integers little-endian):
+//   header: b"SKPB" | version: u8 | flags: u8 | initial_node: u64 | final_node: u64
+//           flags bit 0 = initial_node present, bit 1 = final_node present
+//   body:   repeated records `tag: u8` followed by the step's addresses as u64
+//           tag 0 = jump (1 addr), 1 = call (2 addrs), 2 = ret (1 addr),
+//           3 = tailcall (1 addr)
+// Steps are stored exactly as the text parser returns them, including the
+// leading `jump initial_node` step, so the header values are not re-expanded.
+// Version 1 files have the same layout with u32 node IDs and are still read.
+pub const BINARY_MAGIC: &[u8; 4] = b"SKPB";
+pub const BINARY_VERSION: u8 = 2;
+const NODE_LEN: usize = 8;
+const HEADER_LEN: usize = 4 + 1 + 1 + NODE_LEN + NODE_LEN;
+const FLAG_INITIAL: u8 = 0b01;
+const FLAG_FINAL: u8 = 0b10;
+
+impl JmpType {
+    fn tag(&self) -> u8 {
+        match self {
+            JmpType::Jump => 0,
+            JmpType::Call => 1,
+            JmpType::Ret => 2,
+            JmpType::TailCall => 3,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(JmpType::Jump),
+            1 => Some(JmpType::Call),
+            2 => Some(JmpType::Ret),
+            3 => Some(JmpType::TailCall),
+            _ => None,
+        }
+    }
+
+    /// Number of addresses a step of this type carries.
+    pub fn arity(&self) -> usize {
+        match self {
+            JmpType::Call => 2,
+            JmpType::Jump | JmpType::Ret | JmpType::TailCall => 1,
+        }
+    }
+}
+
+/// Encode a parsed path into the compact binary format.
+pub fn to_binary(steps: &[Step], initial_node: Option<NodeId>, final_node: Option<NodeId>) -> Vec<u8> {
+    let body: usize = steps.iter().map(|s| 1 + NODE_LEN * s.addrs.len()).sum();
+    let mut out = Vec::with_capacity(HEADER_LEN + body);
+
+    let mut flags = 0u8;
+    if initial_node.is_some() { flags |= FLAG_INITIAL; }
+    if final_node.is_some() { flags |= FLAG_FINAL; }
+
+    out.extend_from_slice(BINARY_MAGIC);
+    out.push(BINARY_VERSION);
+    out.push(flags);
+    out.extend_from_slice(&initial_node.unwrap_or(0).to_le_bytes());
+    out.extend_from_slice(&final_node.unwrap_or(0).to_le_bytes());
+
+    for step in steps {
+        out.push(step.jmp_type.tag());
+        for a in &step.addrs {
+            out.extend_from_slice(&a.to_le_bytes());
+        }
+    }
+    out
 }
 
-/// Load file and parse
-pub fn parse_execution_path_file(path: &str)
-    -> Result<(Vec<Step>, Option<u32>, Option<u32>), String>
-{
-    let contents = fs::read_to_string(path)
+/// Parse the compact binary format produced by [`to_binary`].
+/// Returns (steps, initial_node, final_node)
+pub fn parse_execution_path_binary(bytes: &[u8]) -> Result<ParsedPath, StarkraError> {
+    if bytes.len() < 6 || &bytes[..4] != BINARY_MAGIC {
+        return Err(StarkraError::Parse("missing binary path header".to_string()));
+    }
+    let width = match bytes[4] {
+        1 => 4,
+        BINARY_VERSION => NODE_LEN,
+        v => return Err(StarkraError::Parse(format!("unsupported binary path version {}", v))),
+    };
+    let header_len = 6 + 2 * width;
+    if bytes.len() < header_len {
+        return Err(StarkraError::Parse("missing binary path header".to_string()));
+    }
+    let flags = bytes[5];
+    let initial_node = (flags & FLAG_INITIAL != 0).then(|| read_node_le(bytes, 6, width)).transpose()?;
+    let final_node = (flags & FLAG_FINAL != 0).then(|| read_node_le(bytes, 6 + width, width)).transpose()?;
+
+    let mut steps: Vec<Step> = Vec::new();
+    let mut pos = header_len;
+    while pos < bytes.len() {
+        let tag = bytes[pos];
+        let jmp_type = JmpType::from_tag(tag)
+            .ok_or_else(|| StarkraError::Parse(format!("Offset {}: unknown opcode tag {}", pos, tag)))?;
+        let end = pos + 1 + width * jmp_type.arity();
+        if end > bytes.len() {
+            return Err(StarkraError::Parse(format!(
+                "Offset {}: truncated '{}' record", pos, jmp_type
+            )));
+        }
+        let addrs = (0..jmp_type.arity())
+            .map(|i| read_node_le(bytes, pos + 1 + width * i, width))
+            .collect::<Result<_, _>>()?;
+        steps.push(Step { jmp_type, addrs });
+        pos = end;
+    }
+
+    Ok((steps, initial_node, final_node))
+}
+
+/// Little-endian node ID of `width` bytes (4 in version 1, 8 since),
+/// rejected above [`MAX_NODE_ID`] like the text parser does.
+fn read_node_le(bytes: &[u8], at: usize, width: usize) -> Result<NodeId, StarkraError> {
+    let mut buf = [0u8; NODE_LEN];
+    buf[..width].copy_from_slice(&bytes[at..at + width]);
+    let n = NodeId::from_le_bytes(buf);
+    if n > MAX_NODE_ID {
+        return Err(StarkraError::Parse(format!("Offset {}: node {} exceeds the maximum {}", at, n, MAX_NODE_ID)));
+    }
+    Ok(n)
+}
+
+/// Load file and parse.
+/// Files starting with [`BINARY_MAGIC`] are decoded as the binary format,
+/// anything else as text. With the `gzip` feature, files named `*.gz` or
+/// starting with the gzip magic are decompressed first.
+#[cfg(feature = "std")]
+pub fn parse_execution_path_file(path: &str) -> Result<ParsedPath, String> {
+    parse_execution_path_bytes(path, crate::input::read_input(path)?)
+}
+
+/// `parse_execution_path_file` with [`parse_execution_path_lenient`] for
+/// text files. Binary files are always parsed strictly.
+#[cfg(feature = "std")]
+pub fn parse_execution_path_file_lenient(path: &str) -> Result<(ParsedPath, Vec<StarkraError>), String> {
+    let contents = crate::input::read_input(path)?;
+    if contents.starts_with(BINARY_MAGIC) {
+        return Ok((parse_execution_path_binary(&contents).map_err(|e| e.to_string())?, Vec::new()));
+    }
+    let text = String::from_utf8(contents)
         .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    parse_execution_path_lenient(&text)
+}
 
-    parse_execution_path(&contents)
+/// `parse_execution_path_file` with non-blocking IO for Tokio runtimes.
+/// Only the read is async; parsing runs synchronously afterwards.
+#[cfg(feature = "tokio")]
+pub async fn parse_execution_path_file_async(path: &str) -> Result<ParsedPath, String> {
+    let contents = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    parse_execution_path_bytes(path, crate::input::decompress(path, contents)?)
 }
 
+/// Sniff the format of a loaded file and parse it.
+#[cfg(feature = "std")]
+fn parse_execution_path_bytes(path: &str, contents: Vec<u8>) -> Result<ParsedPath, String> {
+    if contents.starts_with(BINARY_MAGIC) {
+        return parse_execution_path_binary(&contents).map_err(|e| e.to_string());
+    }
+
+    let text = String::from_utf8(contents)
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    parse_execution_path(&text)
+}
@@ -0,0 +1,38 @@
+// input.rs
+//! Loading input files, with transparent gzip decompression (`gzip` feature).
+
+use std::fs;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Contents of `path`, decompressed (see [`decompress`]).
+pub(crate) fn read_input(path: &str) -> Result<Vec<u8>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    decompress(path, bytes)
+}
+
+/// Decompress `bytes` if `path` ends in `.gz` or they start with the gzip
+/// magic; anything else is returned as is.
+#[cfg(feature = "gzip")]
+pub(crate) fn decompress(path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    if !path.ends_with(".gz") && !bytes.starts_with(GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+    let mut out = Vec::new();
+    flate2::read::MultiGzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut out)
+        .map_err(|e| format!("Failed to decompress '{}': {}", path, e))?;
+    Ok(out)
+}
+
+/// Without the `gzip` feature, reject compressed input instead of parsing
+/// it as garbage.
+#[cfg(not(feature = "gzip"))]
+pub(crate) fn decompress(path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        return Err(format!("'{}' is gzip-compressed; build with the `gzip` feature", path));
+    }
+    Ok(bytes)
+}
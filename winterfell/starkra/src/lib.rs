@@ -0,0 +1,34 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod air;
+pub mod analysis;
+pub mod bundle;
+pub mod cfg;
+pub mod commitment;
+#[cfg(feature = "std")]
+pub mod config;
+pub mod divergence;
+pub mod error;
+pub mod estimate;
+pub mod exe_path;
+#[cfg(feature = "std")]
+mod input;
+pub mod mutations;
+#[cfg(feature = "witness-only")]
+pub mod profiling;
+pub mod proof_io;
+#[cfg(feature = "prover")]
+pub mod prover;
+#[cfg(feature = "zeroize")]
+pub mod scrub;
+pub mod sequence;
+pub mod source;
+pub mod stats;
+pub mod supergraph;
+#[cfg(feature = "std")]
+pub mod trace_io;
+#[cfg(all(feature = "std", feature = "prover"))]
+pub mod vectors;
+pub mod verifier;
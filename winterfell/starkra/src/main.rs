@@ -1,32 +1,22 @@
-mod cfg;
-use std::env::{self, args};
-use std::fmt::Debug;
-use cfg::{Cfg};
-mod air;
-use air::*;
-use winterfell::{AcceptableOptions, Air, DefaultConstraintCommitment, FieldExtension, ProofOptions, Prover, Trace, TraceTable, crypto::{DefaultRandomCoin, MerkleTree, hashers::Blake3_256}, math::{FieldElement, fields::f64::BaseElement}, verify, VerifierError};
-use log::trace;
-use crate::{exe_path::{JmpType, parse_execution_path_file}, prover::StarkraProver};
-mod prover;
-
-pub fn build_trace(start: BaseElement, steps: usize) -> TraceTable<BaseElement> {
-    // One column, `steps` rows
-    let mut trace = TraceTable::new(1, steps);
-
-    // Fill the column with the recurrence: x_{i+1} = x_i^3 + 42
-    trace.fill(
-        |state| {
-            state[0] = start;
-        },
-        |_, state| {
-            state[0] = state[0].exp(3u32.into()) + BaseElement::new(42);
-        },
-    );
-
-    trace
-}
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use starkra::air::*;
+use starkra::bundle::{Bundle, read_bundle, write_bundle};
+use starkra::cfg::SuccessorOrder;
+use starkra::source::{CfgFile, CfgSource};
+use starkra::commitment::trace_fingerprint;
+use starkra::config::{FieldExt, StarkraConfig};
+use starkra::error::StarkraError;
+use starkra::estimate::estimate_prove_cost;
+use starkra::exe_path::{parse_execution_path_file, parse_execution_path_file_lenient, validate_path};
+use starkra::prover::{MAX_PARTITIONS, StarkraProver, prove_deterministic};
+use starkra::trace_io::{TraceView, print_trace_table, write_trace_csv};
+use starkra::proof_io::{encode_proof, proof_size_breakdown, read_proof_file, write_proof_file};
+use starkra::vectors::run_vectors;
+use starkra::verifier::{VerifyOutcome, verify_batch, verify_path_strict};
+use winterfell::{AcceptableOptions, ProofOptions, Prover, Trace, TraceTable, math::{FieldElement, fields::f64::BaseElement}};
 
-mod exe_path;
 use std::time::Instant;
 
 fn fmt_bytes(n: usize) -> String {
@@ -46,102 +36,501 @@ fn fmt_bytes(n: usize) -> String {
 }
 
 
-pub fn print_trace_table_with_headers(trace: &TraceTable<BaseElement>, max_succ: usize) {
-    let width = trace.width();
-    let length = trace.length();
+/// Flags that take no value.
+const SWITCHES: &[&str] = &["witness-only", "check", "stats", "json", "lenient", "strict", "deterministic", "one-hot"];
 
-    // ---- build header names ----
-    let mut headers = Vec::new();
-    headers.push("nonce".to_string());
-    headers.push("current".to_string());
-    headers.push("stack".to_string());
+/// Command line: positional arguments plus `--name value` options and
+/// `--switch` flags, which may appear anywhere.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+}
 
-    for i in 0..max_succ {
-        headers.push(format!("nei{}", i));
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut switches = Vec::new();
+        let mut it = raw.skip(1);
+        while let Some(arg) = it.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if SWITCHES.contains(&name) {
+                    switches.push(name.to_string());
+                    continue;
+                }
+                let value = it.next().ok_or_else(|| format!("--{} expects a value", name))?;
+                options.insert(name.to_string(), value);
+            } else {
+                positional.push(arg);
+            }
+        }
+        Ok(Self { positional, options, switches })
     }
 
-    headers.push("valid".to_string());
-    headers.push("ret".to_string());
-    headers.push("call".to_string());
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|s| s.as_str())
+    }
 
-    assert_eq!(headers.len(), width, "header/width mismatch");
+    fn has(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+}
+
+/// Security floor proofs must meet: `--min-security <bits>` of conjectured
+/// security (default 128) or `--min-proven-security <bits>` of proven
+/// security. Returns the policy and its bit count.
+fn acceptable_options(cli: &Args) -> Result<(AcceptableOptions, u32), String> {
+    let bits = |name: &str, s: &str| s.parse::<u32>().map_err(|_| format!("--{}: invalid bit count '{}'", name, s));
+    match (cli.get("min-security"), cli.get("min-proven-security")) {
+        (Some(_), Some(_)) => Err("--min-security and --min-proven-security are mutually exclusive".to_string()),
+        (None, Some(s)) => {
+            let n = bits("min-proven-security", s)?;
+            Ok((AcceptableOptions::MinProvenSecurity(n), n))
+        }
+        (c, None) => {
+            let n = c.map(|s| bits("min-security", s)).transpose()?.unwrap_or(128);
+            Ok((AcceptableOptions::MinConjecturedSecurity(n), n))
+        }
+    }
+}
 
-    // ---- print headers ----
-    print!("row |");
-    for h in &headers {
-        print!(" {:>7} |", h);
+/// Run settings: `--config <file.toml>` (see `starkra::config`) overridden
+/// by the command line, i.e. the positional `cfg path [queries blowup
+/// grinding]`, `--fri-folding`, `--fri-max-remainder`, `--field-ext`,
+/// `--nonce` and `--deterministic`. Without `--config` this is the command
+/// line alone.
+fn run_config(cli: &Args) -> Result<StarkraConfig, String> {
+    fn flag<T: std::str::FromStr>(cli: &Args, name: &str) -> Result<Option<T>, String> {
+        cli.get(name)
+            .map(|s| s.parse().map_err(|_| format!("--{}: invalid value '{}'", name, s)))
+            .transpose()
     }
-    println!();
+    let file = match cli.get("config") {
+        Some(f) => StarkraConfig::from_file(f).map_err(|e| e.to_string())?,
+        None => StarkraConfig::default(),
+    };
+    let args = &cli.positional;
+    // positional numbers that do not parse fall back, as they always have
+    let positional = |i: usize| args.get(i).and_then(|s| s.parse().ok());
+    let over = StarkraConfig {
+        num_queries: positional(2),
+        blowup: positional(3),
+        grinding: args.get(4).and_then(|s| s.parse().ok()),
+        field_ext: cli.get("field-ext").map(FieldExt::parse).transpose().map_err(|e| e.to_string())?,
+        fri_folding: flag(cli, "fri-folding")?,
+        fri_max_remainder: flag(cli, "fri-max-remainder")?,
+        nonce: flag(cli, "nonce")?,
+        deterministic: cli.has("deterministic").then_some(true),
+        cfg: args.first().cloned(),
+        path: args.get(1).cloned(),
+    };
+    let config = file.merge(over);
+    config.fri_options().map_err(|e| e.to_string())?;
+    Ok(config)
+}
 
-    // ---- separator ----
-    print!("----+");
-    for _ in &headers {
-        print!("---------+");
+/// Parse `--rows start:end` into a half-open range.
+fn parse_rows(spec: &str) -> Result<(usize, usize), String> {
+    let (a, b) = spec.split_once(':').ok_or_else(|| format!("--rows expects start:end, got '{}'", spec))?;
+    let a = a.parse().map_err(|_| format!("--rows: invalid start '{}'", a))?;
+    let b = b.parse().map_err(|_| format!("--rows: invalid end '{}'", b))?;
+    Ok((a, b))
+}
+
+/// Verify every `*.proof` file in `dir` in parallel and print a summary.
+fn run_verify_batch(dir: &str, acceptable: &AcceptableOptions) {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .expect("error verify-batch dir")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|x| x == "proof"))
+        .collect();
+    files.sort();
+
+    let mut items = Vec::new();
+    let mut names = Vec::new();
+    let mut unreadable = 0;
+    for f in &files {
+        match read_proof_file(&f.to_string_lossy()) {
+            Ok(item) => {
+                items.push(item);
+                names.push(f.display().to_string());
+            }
+            Err(e) => {
+                println!("FAIL {}: {}", f.display(), e);
+                unreadable += 1;
+            }
+        }
     }
-    println!();
 
-    // ---- print rows ----
-    for r in 0..length {
-        print!("{:>3} |", r);
-        for c in 0..width {
-            let v = trace.get(c, r).as_int();
-            print!(" {:>7} |", v);
+    let t_verify_start = Instant::now();
+    let results = verify_batch(items, acceptable);
+    let verify_dur = t_verify_start.elapsed();
+
+    let mut passed = 0;
+    for (name, res) in names.iter().zip(&results) {
+        match res {
+            Ok(()) => passed += 1,
+            Err(e) => println!("FAIL {}: {}", name, e),
         }
-        println!();
     }
+    println!(
+        "Batch: {} passed, {} failed, {} unreadable ({} files) in {:.3?}",
+        passed,
+        results.len() - passed,
+        unreadable,
+        files.len(),
+        verify_dur
+    );
 }
 
+/// `check-cfg <file>`: parse the CFG and report dangling targets,
+/// self-loops and (with declared entries) nodes unreachable from them.
+/// Dangling and unreachable nodes are errors and self-loops warnings;
+/// `--strict` makes every finding an error, `--lenient` every one a
+/// warning. Exits 1 if there are errors.
+fn run_check_cfg(file: &str, cli: &Args) {
+    let cfg = CfgFile(file).into_cfg().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    let ids = |v: &[u64]| v.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+    let mut findings: Vec<(bool, String)> = Vec::new();
+    let dangling = cfg.dangling_targets();
+    if !dangling.is_empty() {
+        findings.push((true, format!("successors without an adjacency line: {}", ids(&dangling))));
+    }
+    let self_loops = cfg.self_loops();
+    if !self_loops.is_empty() {
+        findings.push((false, format!("self-loops: {}", ids(&self_loops))));
+    }
+    if let Some(entries) = cfg.entry_set() {
+        let unreachable = cfg.unreachable_from(entries.iter().copied());
+        if !unreachable.is_empty() {
+            findings.push((true, format!("unreachable from the declared entries: {}", ids(&unreachable))));
+        }
+    }
+
+    let stats = cfg.stats();
+    println!("CFG {}: {} nodes, {} edges", file, stats.nodes, stats.edges);
+    let mut errors = 0;
+    for (is_error, msg) in &findings {
+        let is_error = (*is_error || cli.has("strict")) && !cli.has("lenient");
+        errors += is_error as usize;
+        println!("  {}: {}", if is_error { "error" } else { "warning" }, msg);
+    }
+    println!("check-cfg: {} errors, {} warnings", errors, findings.len() - errors);
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// `prove --bundle <file>` (with `--proof-out`) proves the bundled case;
+/// `replay <file>` also verifies the proof against the bundle's public
+/// inputs. Exits 1 if proving or verifying fails.
+fn run_bundle(file: &str, replay: bool, cli: &Args, acceptable: &AcceptableOptions) {
+    let bundle = read_bundle(file).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    });
+    println!("Bundle {}: {}, {} steps", file, bundle.cfg, bundle.steps.len());
+    let t_start = Instant::now();
+    let result = if replay { bundle.replay(acceptable) } else { bundle.prove().map(|(proof, _)| proof) };
+    let proof = result.unwrap_or_else(|e| {
+        println!("Bundle failed: {}", e);
+        std::process::exit(1);
+    });
+    println!("Proof size: {}", fmt_bytes(proof.to_bytes().len()));
+    if let Some(out) = cli.get("proof-out") {
+        write_proof_file(out, &proof, &bundle.public_inputs).expect("error proof-out");
+        println!("Proof written to {}", out);
+    }
+    println!("{} in {:.3?}", if replay { "Valid Proof" } else { "Proved" }, t_start.elapsed());
+}
+
+/// Run the conformance vectors in `dir`; exit 1 if any does not match.
+fn run_test_vectors(dir: &str) {
+    let reports = run_vectors(dir).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    let failed = reports.iter().filter(|r| !r.passed()).count();
+    for report in &reports {
+        println!("{}", report);
+    }
+    println!("Vectors: {} passed, {} failed", reports.len() - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Profile commitment/FRI cost only; the resulting proof is meaningless.
+#[cfg(feature = "witness-only")]
+fn run_witness_only(trace: TraceTable<BaseElement>, options: ProofOptions) {
+    println!("WITNESS-ONLY MODE: constraints are disabled, the proof attests nothing");
+    let t_prove_start = Instant::now();
+    let proof = starkra::profiling::prove_witness_only(trace, options).expect("prove");
+    let prove_dur = t_prove_start.elapsed();
+    println!("Witness-only proving time: {:.3?} | Proof: {}", prove_dur, fmt_bytes(proof.to_bytes().len()));
+}
+
+#[cfg(not(feature = "witness-only"))]
+fn run_witness_only(_trace: TraceTable<BaseElement>, _options: ProofOptions) {
+    eprintln!("--witness-only requires building with `--features witness-only`");
+    std::process::exit(2);
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let cfg = Cfg::from_file(args[1].as_str()).expect("error cfg");
-    let (path, a, b) = parse_execution_path_file(args[2].as_str()).expect("error");
+    let cli = Args::parse(env::args()).expect("error args");
+    let args = &cli.positional;
+    let (min_security, security_bits) = acceptable_options(&cli).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    });
+    let config = run_config(&cli).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    });
+
+    if let Some(dir) = cli.get("verify-batch") {
+        run_verify_batch(dir, &min_security);
+        return;
+    }
+    if let Some(dir) = cli.get("vectors") {
+        run_test_vectors(dir);
+        return;
+    }
+
+    match (args.first().map(String::as_str), args.get(1), cli.get("bundle")) {
+        (Some("replay"), Some(file), _) => return run_bundle(file, true, &cli, &min_security),
+        (Some("replay"), None, _) => {
+            eprintln!("error: replay expects a bundle file");
+            std::process::exit(2);
+        }
+        (Some("prove"), _, Some(file)) => return run_bundle(file, false, &cli, &min_security),
+        (Some("prove"), _, None) => {
+            eprintln!("error: prove expects --bundle <file>");
+            std::process::exit(2);
+        }
+        _ => {}
+    }
+
+    if args.first().is_some_and(|a| a == "check-cfg") {
+        match args.get(1) {
+            Some(file) => run_check_cfg(file, &cli),
+            None => {
+                eprintln!("error: check-cfg expects a CFG file");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let Some(cfg_file) = config.cfg.as_deref() else {
+        eprintln!("error: no CFG file given (positional or `cfg` in --config)");
+        std::process::exit(2);
+    };
+    let cfg = CfgFile(cfg_file).into_cfg().expect("error cfg");
 
-    let num_queries: usize = args.get(3)
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(20);   // default value
+    // --stats [--json]: summarize the CFG and stop; no path needed
+    if cli.has("stats") {
+        let stats = cfg.stats();
+        if cli.has("json") {
+            println!("{}", stats.to_json());
+        } else {
+            println!("CFG {}:", cfg_file);
+            print!("{}", stats);
+        }
+        return;
+    }
+
+    let Some(path_file) = config.path.as_deref() else {
+        eprintln!("error: no execution path file given (positional or `path` in --config)");
+        std::process::exit(2);
+    };
+    // --lenient: skip unknown or malformed instruction lines with a warning
+    let (path, a, b) = if cli.has("lenient") {
+        let (parsed, warnings) = parse_execution_path_file_lenient(path_file).expect("error");
+        for w in &warnings {
+            eprintln!("warning: {}", w);
+        }
+        parsed
+    } else {
+        parse_execution_path_file(path_file).expect("error")
+    };
+    // reject paths the AIR cannot accept before spending time on the trace,
+    // unless the trace is being built to inspect why
+    if let Some(start) = a
+        && !cfg.is_legal_entry(start)
+    {
+        eprintln!("error: {}", StarkraError::IllegalEntry(start));
+        std::process::exit(1);
+    }
+    let path_ok = validate_path(&path, &cfg).map_err(|e| eprintln!("error: {}", e)).is_ok();
+    if !path_ok && cli.get("explain-row").is_none() {
+        std::process::exit(1);
+    }
 
-    let blowup_factor: usize = args.get(4)
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(64);    // default value
+    // --successor-order given|sorted|frequency: which neighbor column each
+    // target lands in (see `SuccessorOrder`); the commitment is unchanged
+    let mut cfg = cfg;
+    match cli.get("successor-order") {
+        None | Some("given") => {}
+        Some("sorted") => cfg.order_successors(SuccessorOrder::Sorted),
+        Some("frequency") => cfg.order_successors(SuccessorOrder::ByFrequency(&path)),
+        Some(other) => {
+            eprintln!("error: --successor-order must be given, sorted or frequency, got '{}'", other);
+            std::process::exit(2);
+        }
+    }
 
-    let grinding_factor: u32 = args.get(5)
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(0);     // default value
+    let num_queries = config.num_queries();
+    let blowup_factor = config.blowup();
+    let grinding_factor = config.grinding();
+    let nonce = config.nonce();
 
     println!("num_queries = {}", num_queries);
     println!("blowup_factor = {}", blowup_factor);
     println!("grinding_factor = {}", grinding_factor);
 
+    // --one-hot: selector columns instead of the neighbor products, keeping
+    // every neighbor constraint at degree 2 (see `NeighborEncoding`)
+    let encoding = if cli.has("one-hot") { NeighborEncoding::OneHot } else { NeighborEncoding::Product };
+    let info = StarkraAir::constraint_info_with(cfg.max_successors(), 0, 0, encoding);
+    println!(
+        "max constraint degree = {} (recommended min blowup_factor = {})",
+        info.max_degree(),
+        info.min_blowup()
+    );
+    if blowup_factor < info.min_blowup() {
+        eprintln!("warning: blowup_factor {} is below the minimum {}", blowup_factor, info.min_blowup());
+    }
+    let recommended = recommended_blowup(cfg.max_successors(), num_queries, security_bits);
+    if blowup_factor > recommended {
+        println!(
+            "note: blowup_factor {} is larger than needed; {} already reaches {}-bit conjectured security with {} queries",
+            blowup_factor, recommended, security_bits, num_queries
+        );
+    }
+
+    // --max-rows <n>: refuse paths whose trace would exceed n rows
+    let max_rows = cli.get("max-rows").map(|n| n.parse::<usize>().expect("error max-rows"));
+    // the public inputs of the path itself, before the trace build consumes it
+    let path_inputs = PublicInputs::from_path(&cfg, &path, nonce).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    for (header, value, node) in [("initial_node", a, path_inputs.start), ("final_node", b, path_inputs.end)] {
+        if let Some(n) = value
+            && BaseElement::new(n) != node
+        {
+            eprintln!("warning: {} {} in the path header is not the path's node {}; proving the path's", header, n, node);
+        }
+    }
+    // kept only if it is to be bundled; the trace build consumes the path
+    let bundle_steps = cli.get("bundle-out").map(|_| path.clone());
     let t_build_start = Instant::now();
-    let trace = StarkraAir::build_trace(path, cfg.clone(), 123);
+    let (trace, build_profile) = StarkraAir::build_trace_profiled(path, cfg.clone(), nonce, max_rows).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    let trace = match encoding {
+        NeighborEncoding::Product => trace,
+        NeighborEncoding::OneHot => StarkraAir::add_selectors(trace),
+    };
     let build_dur = t_build_start.elapsed();
     println!("Trace built in {:.3?}", build_dur);
+    if cfg!(feature = "profile") {
+        print!("{}", build_profile);
+    }
+    println!("Trace fingerprint: {}", hex::encode(trace_fingerprint(&trace)));
 
-    print_trace_table_with_headers(&trace, cfg.max_successors());
-    // 2) public inputs
-    let public_inputs = PublicInputs{
-        start: BaseElement::from(a.expect("Error Start")),
-        end:   BaseElement::from(b.expect("Error End")),
-        nonce: BaseElement::new(123),
+    // --rows start:end / --cols a,b,c narrow the dump
+    let view = TraceView {
+        rows: cli.get("rows").map(|s| parse_rows(s).expect("error rows")),
+        columns: cli.get("cols").map(|s| s.split(',').map(str::to_string).collect()),
     };
+    print_trace_table(&trace, cfg.max_successors(), &view).expect("error cols");
+    // 2) public inputs
+    let public_inputs = PublicInputs { neighbor_encoding: encoding, ..path_inputs };
+
+    // --check: evaluate the constraints on the trace without proving
+    if cli.has("check") {
+        match StarkraAir::check_trace(&trace, &public_inputs) {
+            Ok(()) => println!("Trace check passed"),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        if let Err(e) = StarkraAir::check_neighbor_order(&trace) {
+            println!("note: neighbor slots are not in canonical order ({}); canonicalize the CFG for reproducible traces", e);
+        }
+    }
+    // --explain-row <r>: labelled constraint values between rows r and r + 1
+    if let Some(r) = cli.get("explain-row") {
+        let row: usize = r.parse().expect("error explain-row");
+        let values = StarkraAir::explain_row(&trace, &public_inputs, row).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        });
+        println!("constraints between rows {} and {}:", row, row + 1);
+        for (label, value) in values {
+            let status = if value == BaseElement::ZERO { "ok" } else { "VIOLATED" };
+            println!("  {:>9} = {:<20} {}", label, value.as_int(), status);
+        }
+        if !path_ok {
+            std::process::exit(1);
+        }
+    }
+    // --trace-out <file>: dump the trace as CSV and stop before proving
+    if let Some(out) = cli.get("trace-out") {
+        write_trace_csv(out, &trace, cfg.max_successors()).expect("error trace-out");
+        println!("Trace written to {}", out);
+        return;
+    }
 
     // 3) prover/options
-    let options = ProofOptions::new(
-        num_queries,
-        blowup_factor,
-        grinding_factor,
-        FieldExtension::Cubic,
-        4,
-        255,
-        winterfell::BatchingMethod::Linear,
-        winterfell::BatchingMethod::Linear,
-    );
-    let prover = StarkraProver::new(options);
+    let options = config.proof_options().expect("checked in run_config");
+    // --bundle-out <file>: save the case for `prove --bundle` / `replay`
+    if let (Some(out), Some(steps)) = (cli.get("bundle-out"), bundle_steps) {
+        let bundle = Bundle { cfg: cfg.clone(), steps, public_inputs: public_inputs.clone(), options: options.clone() };
+        write_bundle(out, &bundle).expect("error bundle-out");
+        println!("Bundle written to {}", out);
+    }
+    if cli.has("witness-only") {
+        run_witness_only(trace, options);
+        return;
+    }
+    let mut prover = StarkraProver::new(options)
+        .with_neighbor_encoding(encoding)
+        .with_path_inputs(public_inputs.clone());
+    // --partitions <n>: split LDE commitments into up to n column partitions
+    if let Some(n) = cli.get("partitions") {
+        let n: usize = n.parse().expect("error partitions");
+        if !(1..=MAX_PARTITIONS).contains(&n) {
+            eprintln!("error: --partitions must be between 1 and {}", MAX_PARTITIONS);
+            std::process::exit(2);
+        }
+        prover = prover.with_partitions(n);
+    }
+
+    // the inputs the proof will be bound to, checked against the claimed ones below
+    let committed = prover.get_pub_inputs(&trace);
+    // --keep-trace <file>: hold on to a copy of the trace and dump it as CSV
+    // if the proof fails to verify; without the flag nothing is kept
+    let kept = cli.get("keep-trace").map(|out| (out, trace.clone()));
+
+    println!("Estimated proving time: {}", estimate_prove_cost(trace.length(), trace.width(), prover.options()));
 
     // 4) generate proof (timed)
     let t_prove_start = Instant::now();
-    let proof = Prover::prove(&prover, trace).expect("prove");
+    let proof = if config.deterministic() {
+        prove_deterministic(&prover, trace)
+    } else {
+        Prover::prove(&prover, trace)
+    }
+    .expect("prove");
     let prove_dur = t_prove_start.elapsed();
     println!("Proving time: {:.3?}", prove_dur);
 
@@ -149,21 +538,28 @@ fn main() {
     let proof_bytes = proof.to_bytes();
     let proof_len = proof_bytes.len();
     println!("Proof size: {}", fmt_bytes(proof_len));
+    print!("{}", proof_size_breakdown(&proof));
 
 
-    // 5) verify (timed)
-    let min_security = AcceptableOptions::MinConjecturedSecurity(128);
-    let t_verify_start = Instant::now();
-    match verify::<
-        StarkraAir,
-        Blake3_256<BaseElement>,
-        DefaultRandomCoin<Blake3_256<BaseElement>>,
-        MerkleTree<Blake3_256<BaseElement>>,
-    >(proof, public_inputs, &min_security) {
-        Ok(_) => {println!("Valid Proof")}
-        Err(_) => {println!("Failed to verify proof")}
+    if let Some(out) = cli.get("proof-out") {
+        write_proof_file(out, &proof, &public_inputs).expect("error proof-out");
+        println!("Proof written to {}", out);
     }
+
+    // 5) verify (timed); a failed verification exits 1 after the report
+    let t_verify_start = Instant::now();
+    let outcome = VerifyOutcome::from(verify_path_strict(&encode_proof(&proof, &committed), &public_inputs, &cfg, &min_security));
     let verify_dur = t_verify_start.elapsed();
+    if outcome != VerifyOutcome::Valid {
+        println!("Failed to verify proof: {}", outcome);
+        if let Some((out, trace)) = &kept {
+            write_trace_csv(out, trace, cfg.max_successors()).expect("error keep-trace");
+            println!("Proved trace written to {}", out);
+        }
+        println!(" Verification failed in {:.3?}", verify_dur);
+        std::process::exit(1);
+    }
+    println!("Valid Proof");
     println!(" Verification succeeded in {:.3?}", verify_dur);
 
     // 6) summary line
@@ -0,0 +1,103 @@
+// mutations.rs
+//! Near-miss traces for testing the AIR: a valid trace with exactly one
+//! thing changed, each chosen so that a specific constraint or assertion
+//! must reject it. Check them with `StarkraAir::check_trace` against the
+//! public inputs of the original trace.
+
+use alloc::{vec, vec::Vec};
+
+use winterfell::{
+    Trace, TraceTable,
+    math::{FieldElement, fields::f64::BaseElement},
+};
+
+use crate::air::TraceLayout;
+
+/// A label and the `(row, column, new value)` cells it changes.
+type Mutation = (&'static str, Vec<(usize, usize, BaseElement)>);
+
+/// Every mutation that applies to `trace`, with a label naming what was
+/// changed. A mutation is skipped when the trace has nothing to apply it
+/// to (e.g. `ret_addr` for a path without rets, `resurrect` without
+/// padding).
+///
+/// | label | change | rejected by |
+/// |---|---|---|
+/// | `next_node` | a real row's `current` is not a neighbor of the row before | `neighbors` |
+/// | `neighbor` | the neighbor slot `taken` points at is replaced | `taken_edge` |
+/// | `taken` | `taken` is out of range | `taken_range` |
+/// | `ret_addr` | the stack top before a ret does not match its target | `stack` |
+/// | `ret_flag` | a non-ret row is flagged as a ret | `stack` |
+/// | `stack_top` | the stack top changes into a jump row | `stack_hold` |
+/// | `call_ret` | a row is flagged both call and ret | `call_ret` |
+/// | `call_flag` | a jump row's `call` is 2 | `call_bool` |
+/// | `resurrect` | a padding row is made real | `step` |
+/// | `valid_dropped` | a real row is made padding | `step` |
+/// | `nonce` | a real row's nonce differs from the row before | `nonce` |
+/// | `step` | the step counter skips | `step` |
+/// | `budget` | the budget slack grows by 2 | `budget` |
+/// | `forbidden_inv` | the forbidden-node inverse is wrong | `forbidden` |
+/// | `start`, `end` | the first or last `current` | the endpoint assertions |
+pub fn mutations(
+    trace: &TraceTable<BaseElement>,
+    layout: &TraceLayout,
+) -> impl Iterator<Item = (TraceTable<BaseElement>, &'static str)> {
+    let length = trace.length();
+    let l = *layout;
+    let get = |c: usize, r: usize| trace.get(c, r);
+    let is_real = |r: usize| get(l.valid, r) == BaseElement::ONE;
+
+    // a node value no node column of the trace holds
+    let mut fresh = BaseElement::ZERO;
+    for r in 0..length {
+        for c in [l.current, l.stack].into_iter().chain(l.neighbors()) {
+            fresh = BaseElement::new(fresh.as_int().max(get(c, r).as_int()));
+        }
+    }
+    let fresh = fresh + BaseElement::ONE;
+
+    let mut edits: Vec<Mutation> = Vec::new();
+    // a real row followed by a real row
+    if let Some(r) = (0..length - 1).find(|&r| is_real(r) && is_real(r + 1)) {
+        edits.push(("next_node", vec![(r + 1, l.current, fresh)]));
+        let slot = get(l.taken, r).as_int() as usize;
+        if slot < l.max_succ {
+            edits.push(("neighbor", vec![(r, l.neighbors_start + slot, fresh)]));
+        }
+        edits.push(("taken", vec![(r, l.taken, BaseElement::new(l.max_succ as u64))]));
+        edits.push(("nonce", vec![(r + 1, l.nonce, get(l.nonce, r + 1) + BaseElement::ONE)]));
+        edits.push(("step", vec![(r + 1, l.step, get(l.step, r + 1) + BaseElement::ONE)]));
+        edits.push(("budget", vec![(r + 1, l.budget, get(l.budget, r + 1) + BaseElement::new(2))]));
+        edits.push(("valid_dropped", vec![(r + 1, l.valid, BaseElement::ZERO)]));
+        edits.push(("forbidden_inv", vec![(r, l.forbidden_inv, get(l.forbidden_inv, r).double())]));
+    }
+    if let Some(r) = (0..length - 1).find(|&r| is_real(r) && is_real(r + 1) && get(l.ret, r + 1) == BaseElement::ONE) {
+        edits.push(("ret_addr", vec![(r, l.stack, fresh)]));
+    }
+    if let Some(r) = (0..length - 1).find(|&r| {
+        is_real(r) && is_real(r + 1) && get(l.ret, r + 1) == BaseElement::ZERO && get(l.stack, r) != get(l.current, r + 1)
+    }) {
+        edits.push(("ret_flag", vec![(r + 1, l.ret, BaseElement::ONE)]));
+    }
+    if let Some(r) = (0..length - 1).find(|&r| {
+        is_real(r) && is_real(r + 1) && get(l.call, r + 1) == BaseElement::ZERO && get(l.ret, r + 1) == BaseElement::ZERO
+    }) {
+        edits.push(("stack_top", vec![(r + 1, l.stack, fresh)]));
+        edits.push(("call_flag", vec![(r + 1, l.call, BaseElement::new(2))]));
+    }
+    if let Some(r) = (1..length).find(|&r| !is_real(r) && is_real(r - 1)) {
+        edits.push(("resurrect", vec![(r, l.valid, BaseElement::ONE)]));
+    }
+    edits.push(("call_ret", vec![(1, l.call, BaseElement::ONE), (1, l.ret, BaseElement::ONE)]));
+    edits.push(("start", vec![(0, l.current, fresh)]));
+    edits.push(("end", vec![(length - 1, l.current, fresh)]));
+
+    let trace = trace.clone();
+    edits.into_iter().map(move |(label, cells)| {
+        let mut mutated = trace.clone();
+        for (r, c, value) in cells {
+            mutated.set(c, r, value);
+        }
+        (mutated, label)
+    })
+}
@@ -0,0 +1,140 @@
+// profiling.rs
+//! Witness-only proving, for profiling the commitment/FRI cost of a trace
+//! shape in isolation from the Starkra constraints.
+//!
+//! **Proofs produced here are meaningless.** The AIR has a single
+//! constraint that is identically zero and a single assertion on the nonce
+//! cell, so it accepts virtually any trace. Never verify them as
+//! path-validity proofs; use [`crate::prover::StarkraProver`] for that.
+
+use winterfell::{
+    Air, AirContext, Assertion, CompositionPoly, CompositionPolyTrace, DefaultConstraintCommitment,
+    DefaultConstraintEvaluator, DefaultTraceLde, EvaluationFrame, PartitionOptions, Proof,
+    ProofOptions, Prover, ProverError, StarkDomain, Trace, TraceInfo, TracePolyTable, TraceTable,
+    TransitionConstraintDegree,
+    crypto::{DefaultRandomCoin, MerkleTree, hashers::Blake3_256},
+    math::{FieldElement, fields::f64::BaseElement},
+    matrix::ColMatrix,
+};
+
+use crate::air::{Column, NeighborEncoding, PublicInputs, TraceLayout, trace_get};
+
+/// Prove `trace` against the trivially-satisfiable AIR.
+pub fn prove_witness_only(trace: TraceTable<BaseElement>, options: ProofOptions) -> Result<Proof, ProverError> {
+    WitnessOnlyProver { options }.prove(trace)
+}
+
+pub struct WitnessOnlyAir {
+    context: AirContext<BaseElement>,
+    nonce: BaseElement,
+}
+
+impl Air for WitnessOnlyAir {
+    type BaseField = BaseElement;
+    type PublicInputs = PublicInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        // an identically-zero degree-1 constraint passes winterfell's degree check
+        let degrees = vec![TransitionConstraintDegree::new(1)];
+        let context = AirContext::new(trace_info, degrees, 1, options);
+        Self { context, nonce: pub_inputs.nonce }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        _frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        result[0] = E::ZERO;
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        vec![Assertion::single(0, 0, self.nonce)]
+    }
+}
+
+pub struct WitnessOnlyProver {
+    options: ProofOptions,
+}
+
+impl Prover for WitnessOnlyProver {
+    type BaseField = BaseElement;
+    type Air = WitnessOnlyAir;
+    type Trace = TraceTable<Self::BaseField>;
+
+    type HashFn = Blake3_256<Self::BaseField>;
+    type VC = MerkleTree<Self::HashFn>;
+    type RandomCoin = DefaultRandomCoin<Self::HashFn>;
+
+    type TraceLde<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
+        let last = trace.length() - 1;
+        // witness traces never carry selectors
+        let layout = TraceLayout::from_width(trace.width());
+        // node columns hold `encode_node(n) = n + 1`
+        PublicInputs {
+            start: trace_get(trace, &layout, Column::Current, 0) - BaseElement::ONE,
+            end: trace_get(trace, &layout, Column::Current, last) - BaseElement::ONE,
+            nonce: trace_get(trace, &layout, Column::Nonce, 0),
+            segment_nonces: Vec::new(),
+            forbidden: Vec::new(),
+            num_steps: None,
+            max_steps: None,
+            waypoints: Vec::new(),
+            cfg_commitment: None,
+            sequence_root: None,
+            prefix: Vec::new(),
+            neighbor_encoding: NeighborEncoding::Product,
+            entry_policies: Vec::new(),
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<Self::BaseField>,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        DefaultTraceLde::new(trace_info, main_trace, domain, partition_options)
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<winterfell::AuxRandElements<E>>,
+        composition_coefficients: winterfell::ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        DefaultConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+}
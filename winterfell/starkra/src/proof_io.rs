@@ -0,0 +1,330 @@
+// proof_io.rs
+use alloc::{format, string::ToString, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+
+use winter_utils::Serializable;
+use winterfell::{
+    Proof,
+    math::{StarkField, fields::f64::BaseElement},
+};
+
+use crate::{
+    air::{NeighborEncoding, PublicInputs},
+    cfg::EntryPolicy,
+    error::StarkraError,
+};
+
+// Proof file layout (all integers little-endian):
+//   b"SKPF" | version: u8 | public inputs | proof bytes (winterfell encoding)
+// public inputs:
+//   start: u64 | end: u64 | nonce: u64
+//   n_segments: u32 | (first_row: u64, nonce: u64) * n_segments
+//   n_forbidden: u32 | node: u64 * n_forbidden
+//   has_steps: u8 | num_steps: u64 (only if has_steps = 1)
+//   has_max: u8 | max_steps: u64 (only if has_max = 1)
+//   neighbor_encoding: u8 (0 = product, 1 = one-hot)
+//   n_waypoints: u32 | (step: u64, node: u64) * n_waypoints
+//   has_cfg: u8 | cfg_commitment: [u8; 32] (only if has_cfg = 1)
+//   has_root: u8 | sequence_root: [u8; 32] (only if has_root = 1)
+//   n_prefix: u32 | node: u64 * n_prefix
+//   n_policies: u32 | (node: u64, kinds: u8) * n_policies
+//     (kinds: bit 0 = call, bit 1 = ret, bit 2 = jump)
+pub const PROOF_MAGIC: &[u8; 4] = b"SKPF";
+pub const PROOF_VERSION: u8 = 10;
+
+impl PublicInputs {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for e in [self.start, self.end, self.nonce] {
+            out.extend_from_slice(&e.as_int().to_le_bytes());
+        }
+        out.extend_from_slice(&(self.segment_nonces.len() as u32).to_le_bytes());
+        for &(row, nonce) in &self.segment_nonces {
+            out.extend_from_slice(&(row as u64).to_le_bytes());
+            out.extend_from_slice(&nonce.as_int().to_le_bytes());
+        }
+        out.extend_from_slice(&(self.forbidden.len() as u32).to_le_bytes());
+        for f in &self.forbidden {
+            out.extend_from_slice(&f.as_int().to_le_bytes());
+        }
+        match self.num_steps {
+            Some(n) => {
+                out.push(1);
+                out.extend_from_slice(&(n as u64).to_le_bytes());
+            }
+            None => out.push(0),
+        }
+        match self.max_steps {
+            Some(n) => {
+                out.push(1);
+                out.extend_from_slice(&(n as u64).to_le_bytes());
+            }
+            None => out.push(0),
+        }
+        out.push(self.neighbor_encoding.tag());
+        out.extend_from_slice(&(self.waypoints.len() as u32).to_le_bytes());
+        for &(step, node) in &self.waypoints {
+            out.extend_from_slice(&(step as u64).to_le_bytes());
+            out.extend_from_slice(&node.as_int().to_le_bytes());
+        }
+        for digest in [&self.cfg_commitment, &self.sequence_root] {
+            match digest {
+                Some(c) => {
+                    out.push(1);
+                    out.extend_from_slice(c);
+                }
+                None => out.push(0),
+            }
+        }
+        out.extend_from_slice(&(self.prefix.len() as u32).to_le_bytes());
+        for n in &self.prefix {
+            out.extend_from_slice(&n.as_int().to_le_bytes());
+        }
+        out.extend_from_slice(&(self.entry_policies.len() as u32).to_le_bytes());
+        for (node, policy) in &self.entry_policies {
+            out.extend_from_slice(&node.as_int().to_le_bytes());
+            out.push(policy.bits());
+        }
+        out
+    }
+
+    /// Decode public inputs from the front of `bytes`.
+    /// Returns the inputs and the number of bytes consumed.
+    pub fn read_from(bytes: &[u8]) -> Result<(Self, usize), StarkraError> {
+        let mut r = Reader::new(bytes);
+        let start = r.element()?;
+        let end = r.element()?;
+        let nonce = r.element()?;
+        let n_segments = r.u32()? as usize;
+        let mut segment_nonces = Vec::with_capacity(n_segments.min(bytes.len()));
+        for _ in 0..n_segments {
+            let row = r.u64()? as usize;
+            segment_nonces.push((row, r.element()?));
+        }
+        let n_forbidden = r.u32()? as usize;
+        let mut forbidden = Vec::with_capacity(n_forbidden.min(bytes.len()));
+        for _ in 0..n_forbidden {
+            forbidden.push(r.element()?);
+        }
+        let num_steps = match r.u8()? {
+            0 => None,
+            1 => Some(r.u64()? as usize),
+            flag => return Err(StarkraError::Parse(format!("invalid step count flag {}", flag))),
+        };
+        let max_steps = match r.u8()? {
+            0 => None,
+            1 => Some(r.u64()? as usize),
+            flag => return Err(StarkraError::Parse(format!("invalid step bound flag {}", flag))),
+        };
+        let tag = r.u8()?;
+        let neighbor_encoding = NeighborEncoding::from_tag(tag)
+            .ok_or_else(|| StarkraError::Parse(format!("invalid neighbor encoding {}", tag)))?;
+        let n_waypoints = r.u32()? as usize;
+        let mut waypoints = Vec::with_capacity(n_waypoints.min(bytes.len()));
+        for _ in 0..n_waypoints {
+            let step = r.u64()? as usize;
+            waypoints.push((step, r.element()?));
+        }
+        let cfg_commitment = match r.u8()? {
+            0 => None,
+            1 => Some(r.take::<32>()?),
+            flag => return Err(StarkraError::Parse(format!("invalid CFG commitment flag {}", flag))),
+        };
+        let sequence_root = match r.u8()? {
+            0 => None,
+            1 => Some(r.take::<32>()?),
+            flag => return Err(StarkraError::Parse(format!("invalid sequence root flag {}", flag))),
+        };
+        let n_prefix = r.u32()? as usize;
+        let mut prefix = Vec::with_capacity(n_prefix.min(bytes.len()));
+        for _ in 0..n_prefix {
+            prefix.push(r.element()?);
+        }
+        let n_policies = r.u32()? as usize;
+        let mut entry_policies = Vec::with_capacity(n_policies.min(bytes.len()));
+        for _ in 0..n_policies {
+            let node = r.element()?;
+            let bits = r.u8()?;
+            let policy = EntryPolicy::from_bits(bits)
+                .ok_or_else(|| StarkraError::Parse(format!("invalid entry policy {:#x}", bits)))?;
+            entry_policies.push((node, policy));
+        }
+        Ok((
+            PublicInputs {
+                start,
+                end,
+                nonce,
+                segment_nonces,
+                forbidden,
+                num_steps,
+                max_steps,
+                waypoints,
+                cfg_commitment,
+                sequence_root,
+                prefix,
+                neighbor_encoding,
+                entry_policies,
+            },
+            r.pos,
+        ))
+    }
+}
+
+/// Encode a proof together with the public inputs it is verified against.
+pub fn encode_proof(proof: &Proof, pub_inputs: &PublicInputs) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(PROOF_MAGIC);
+    out.push(PROOF_VERSION);
+    out.extend_from_slice(&pub_inputs.to_bytes());
+    out.extend_from_slice(&proof.to_bytes());
+    out
+}
+
+pub fn decode_proof(bytes: &[u8]) -> Result<(Proof, PublicInputs), StarkraError> {
+    check_header(bytes)?;
+    let (pub_inputs, used) = PublicInputs::read_from(&bytes[5..])?;
+    let proof = decode_proof_body(&bytes[5 + used..])?;
+    Ok((proof, pub_inputs))
+}
+
+/// The magic and version that open an encoded proof (5 bytes).
+pub(crate) fn check_header(bytes: &[u8]) -> Result<(), StarkraError> {
+    if bytes.len() < 5 || &bytes[..4] != PROOF_MAGIC {
+        return Err(StarkraError::Parse("missing proof file header".to_string()));
+    }
+    if bytes[4] != PROOF_VERSION {
+        return Err(StarkraError::Parse(format!("unsupported proof file version {}", bytes[4])));
+    }
+    Ok(())
+}
+
+/// The winterfell proof that follows the public inputs.
+pub(crate) fn decode_proof_body(bytes: &[u8]) -> Result<Proof, StarkraError> {
+    Proof::from_bytes(bytes).map_err(|e| StarkraError::Parse(format!("invalid proof: {}", e)))
+}
+
+#[cfg(feature = "std")]
+pub fn write_proof_file(path: &str, proof: &Proof, pub_inputs: &PublicInputs) -> Result<(), StarkraError> {
+    fs::write(path, encode_proof(proof, pub_inputs))
+        .map_err(|e| StarkraError::Io(format!("Failed to write '{}': {}", path, e)))
+}
+
+#[cfg(feature = "std")]
+pub fn read_proof_file(path: &str) -> Result<(Proof, PublicInputs), StarkraError> {
+    let bytes = fs::read(path)
+        .map_err(|e| StarkraError::Io(format!("Failed to read '{}': {}", path, e)))?;
+    decode_proof(&bytes)
+}
+
+/// Serialized size in bytes of each part of a proof, as laid out by
+/// `Proof::to_bytes`. The fields sum to the total proof size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofSizeBreakdown {
+    /// Proof context (trace info, options) and the unique-query count.
+    pub context: usize,
+    /// Trace, constraint and FRI layer commitments.
+    pub commitments: usize,
+    /// Trace openings at the query positions, with authentication paths.
+    pub trace_queries: usize,
+    /// Constraint evaluation openings at the query positions.
+    pub constraint_queries: usize,
+    /// Out-of-domain evaluation frame.
+    pub ood_frame: usize,
+    /// FRI layer openings and remainder.
+    pub fri: usize,
+    /// Proof-of-work nonce.
+    pub pow_nonce: usize,
+}
+
+impl ProofSizeBreakdown {
+    pub fn total(&self) -> usize {
+        self.context
+            + self.commitments
+            + self.trace_queries
+            + self.constraint_queries
+            + self.ood_frame
+            + self.fri
+            + self.pow_nonce
+    }
+}
+
+/// One `name: bytes (percent)` line per component.
+impl fmt::Display for ProofSizeBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total().max(1) as f64;
+        let parts = [
+            ("context", self.context),
+            ("commitments", self.commitments),
+            ("trace queries", self.trace_queries),
+            ("constraint queries", self.constraint_queries),
+            ("ood frame", self.ood_frame),
+            ("fri", self.fri),
+            ("pow nonce", self.pow_nonce),
+        ];
+        for (name, bytes) in parts {
+            writeln!(f, "  {:<18} {:>8} bytes ({:>5.1}%)", name, bytes, 100.0 * bytes as f64 / total)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn proof_size_breakdown(proof: &Proof) -> ProofSizeBreakdown {
+    ProofSizeBreakdown {
+        // +1 for `num_unique_queries`
+        context: proof.context.to_bytes().len() + 1,
+        commitments: proof.commitments.to_bytes().len(),
+        trace_queries: proof.trace_queries.iter().map(|q| q.to_bytes().len()).sum(),
+        constraint_queries: proof.constraint_queries.to_bytes().len(),
+        ood_frame: proof.ood_frame.to_bytes().len(),
+        fri: proof.fri_proof.to_bytes().len(),
+        pow_nonce: proof.pow_nonce.to_bytes().len(),
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// The next `n` bytes.
+    pub(crate) fn slice(&mut self, n: usize) -> Result<&'a [u8], StarkraError> {
+        let chunk = self
+            .bytes
+            .get(self.pos..self.pos.saturating_add(n))
+            .ok_or_else(|| StarkraError::Parse(format!("Offset {}: truncated input", self.pos)))?;
+        self.pos += n;
+        Ok(chunk)
+    }
+
+    pub(crate) fn take<const N: usize>(&mut self) -> Result<[u8; N], StarkraError> {
+        Ok(self.slice(N)?.try_into().expect("chunk has length N"))
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, StarkraError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, StarkraError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, StarkraError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn element(&mut self) -> Result<BaseElement, StarkraError> {
+        let at = self.pos;
+        let v = self.u64()?;
+        if v >= BaseElement::MODULUS {
+            return Err(StarkraError::Parse(format!("Offset {}: non-canonical field element {}", at, v)));
+        }
+        Ok(BaseElement::new(v))
+    }
+}
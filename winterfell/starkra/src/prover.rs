@@ -1,34 +1,393 @@
 // use winterfell::{
 
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
 use winterfell::{
     CompositionPoly, CompositionPolyTrace, DefaultConstraintCommitment, DefaultConstraintEvaluator,
-    DefaultTraceLde, PartitionOptions, ProofOptions, Prover, StarkDomain, Trace, TraceInfo,
+    DefaultTraceLde, PartitionOptions, Proof, ProofOptions, Prover, ProverError, StarkDomain, Trace, TraceInfo,
     TracePolyTable, TraceTable,
-    crypto::{DefaultRandomCoin, MerkleTree, hashers::Blake3_256},
+    crypto::VectorCommitment,
     math::{FieldElement, fields::f64::BaseElement},
     matrix::ColMatrix,
 };
 
-use crate::air::{PublicInputs, StarkraAir};
+use crate::{
+    air::{Column, NeighborEncoding, PublicInputs, StarkraAir, TraceLayout},
+    cfg::{Cfg, EntryPolicy, NodeId, SuccessorOrder},
+    error::{PathError, StarkraError},
+    exe_path::Step,
+    source::CfgSource,
+};
+
+pub use crate::verifier::{DefaultVC, HashFn, RandCoin};
+
+/// Field elements per BLAKE3 input block (64 bytes / 8-byte elements).
+const HASH_RATE: usize = 8;
+
+/// Most partitions winterfell accepts.
+pub const MAX_PARTITIONS: usize = 16;
 
-pub struct StarkraProver {
+/// Proves paths over a CFG. `VC` selects the vector commitment for the
+/// trace and constraint LDEs; a proof only verifies with the same `VC`
+/// (see `verifier::verify_path_with_vc`).
+pub struct StarkraProver<VC = DefaultVC> {
     options: ProofOptions,
+    forbidden: Vec<NodeId>,
+    count_steps: bool,
+    max_steps: Option<usize>,
+    waypoints: Vec<(usize, NodeId)>,
+    cfg_commitment: Option<[u8; 32]>,
+    /// sequence root and prefix length
+    committed_prefix: Option<([u8; 32], usize)>,
+    neighbor_encoding: NeighborEncoding,
+    entry_policies: Vec<(NodeId, EntryPolicy)>,
+    /// `PublicInputs::from_path` of the path being proved, if given
+    path_inputs: Option<PublicInputs>,
+    _vc: PhantomData<fn() -> VC>,
 }
 impl StarkraProver {
     pub fn new(options: ProofOptions) -> Self {
-        Self { options }
+        Self::with_vector_commitment(options)
+    }
+}
+
+impl<VC> StarkraProver<VC> {
+    /// A prover committing with `VC`, e.g.
+    /// `StarkraProver::<MyVC>::with_vector_commitment(options)`.
+    pub fn with_vector_commitment(options: ProofOptions) -> Self {
+        Self {
+            options,
+            forbidden: Vec::new(),
+            count_steps: false,
+            max_steps: None,
+            waypoints: Vec::new(),
+            cfg_commitment: None,
+            committed_prefix: None,
+            neighbor_encoding: NeighborEncoding::Product,
+            entry_policies: Vec::new(),
+            path_inputs: None,
+            _vc: PhantomData,
+        }
+    }
+
+    /// Prove that the path avoids these nodes; the trace must be built with
+    /// `StarkraAir::build_trace_avoiding` over the same set.
+    pub fn with_forbidden(mut self, forbidden: Vec<NodeId>) -> Self {
+        self.forbidden = forbidden;
+        self
+    }
+
+    /// Also prove how many steps the path took: the public inputs claim the
+    /// trace's number of real rows (all segments together) as `num_steps`.
+    pub fn with_step_count(mut self) -> Self {
+        self.count_steps = true;
+        self
+    }
+
+    /// Prove that the path took at most `max_steps` steps, without
+    /// revealing how many; the trace must be built with
+    /// `StarkraAir::build_trace_bounded` over the same bound.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Claim that the path is at `node` on each given `(step, node)` (see
+    /// `PublicInputs::waypoints`). A waypoint the trace does not pass
+    /// through gives a proof that fails verification.
+    pub fn with_waypoints(mut self, waypoints: Vec<(usize, NodeId)>) -> Self {
+        self.waypoints = waypoints;
+        self
+    }
+
+    /// Name the CFG the proof is for in its public inputs, so a verifier
+    /// holding several versions of a CFG can pick the right one (see
+    /// `verifier::verify_path_any`). Pass `cfg.commitment()`.
+    pub fn with_cfg_commitment(mut self, commitment: [u8; 32]) -> Self {
+        self.cfg_commitment = Some(commitment);
+        self
+    }
+
+    /// Claim that the trace's first `len` rows are the start of the node
+    /// sequence committed to by `root` (see `PublicInputs::sequence_root`
+    /// and `sequence::SequenceCommitment`); the public inputs carry the
+    /// trace's nodes on those rows as `prefix`. A trace that deviates from
+    /// the committed sequence gives a proof `verifier::verify_prefix`
+    /// rejects.
+    pub fn with_committed_prefix(mut self, root: [u8; 32], len: usize) -> Self {
+        self.committed_prefix = Some((root, len));
+        self
+    }
+
+    /// Prove with `encoding`'s neighbor constraints; for `OneHot` the
+    /// trace must carry selector columns (`StarkraAir::add_selectors`).
+    pub fn with_neighbor_encoding(mut self, encoding: NeighborEncoding) -> Self {
+        self.neighbor_encoding = encoding;
+        self
+    }
+
+    /// Prove that every step into one of these nodes is of a kind its
+    /// policy allows (see `PublicInputs::entry_policies`). Pass the
+    /// CFG's, `cfg.entry_policies()`; the verifier rejects any others.
+    pub fn with_entry_policies(mut self, policies: Vec<(NodeId, EntryPolicy)>) -> Self {
+        self.entry_policies = policies;
+        self
+    }
+
+    /// Claim the start, end and nonce of `inputs` (from
+    /// `PublicInputs::from_path`) instead of reading them off the trace,
+    /// and its CFG commitment and entry policies, so the proof's public
+    /// inputs are the path's. A trace built from another path gives a
+    /// proof that fails verification.
+    pub fn with_path_inputs(mut self, inputs: PublicInputs) -> Self {
+        self.cfg_commitment = inputs.cfg_commitment;
+        self.entry_policies = inputs.entry_policies.iter().map(|&(n, p)| (n.as_int(), p)).collect();
+        self.path_inputs = Some(inputs);
+        self
+    }
+
+    /// Commit to the trace and constraint LDEs in up to `num_partitions`
+    /// column partitions, hashing each partition of a row separately.
+    /// Stored in the proof options, so the proof carries it and the
+    /// verifier needs no extra setting.
+    ///
+    /// Trace partitions are at least one hash block (8 columns) wide, so a
+    /// layout narrower than 16 columns still commits its trace as one.
+    /// Partitioning does not shrink the LDE, which dominates memory; on a
+    /// single core a 2^18-row, 16-column trace kept the same peak memory and
+    /// proved slower (37 s at 1, 39 s at 2, 45 s at 4 partitions). Any gain
+    /// comes from hashing partitions in parallel on multi-core machines.
+    ///
+    /// # Panics
+    /// If `num_partitions` is 0 or above [`MAX_PARTITIONS`].
+    pub fn with_partitions(mut self, num_partitions: usize) -> Self {
+        self.options = self.options.with_partitions(num_partitions, HASH_RATE);
+        self
+    }
+}
+
+/// Whether [`prove_path_with`] checks that the path's last node is
+/// reachable from its first before building the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reachability {
+    #[default]
+    Check,
+    /// For CFGs that are intentionally incomplete.
+    Skip,
+}
+
+/// Build the trace for `path` over `cfg` and prove it, claiming
+/// `PublicInputs::from_path` (so the proof carries `cfg`'s commitment). A
+/// path starting outside the CFG's declared entry points is rejected with
+/// `IllegalEntry`, and one whose end cannot follow from its start in `cfg`
+/// with `UnreachableEndpoints`, both before the trace is built.
+///
+/// A CFG without edges (`max_successors() == 0`) has no legal transition,
+/// so only a single-node path is provable over it; a longer one is
+/// rejected up front with an `IllegalEdge` at step 1 (a skipped
+/// reachability check does not change that).
+///
+/// With the `zeroize` feature the trace storage is scrubbed before this
+/// returns; see [`crate::scrub`] for what that does and does not cover.
+pub fn prove_path(
+    path: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    options: ProofOptions,
+) -> Result<(Proof, PublicInputs), StarkraError> {
+    prove_path_with(path, cfg, nonce, options, Reachability::Check)
+}
+
+/// `prove_path` over a CFG loaded from `source`, e.g. a
+/// [`crate::source::CfgFile`]. The loaded CFG is returned with the proof,
+/// since the verifier needs it too. Loading errors come back as they are.
+pub fn prove_path_from(
+    path: Vec<Step>,
+    source: impl CfgSource,
+    nonce: u32,
+    options: ProofOptions,
+) -> Result<(Proof, PublicInputs, Cfg), StarkraError> {
+    let cfg = source.into_cfg()?;
+    let (proof, pub_inputs) = prove_path(path, &cfg, nonce, options)?;
+    Ok((proof, pub_inputs, cfg))
+}
+
+/// `prove_path`, with the reachability check optional.
+pub fn prove_path_with(
+    path: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    options: ProofOptions,
+    reachability: Reachability,
+) -> Result<(Proof, PublicInputs), StarkraError> {
+    check_provable(&path, cfg, reachability)?;
+    let pub_inputs = PublicInputs::from_path(cfg, &path, nonce)?;
+    prove_built(StarkraAir::build_trace(path, cfg.clone(), nonce), pub_inputs, options)
+}
+
+/// `prove_path` over `cfg` with its successor lists rearranged by `order`
+/// first (see [`Cfg::order_successors`]). The commitment does not depend on
+/// successor order, so the proof verifies against `cfg` as given.
+pub fn prove_path_ordered(
+    path: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    options: ProofOptions,
+    order: SuccessorOrder<'_>,
+) -> Result<(Proof, PublicInputs), StarkraError> {
+    let mut ordered = cfg.clone();
+    ordered.order_successors(order);
+    prove_path(path, &ordered, nonce, options)
+}
+
+/// `prove_path_with`, also returning the trace that was proved, so that a
+/// proof which later fails to verify can be examined against it (e.g. with
+/// `trace_io::print_trace_table_with_headers` or `trace_io::write_trace_csv`).
+/// The trace is copied before proving and the copy kept alive for the
+/// caller, so this is for debugging; `prove_path` keeps nothing. With the
+/// `zeroize` feature the proved trace is still scrubbed, the returned copy
+/// is not.
+pub fn prove_path_retaining(
+    path: Vec<Step>,
+    cfg: &Cfg,
+    nonce: u32,
+    options: ProofOptions,
+    reachability: Reachability,
+) -> Result<(Proof, PublicInputs, TraceTable<BaseElement>), StarkraError> {
+    check_provable(&path, cfg, reachability)?;
+    let pub_inputs = PublicInputs::from_path(cfg, &path, nonce)?;
+    let trace = StarkraAir::build_trace(path, cfg.clone(), nonce);
+    let kept = trace.clone();
+    let (proof, pub_inputs) = prove_built(trace, pub_inputs, options)?;
+    Ok((proof, pub_inputs, kept))
+}
+
+/// The up-front checks of [`prove_path`].
+fn check_provable(path: &[Step], cfg: &Cfg, reachability: Reachability) -> Result<(), StarkraError> {
+    let node = |s: Option<&Step>| s.and_then(|s| s.addrs.first()).copied();
+    if let Some(start) = node(path.first())
+        && !cfg.is_legal_entry(start)
+    {
+        return Err(StarkraError::IllegalEntry(start));
+    }
+    if cfg.max_successors() == 0
+        && let (Some(from), Some(to)) = (node(path.first()), node(path.get(1)))
+    {
+        return Err(PathError::IllegalEdge { step: 1, from, to }.into());
+    }
+    if reachability == Reachability::Check
+        && let (Some(start), Some(end)) = (node(path.first()), node(path.last()))
+        && !cfg.reaches(start, end)
+    {
+        return Err(StarkraError::UnreachableEndpoints { start, end });
+    }
+    Ok(())
+}
+
+fn prove_built(
+    trace: TraceTable<BaseElement>,
+    path_inputs: PublicInputs,
+    options: ProofOptions,
+) -> Result<(Proof, PublicInputs), StarkraError> {
+    let layout = TraceLayout::from_width_with(trace.width(), path_inputs.neighbor_encoding);
+    path_inputs.check_blowup(&layout, options.blowup_factor())?;
+
+    #[cfg(feature = "zeroize")]
+    let result = crate::scrub::prove_scrubbed(trace, path_inputs, options);
+
+    #[cfg(not(feature = "zeroize"))]
+    let result = {
+        let prover = StarkraProver::new(options).with_path_inputs(path_inputs);
+        let pub_inputs = prover.get_pub_inputs(&trace);
+        prover.prove(trace).map(|proof| (proof, pub_inputs))
+    };
+
+    result.map_err(StarkraError::Prove)
+}
+
+/// Prove `trace` so that the same trace and options always give the same
+/// proof bytes.
+///
+/// Every challenge winterfell draws comes from the Fiat-Shamir coin, which
+/// is seeded from the public inputs and the commitments, so a proof has no
+/// randomness of its own. The one exception is the proof-of-work nonce:
+/// with `std` (winterfell's `concurrent` feature) the grinding search runs
+/// as a parallel `find_any` and returns whichever passing nonce a worker
+/// thread hits first. That happens for `grinding_factor = 0` too, where
+/// every nonce passes. Here the whole proof runs on a one-thread rayon
+/// pool, so the search checks nonces in order and returns the first that
+/// passes; proving is correspondingly slower on multi-core machines.
+/// Without `std` the search is sequential already.
+pub fn prove_deterministic<P>(prover: &P, trace: P::Trace) -> Result<Proof, ProverError>
+where
+    P: Prover + Sync,
+    P::Trace: Send,
+{
+    #[cfg(feature = "std")]
+    {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .expect("a one-thread pool")
+            .install(|| prover.prove(trace))
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        prover.prove(trace)
+    }
+}
+
+/// Public inputs of a main trace segment: start/end, the nonce of every
+/// segment, the given forbidden nodes and, with `count_steps`, the number
+/// of real rows, for a trace laid out with `encoding`.
+pub(crate) fn pub_inputs_from(
+    trace: &ColMatrix<BaseElement>,
+    forbidden: &[NodeId],
+    count_steps: bool,
+    encoding: NeighborEncoding,
+) -> PublicInputs {
+    let length = trace.num_rows();
+    let layout = TraceLayout::from_width_with(trace.num_cols(), encoding);
+    let (nonce, current, valid) =
+        (layout.index(Column::Nonce), layout.index(Column::Current), layout.index(Column::Valid));
+
+    // a segment starts wherever a `valid = 0` row is followed by a real row
+    let segment_nonces = (1..length)
+        .filter(|&r| trace.get(valid, r - 1) == BaseElement::ZERO && trace.get(valid, r) == BaseElement::ONE)
+        .map(|r| (r, trace.get(nonce, r)))
+        .collect();
+
+    // node columns hold `encode_node(n) = n + 1`
+    PublicInputs {
+        start: trace.get(current, 0) - BaseElement::ONE,
+        end: trace.get(current, length - 1) - BaseElement::ONE,
+        nonce: trace.get(nonce, 0),
+        segment_nonces,
+        forbidden: forbidden.iter().map(|&f| BaseElement::new(f)).collect(),
+        num_steps: count_steps.then(|| (0..length).filter(|&r| trace.get(valid, r) == BaseElement::ONE).count()),
+        max_steps: None,
+        waypoints: Vec::new(),
+        cfg_commitment: None,
+        sequence_root: None,
+        prefix: Vec::new(),
+        neighbor_encoding: encoding,
+        entry_policies: Vec::new(),
     }
 }
 
-impl Prover for StarkraProver {
+impl<VC> Prover for StarkraProver<VC>
+where
+    VC: VectorCommitment<HashFn> + Sync,
+{
     type BaseField = BaseElement;
     type Air = StarkraAir; // your AIR from earlier
     type Trace = TraceTable<Self::BaseField>;
 
     // Hash / commitments / coin
-    type HashFn = Blake3_256<Self::BaseField>;
-    type VC = MerkleTree<Self::HashFn>;
-    type RandomCoin = DefaultRandomCoin<Self::HashFn>;
+    type HashFn = HashFn;
+    type VC = VC;
+    type RandomCoin = RandCoin;
 
     // ---- “defaults” that must be spelled out on the trait ----
     type TraceLde<E: FieldElement<BaseField = Self::BaseField>> =
@@ -39,11 +398,29 @@ impl Prover for StarkraProver {
         DefaultConstraintEvaluator<'a, Self::Air, E>;
 
     fn get_pub_inputs(&self, trace: &Self::Trace) -> <Self::Air as winterfell::Air>::PublicInputs {
-        let last = trace.length() - 1;
+        let pub_inputs = pub_inputs_from(trace.main_segment(), &self.forbidden, self.count_steps, self.neighbor_encoding);
+        let current = TraceLayout::from_width_with(trace.width(), self.neighbor_encoding).current;
+        // node columns hold `encode_node(n) = n + 1`
+        let prefix = match self.committed_prefix {
+            Some((_, len)) => (0..len.min(trace.length())).map(|r| trace.get(current, r) - BaseElement::ONE).collect(),
+            None => Vec::new(),
+        };
+        // a prover `with_path_inputs` claims the path's, not the trace's
+        let (start, end, nonce) = match &self.path_inputs {
+            Some(p) => (p.start, p.end, p.nonce),
+            None => (pub_inputs.start, pub_inputs.end, pub_inputs.nonce),
+        };
         PublicInputs {
-            start: trace.get(1, 0),
-            end: trace.get(1, last),
-            nonce: trace.get(0, 0),
+            start,
+            end,
+            nonce,
+            max_steps: self.max_steps,
+            waypoints: self.waypoints.iter().map(|&(step, node)| (step, BaseElement::new(node))).collect(),
+            cfg_commitment: self.cfg_commitment,
+            sequence_root: self.committed_prefix.map(|(root, _)| root),
+            prefix,
+            entry_policies: self.entry_policies.iter().map(|&(n, p)| (BaseElement::new(n), p)).collect(),
+            ..pub_inputs
         }
     }
 
@@ -0,0 +1,144 @@
+// scrub.rs
+//! Scrubbing of prover-side data after proving (`zeroize` feature).
+//!
+//! **Scrubbed:** the `TraceTable` built for the path. Every cell, including
+//! the nonce and shadow-stack columns, is overwritten with zero when
+//! winterfell drops the trace at the end of `prove`.
+//!
+//! **Not scrubbed:**
+//! - copies winterfell makes inside `prove` (trace polynomials, LDE
+//!   evaluations, Merkle leaves); they are freed without being cleared and
+//!   are outside our control;
+//! - the temporary shadow stack in `StarkraAir::build_trace`;
+//! - the returned `PublicInputs` and `Proof`, which carry the nonce by
+//!   design.
+
+use std::{hint::black_box, sync::atomic};
+
+use winterfell::{
+    CompositionPoly, CompositionPolyTrace, DefaultConstraintCommitment, DefaultConstraintEvaluator,
+    DefaultTraceLde, EvaluationFrame, PartitionOptions, Proof, ProofOptions, Prover, ProverError,
+    StarkDomain, Trace, TraceInfo, TracePolyTable, TraceTable,
+    math::{FieldElement, fields::f64::BaseElement},
+    matrix::ColMatrix,
+};
+
+use crate::{
+    air::{PublicInputs, StarkraAir},
+    prover::{DefaultVC, HashFn, RandCoin},
+};
+
+/// A trace that zeroes its storage when dropped.
+pub struct ScrubOnDrop(TraceTable<BaseElement>);
+
+impl ScrubOnDrop {
+    pub fn new(trace: TraceTable<BaseElement>) -> Self {
+        Self(trace)
+    }
+}
+
+impl Trace for ScrubOnDrop {
+    type BaseField = BaseElement;
+
+    fn info(&self) -> &TraceInfo {
+        self.0.info()
+    }
+
+    fn main_segment(&self) -> &ColMatrix<BaseElement> {
+        self.0.main_segment()
+    }
+
+    fn read_main_frame(&self, row_idx: usize, frame: &mut EvaluationFrame<BaseElement>) {
+        self.0.read_main_frame(row_idx, frame)
+    }
+}
+
+impl Drop for ScrubOnDrop {
+    fn drop(&mut self) {
+        for c in 0..self.0.width() {
+            for r in 0..self.0.length() {
+                self.0.set(c, r, BaseElement::ZERO);
+            }
+        }
+        // keep the stores from being elided as dead before the free
+        black_box(&self.0);
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+    }
+}
+
+/// Prove `trace`, the trace of the path `path_inputs` were built from,
+/// with the default prover configuration, scrubbing it before returning.
+pub(crate) fn prove_scrubbed(
+    trace: TraceTable<BaseElement>,
+    path_inputs: PublicInputs,
+    options: ProofOptions,
+) -> Result<(Proof, PublicInputs), ProverError> {
+    let prover = ScrubbingProver { options, path_inputs };
+    let trace = ScrubOnDrop::new(trace);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    prover.prove(trace).map(|proof| (proof, pub_inputs))
+}
+
+/// `StarkraProver` over a [`ScrubOnDrop`] trace, claiming the
+/// `PublicInputs::from_path` of the path it was built from.
+struct ScrubbingProver {
+    options: ProofOptions,
+    path_inputs: PublicInputs,
+}
+
+impl Prover for ScrubbingProver {
+    type BaseField = BaseElement;
+    type Air = StarkraAir;
+    type Trace = ScrubOnDrop;
+    type HashFn = HashFn;
+    type VC = DefaultVC;
+    type RandomCoin = RandCoin;
+    type TraceLde<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, _trace: &Self::Trace) -> PublicInputs {
+        self.path_inputs.clone()
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<Self::BaseField>,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        DefaultTraceLde::new(trace_info, main_trace, domain, partition_options)
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<winterfell::AuxRandElements<E>>,
+        composition_coefficients: winterfell::ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        DefaultConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+}
@@ -0,0 +1,138 @@
+// sequence.rs
+//! Commitments to a full expected node sequence, so a proof over an
+//! observed prefix can be tied to it (see `PublicInputs::sequence_root`).
+//!
+//! The commitment is a plain BLAKE3 Merkle tree, like `Cfg::commitment`,
+//! so tools outside the prover can build it. Leaves are the nodes'
+//! `encode_node` values, padded to a power of two with the "no node"
+//! value 0; leaf and inner hashes are domain-separated.
+//!
+//! The openings are checked by the verifier, outside the circuit. The AIR
+//! only pins `current` on the prefix rows to the claimed prefix nodes,
+//! with one assertion per row (see `PublicInputs::prefix`), and
+//! `verifier::verify_prefix` checks those nodes against the root. There
+//! are no opening columns: verifying a path in-circuit would need a
+//! field-native hash (e.g. Rescue) laid out over several rows per
+//! permutation, while the trace has one row per step, and it would buy
+//! nothing here, since the prefix the verifier checks is public anyway.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{air::encode_node, cfg::NodeId, error::StarkraError};
+
+type Digest = [u8; 32];
+
+fn leaf(n: Option<NodeId>) -> Digest {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[0]);
+    hasher.update(&n.map_or(0, |n| encode_node(n).as_int()).to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+fn parent(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[1]);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+/// A Merkle tree over a node sequence, e.g. the nodes of every step of an
+/// expected execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceCommitment {
+    /// `levels[0]` holds the leaves, the last level the root.
+    levels: Vec<Vec<Digest>>,
+    len: usize,
+}
+
+impl SequenceCommitment {
+    pub fn new(nodes: &[NodeId]) -> Self {
+        let width = nodes.len().max(1).next_power_of_two();
+        let mut level: Vec<Digest> = nodes.iter().map(|&n| leaf(Some(n))).collect();
+        level.resize(width, leaf(None));
+        let mut levels = vec![level];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1].chunks(2).map(|pair| parent(&pair[0], &pair[1])).collect();
+            levels.push(next);
+        }
+        Self { levels, len: nodes.len() }
+    }
+
+    pub fn root(&self) -> Digest {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Number of committed nodes, without padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The opening of the first `k` nodes: the hashes of the subtrees
+    /// right of the prefix, at most one per level. `k` must be in
+    /// `1..=len`.
+    pub fn open_prefix(&self, k: usize) -> Result<PrefixOpening, StarkraError> {
+        if k == 0 || k > self.len {
+            return Err(StarkraError::PrefixLength { len: k, limit: self.len });
+        }
+        let depth = self.levels.len() - 1;
+        let mut siblings = Vec::new();
+        self.collect(depth, 0, k, &mut siblings);
+        Ok(PrefixOpening { depth: depth as u32, siblings })
+    }
+
+    fn collect(&self, level: usize, index: usize, k: usize, siblings: &mut Vec<Digest>) {
+        let (start, end) = (index << level, (index + 1) << level);
+        if start >= k {
+            siblings.push(self.levels[level][index]);
+        } else if end > k {
+            self.collect(level - 1, 2 * index, k, siblings);
+            self.collect(level - 1, 2 * index + 1, k, siblings);
+        }
+    }
+}
+
+/// What opens a prefix against a [`SequenceCommitment`] root, from
+/// [`SequenceCommitment::open_prefix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixOpening {
+    /// Depth of the tree: it has `2^depth` leaves.
+    pub depth: u32,
+    /// Subtree hashes right of the prefix, left to right.
+    pub siblings: Vec<Digest>,
+}
+
+impl PrefixOpening {
+    /// Whether `prefix` is the start of the sequence committed to by
+    /// `root`. A prefix cannot reach into the padding: committed nodes and
+    /// padding leaves hash different values.
+    pub fn verify(&self, root: &Digest, prefix: &[NodeId]) -> bool {
+        if self.depth >= usize::BITS || prefix.is_empty() || prefix.len() > 1 << self.depth {
+            return false;
+        }
+        let mut siblings = self.siblings.iter();
+        let computed = Self::node(self.depth as usize, 0, prefix, &mut siblings);
+        siblings.next().is_none() && computed.as_ref() == Some(root)
+    }
+
+    fn node<'a>(
+        level: usize,
+        index: usize,
+        prefix: &[NodeId],
+        siblings: &mut impl Iterator<Item = &'a Digest>,
+    ) -> Option<Digest> {
+        if index << level >= prefix.len() {
+            return siblings.next().copied();
+        }
+        if level == 0 {
+            return Some(leaf(Some(prefix[index])));
+        }
+        let left = Self::node(level - 1, 2 * index, prefix, siblings)?;
+        let right = Self::node(level - 1, 2 * index + 1, prefix, siblings)?;
+        Some(parent(&left, &right))
+    }
+}
@@ -0,0 +1,371 @@
+// source.rs
+//! Where a CFG comes from. [`CfgSource`] is what the proving pipeline
+//! takes (see `prover::prove_path_from`), so a custom loader, e.g. one
+//! reading a disassembler's export, plugs in by implementing it.
+//!
+//! Provided sources: a [`Cfg`] itself, adjacency list and DOT text
+//! ([`Adjacency`], [`Dot`]), and with `std` the same as files
+//! ([`AdjacencyFile`], [`DotFile`]), plus [`CfgFile`], which picks the
+//! format from the file name like the CLI does.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+
+use crate::{
+    cfg::{Cfg, MAX_NODE_ID, NodeId},
+    error::StarkraError,
+    exe_path::JmpType,
+};
+
+/// Something a [`Cfg`] can be loaded from.
+pub trait CfgSource {
+    fn into_cfg(self) -> Result<Cfg, StarkraError>;
+}
+
+impl CfgSource for Cfg {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        Ok(self)
+    }
+}
+
+impl CfgSource for &Cfg {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        Ok(self.clone())
+    }
+}
+
+/// Adjacency list text, as parsed by `Cfg`'s `FromStr` impl.
+#[derive(Debug, Clone, Copy)]
+pub struct Adjacency<'a>(pub &'a str);
+
+impl CfgSource for Adjacency<'_> {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        self.0.parse().map_err(StarkraError::Parse)
+    }
+}
+
+/// DOT text, as parsed by [`Cfg::from_dot`].
+#[derive(Debug, Clone, Copy)]
+pub struct Dot<'a>(pub &'a str);
+
+impl CfgSource for Dot<'_> {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        Cfg::from_dot(self.0).map_err(StarkraError::Parse)
+    }
+}
+
+/// An adjacency list file, read with [`Cfg::from_file`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct AdjacencyFile<'a>(pub &'a str);
+
+#[cfg(feature = "std")]
+impl CfgSource for AdjacencyFile<'_> {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        Cfg::from_file(self.0).map_err(StarkraError::Parse)
+    }
+}
+
+/// A DOT file, read with [`Cfg::from_dot_file`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct DotFile<'a>(pub &'a str);
+
+#[cfg(feature = "std")]
+impl CfgSource for DotFile<'_> {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        Cfg::from_dot_file(self.0).map_err(StarkraError::Parse)
+    }
+}
+
+/// A CFG file in either format: DOT if the name ends in `.dot` or `.gv`
+/// (before any `.gz`), the adjacency list format otherwise.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct CfgFile<'a>(pub &'a str);
+
+#[cfg(feature = "std")]
+impl CfgFile<'_> {
+    pub fn is_dot(&self) -> bool {
+        let name = self.0.strip_suffix(".gz").unwrap_or(self.0);
+        name.ends_with(".dot") || name.ends_with(".gv")
+    }
+}
+
+#[cfg(feature = "std")]
+impl CfgSource for CfgFile<'_> {
+    fn into_cfg(self) -> Result<Cfg, StarkraError> {
+        if self.is_dot() {
+            DotFile(self.0).into_cfg()
+        } else {
+            AdjacencyFile(self.0).into_cfg()
+        }
+    }
+}
+
+// DOT ------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Id(String),
+    Arrow,
+    UndirectedEdge,
+    Punct(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // `#` lines are preprocessor output, ignored like comments
+            '#' if line_start => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            prev = c;
+                        }
+                        None => return Err(format!("Line {}: unterminated comment", line)),
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((Token::Arrow, line));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                tokens.push((Token::UndirectedEdge, line));
+            }
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            id.push('"');
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        }
+                        None => return Err(format!("Line {}: unterminated string", start)),
+                    }
+                }
+                tokens.push((Token::Id(id), start));
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => tokens.push((Token::Punct(c), line)),
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    id.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Id(id), line));
+            }
+            c => return Err(format!("Line {}: unexpected character '{}'", line, c)),
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |&(_, l)| l)
+    }
+
+    fn eat(&mut self, t: &Token) -> bool {
+        let hit = self.peek() == Some(t);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect(&mut self, t: Token, what: &str) -> Result<(), String> {
+        if self.eat(&t) {
+            Ok(())
+        } else {
+            Err(format!("Line {}: expected {}", self.line(), what))
+        }
+    }
+
+    fn id(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Id(id), _)) => {
+                self.pos += 1;
+                Ok(id.clone())
+            }
+            _ => Err(format!("Line {}: expected an ID", self.line())),
+        }
+    }
+
+    fn keyword(&self, kw: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(kw))
+    }
+
+    /// `[a=b, c=d; ...]...`, as `(key, value)` pairs.
+    fn attrs(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut attrs = Vec::new();
+        while self.eat(&Token::Punct('[')) {
+            while !self.eat(&Token::Punct(']')) {
+                let key = self.id()?;
+                self.expect(Token::Punct('='), "'=' in attribute")?;
+                attrs.push((key, self.id()?));
+                let _ = self.eat(&Token::Punct(',')) || self.eat(&Token::Punct(';'));
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn node(&mut self) -> Result<NodeId, String> {
+        let line = self.line();
+        let id = self.id()?;
+        if self.eat(&Token::Punct(':')) {
+            return Err(format!("Line {}: ports are not supported ('{}:...')", line, id));
+        }
+        parse_node(&id).ok_or_else(|| format!("Line {}: invalid node '{}'", line, id))
+    }
+}
+
+/// A decimal or `0x` hex node ID no larger than [`MAX_NODE_ID`].
+fn parse_node(id: &str) -> Option<NodeId> {
+    let n = match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        Some(hex) => NodeId::from_str_radix(hex, 16).ok()?,
+        None => id.parse().ok()?,
+    };
+    (n <= MAX_NODE_ID).then_some(n)
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(value.to_ascii_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+/// See [`Cfg::from_dot`].
+pub(crate) fn parse_dot(text: &str) -> Result<Cfg, String> {
+    let mut p = Parser { tokens: tokenize(text)?, pos: 0 };
+    if p.keyword("strict") {
+        p.pos += 1;
+    }
+    if p.keyword("graph") {
+        return Err(format!("Line {}: undirected graphs are not CFGs; use 'digraph'", p.line()));
+    }
+    if !p.keyword("digraph") {
+        return Err(format!("Line {}: expected 'digraph'", p.line()));
+    }
+    p.pos += 1;
+    if !matches!(p.peek(), Some(Token::Punct('{'))) {
+        p.id()?;
+    }
+    p.expect(Token::Punct('{'), "'{'")?;
+
+    let mut adj: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+    let mut entries = BTreeSet::new();
+    let mut kinds = Vec::new();
+    loop {
+        if p.eat(&Token::Punct('}')) {
+            break;
+        }
+        if p.eat(&Token::Punct(';')) {
+            continue;
+        }
+        match p.peek() {
+            None => return Err(format!("Line {}: expected '}}'", p.line())),
+            Some(Token::Punct('{')) => {
+                return Err(format!("Line {}: subgraphs are not supported", p.line()));
+            }
+            _ => {}
+        }
+        if p.keyword("subgraph") {
+            return Err(format!("Line {}: subgraphs are not supported", p.line()));
+        }
+        if p.keyword("graph") || p.keyword("node") || p.keyword("edge") {
+            p.pos += 1;
+            p.attrs()?;
+            continue;
+        }
+        // `key = value` graph attribute
+        if matches!(p.tokens.get(p.pos + 1), Some((Token::Punct('='), _))) {
+            p.pos += 2;
+            p.id()?;
+            continue;
+        }
+
+        let line = p.line();
+        let mut chain = Vec::from([p.node()?]);
+        loop {
+            if p.eat(&Token::Arrow) {
+                chain.push(p.node()?);
+            } else if p.peek() == Some(&Token::UndirectedEdge) {
+                return Err(format!("Line {}: undirected edge '--' in a digraph", p.line()));
+            } else {
+                break;
+            }
+        }
+        let attrs = p.attrs()?;
+        for &n in &chain {
+            adj.entry(n).or_default();
+        }
+        for pair in chain.windows(2) {
+            adj.entry(pair[0]).or_default().push(pair[1]);
+        }
+        if let Some((_, kind)) = attrs.iter().find(|(k, _)| k == "kind") {
+            let kind: JmpType = kind.parse().map_err(|e| format!("Line {}: {}", line, e))?;
+            kinds.extend(chain.windows(2).map(|pair| ((pair[0], pair[1]), kind.clone())));
+        }
+        if chain.len() == 1 && attrs.iter().any(|(k, v)| k == "entry" && is_true(v)) {
+            entries.insert(chain[0]);
+        }
+    }
+    if p.peek().is_some() {
+        return Err(format!("Line {}: trailing input after '}}'", p.line()));
+    }
+
+    let cfg = Cfg::from_adjacency(adj).with_edge_kinds(kinds);
+    Ok(if entries.is_empty() { cfg } else { cfg.with_entries(entries) })
+}
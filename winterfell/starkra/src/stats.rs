@@ -0,0 +1,104 @@
+// stats.rs
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::cfg::Cfg;
+
+/// Summary of a CFG's shape, for choosing proof parameters.
+/// Counts cover the nodes that appear in the graph (see [`Cfg::contains`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CfgStats {
+    pub nodes: usize,
+    /// Nodes with at least one edge (see [`Cfg::active_nodes`]).
+    pub active_nodes: usize,
+    pub edges: usize,
+    pub max_out_degree: usize,
+    pub mean_out_degree: f64,
+    /// Nodes without predecessors.
+    pub entries: usize,
+    /// Size of the declared entry set, if one was declared.
+    pub declared_entries: Option<usize>,
+    pub exits: usize,
+    pub sccs: usize,
+    /// No cycles at all, self-loops included.
+    pub is_dag: bool,
+    pub self_loops: usize,
+    /// As returned by [`Cfg::out_degree_histogram`].
+    pub out_degree_histogram: Vec<usize>,
+}
+
+impl Cfg {
+    pub fn stats(&self) -> CfgStats {
+        let histogram = self.out_degree_histogram();
+        let nodes: usize = histogram.iter().sum();
+        let edges = self.edges().count();
+        let self_loops = self.edges().filter(|(u, v)| u == v).count();
+        let sccs = self.sccs().len();
+        CfgStats {
+            nodes,
+            active_nodes: self.active_nodes(),
+            edges,
+            max_out_degree: histogram.len().saturating_sub(1),
+            mean_out_degree: if nodes == 0 { 0.0 } else { edges as f64 / nodes as f64 },
+            entries: self.entries().len(),
+            declared_entries: self.entry_set().map(|e| e.len()),
+            exits: self.exits().len(),
+            is_dag: self.is_dag(),
+            sccs,
+            self_loops,
+            out_degree_histogram: histogram,
+        }
+    }
+}
+
+impl CfgStats {
+    /// The stats as a single JSON object, with the same field names.
+    pub fn to_json(&self) -> String {
+        let declared = self.declared_entries.map_or("null".to_string(), |n| n.to_string());
+        let histogram: Vec<String> = self.out_degree_histogram.iter().map(|n| n.to_string()).collect();
+        format!(
+            "{{\"nodes\":{},\"active_nodes\":{},\"edges\":{},\"max_out_degree\":{},\"mean_out_degree\":{:.3},\
+             \"entries\":{},\"declared_entries\":{},\"exits\":{},\"sccs\":{},\"is_dag\":{},\
+             \"self_loops\":{},\"out_degree_histogram\":[{}]}}",
+            self.nodes,
+            self.active_nodes,
+            self.edges,
+            self.max_out_degree,
+            self.mean_out_degree,
+            self.entries,
+            declared,
+            self.exits,
+            self.sccs,
+            self.is_dag,
+            self.self_loops,
+            histogram.join(",")
+        )
+    }
+}
+
+/// One `name: value` line per statistic.
+impl fmt::Display for CfgStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  {:<18} {}", "nodes", self.nodes)?;
+        writeln!(f, "  {:<18} {}", "active nodes", self.active_nodes)?;
+        writeln!(f, "  {:<18} {}", "edges", self.edges)?;
+        writeln!(f, "  {:<18} max {}, mean {:.2}", "out-degree", self.max_out_degree, self.mean_out_degree)?;
+        match self.declared_entries {
+            Some(n) => writeln!(f, "  {:<18} {} ({} declared)", "entries", self.entries, n)?,
+            None => writeln!(f, "  {:<18} {}", "entries", self.entries)?,
+        }
+        writeln!(f, "  {:<18} {}", "exits", self.exits)?;
+        writeln!(f, "  {:<18} {}", "SCCs", self.sccs)?;
+        writeln!(f, "  {:<18} {}", "DAG", if self.is_dag { "yes" } else { "no" })?;
+        writeln!(f, "  {:<18} {}", "self-loops", self.self_loops)?;
+        write!(f, "  {:<18}", "degree histogram")?;
+        for (d, n) in self.out_degree_histogram.iter().enumerate().filter(|&(_, &n)| n > 0) {
+            write!(f, " {}:{}", d, n)?;
+        }
+        writeln!(f)
+    }
+}
@@ -0,0 +1,116 @@
+// supergraph.rs
+//! Interprocedural supergraph: one CFG per function, joined by call edges.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+
+use crate::cfg::{Cfg, NodeId};
+
+/// A call from `site` in one function to the entry `callee` of another,
+/// returning to `ret` in the caller (the `ret_addr` of the `call` step).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CallEdge {
+    pub site: NodeId,
+    pub callee: NodeId,
+    pub ret: NodeId,
+}
+
+/// Per-function CFGs over disjoint node IDs, plus the calls between them.
+///
+/// The supergraph is flattened into a single [`Cfg`] holding every
+/// function's edges, `site -> callee` for each call and `exit -> ret` from
+/// every exit of the called function. That graph lets a return reach any
+/// of the callee's return sites; which one a path may take is decided by
+/// the shadow stack, exactly as for calls within one CFG. So
+/// `as_cfg()` drops into `prove_path` and `verify_path` unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuperCfg {
+    functions: Vec<Cfg>,
+    calls: Vec<CallEdge>,
+    flat: Cfg,
+}
+
+impl SuperCfg {
+    /// Join `functions` with `calls`. Fails if two functions share a node,
+    /// or a call's site, callee or return site is not in any function, or
+    /// its return site is in a different function from its site.
+    /// Declared entry points of the functions are carried over as a union.
+    pub fn new(functions: Vec<Cfg>, calls: Vec<CallEdge>) -> Result<Self, String> {
+        let mut owner: BTreeMap<NodeId, usize> = BTreeMap::new();
+        for (f, cfg) in functions.iter().enumerate() {
+            for n in cfg.nodes() {
+                if let Some(g) = owner.insert(n, f) {
+                    return Err(format!("node {} is in both function {} and function {}", n, g, f));
+                }
+            }
+        }
+        let function_of = |n: NodeId| owner.get(&n).copied().ok_or_else(|| format!("node {} is not in any function", n));
+
+        let mut adj: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+        for cfg in &functions {
+            for n in cfg.nodes() {
+                adj.entry(n).or_default().extend_from_slice(cfg.successors(n));
+            }
+        }
+        let mut inter: BTreeSet<(NodeId, NodeId)> = BTreeSet::new();
+        for call in &calls {
+            let caller = function_of(call.site)?;
+            let callee = function_of(call.callee)?;
+            if function_of(call.ret)? != caller {
+                return Err(format!("call at {} returns to {}, outside the calling function", call.site, call.ret));
+            }
+            inter.insert((call.site, call.callee));
+            for exit in functions[callee].exits() {
+                inter.insert((exit, call.ret));
+            }
+        }
+        for (u, v) in inter {
+            let succ = adj.entry(u).or_default();
+            if !succ.contains(&v) {
+                succ.push(v);
+            }
+        }
+
+        let mut flat = Cfg::from_adjacency(adj);
+        let entries: Vec<NodeId> = functions.iter().filter_map(|f| f.entry_set()).flatten().copied().collect();
+        if functions.iter().any(|f| f.entry_set().is_some()) {
+            flat = flat.with_entries(entries);
+        }
+        Ok(Self { functions, calls, flat })
+    }
+
+    /// The flattened graph, for the prover and verifier.
+    pub fn as_cfg(&self) -> &Cfg {
+        &self.flat
+    }
+
+    pub fn functions(&self) -> &[Cfg] {
+        &self.functions
+    }
+
+    pub fn calls(&self) -> &[CallEdge] {
+        &self.calls
+    }
+
+    /// Index of the function `n` belongs to.
+    pub fn function_of(&self, n: NodeId) -> Option<usize> {
+        self.functions.iter().position(|f| f.contains(n))
+    }
+
+    pub fn successors(&self, n: NodeId) -> &[NodeId] {
+        self.flat.successors(n)
+    }
+
+    pub fn predecessors(&self, n: NodeId) -> &[NodeId] {
+        self.flat.predecessors(n)
+    }
+
+    /// [`Cfg::commitment`] of the flattened graph.
+    pub fn commitment(&self) -> [u8; 32] {
+        self.flat.commitment()
+    }
+}
@@ -0,0 +1,171 @@
+// trace_io.rs
+use std::{fmt, fs};
+
+use winterfell::{Trace, TraceTable, math::fields::f64::BaseElement};
+
+use crate::{
+    air::{NeighborEncoding, TraceLayout},
+    error::StarkraError,
+};
+#[cfg(feature = "prover")]
+use crate::{
+    air::{Column, StarkraAir},
+    cfg::Cfg,
+    exe_path::Step,
+};
+
+/// Which part of a trace to print. `None` means everything.
+#[derive(Debug, Clone, Default)]
+pub struct TraceView {
+    /// Half-open row range `[start, end)`, clamped to the trace length.
+    pub rows: Option<(usize, usize)>,
+    /// Column subset by header name, printed in the given order.
+    pub columns: Option<Vec<String>>,
+}
+
+/// Print the whole trace with a header row.
+pub fn print_trace_table_with_headers(trace: &TraceTable<BaseElement>, max_succ: usize) {
+    print_trace_table(trace, max_succ, &TraceView::default()).expect("full view is always valid");
+}
+
+/// The layout of a trace over `max_succ` neighbor slots: `OneHot` if it
+/// carries the selector columns, `Product` otherwise.
+fn layout_for(trace: &TraceTable<BaseElement>, max_succ: usize) -> TraceLayout {
+    let one_hot = TraceLayout::with_encoding(max_succ, NeighborEncoding::OneHot);
+    if max_succ > 0 && trace.width() == one_hot.width { one_hot } else { TraceLayout::new(max_succ) }
+}
+
+/// Print the rows and columns selected by `view`.
+/// Errors on a column name that is not in the layout.
+pub fn print_trace_table(
+    trace: &TraceTable<BaseElement>,
+    max_succ: usize,
+    view: &TraceView,
+) -> Result<(), String> {
+    let width = trace.width();
+    let length = trace.length();
+
+    // ---- build header names ----
+    let headers = layout_for(trace, max_succ).headers();
+
+    assert_eq!(headers.len(), width, "header/width mismatch");
+
+    // ---- resolve selected columns ----
+    let cols: Vec<usize> = match &view.columns {
+        None => (0..width).collect(),
+        Some(names) => names
+            .iter()
+            .map(|n| {
+                headers
+                    .iter()
+                    .position(|h| h == n)
+                    .ok_or_else(|| format!("unknown column '{}' (have: {})", n, headers.join(", ")))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let (start, end) = view.rows.unwrap_or((0, length));
+    let end = end.min(length);
+
+    // ---- print headers ----
+    print!("row |");
+    for &c in &cols {
+        print!(" {:>7} |", headers[c]);
+    }
+    println!();
+
+    // ---- separator ----
+    print!("----+");
+    for _ in &cols {
+        print!("---------+");
+    }
+    println!();
+
+    // ---- print rows ----
+    for r in start..end {
+        print!("{:>3} |", r);
+        for &c in &cols {
+            let v = trace.get(c, r).as_int();
+            print!(" {:>7} |", v);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Render the trace as CSV: a header row of column names, then one line per
+/// row of canonical integer values.
+pub fn trace_to_csv(trace: &TraceTable<BaseElement>, max_succ: usize) -> String {
+    let headers = layout_for(trace, max_succ).headers();
+    assert_eq!(headers.len(), trace.width(), "header/width mismatch");
+
+    let mut out = headers.join(",");
+    out.push('\n');
+    for r in 0..trace.length() {
+        let row: Vec<String> = (0..trace.width()).map(|c| trace.get(c, r).as_int().to_string()).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write [`trace_to_csv`] to `path`.
+pub fn write_trace_csv(path: &str, trace: &TraceTable<BaseElement>, max_succ: usize) -> Result<(), StarkraError> {
+    fs::write(path, trace_to_csv(trace, max_succ))
+        .map_err(|e| StarkraError::Io(format!("Failed to write '{}': {}", path, e)))
+}
+
+/// A cell where a trace differs from the one `build_trace` gives; see
+/// [`diff_trace`]. A side is `None` where that trace has no such row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellDiff {
+    pub row: usize,
+    /// Header name, as in [`TraceLayout::headers`].
+    pub column: String,
+    pub expected: Option<BaseElement>,
+    pub actual: Option<BaseElement>,
+}
+
+/// `row 12, stack: expected 5, got 7`.
+impl fmt::Display for CellDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: Option<BaseElement>| v.map_or("nothing".to_string(), |v| v.as_int().to_string());
+        write!(f, "row {}, {}: expected {}, got {}", self.row, self.column, show(self.expected), show(self.actual))
+    }
+}
+
+/// Every cell where `actual` differs from the trace
+/// `StarkraAir::build_trace(steps, cfg, nonce)` builds, in row-major order,
+/// for finding where a hand-edited or externally generated trace deviates
+/// from the one the constraints are written against. The nonce is read
+/// from `actual`'s first row, so only later nonce cells can differ.
+///
+/// The expected trace is the plain `build_trace` one, with selector
+/// columns added if `actual` has them: a trace built with forbidden nodes
+/// or a step bound differs in `forb_inv` and `budget` by design. A
+/// length mismatch shows as rows with one side `None`; `actual` of the
+/// wrong width for `cfg` is compared on the columns both have.
+#[cfg(feature = "prover")]
+pub fn diff_trace(actual: &TraceTable<BaseElement>, cfg: &Cfg, steps: &[Step]) -> Vec<CellDiff> {
+    let max_succ = cfg.max_successors();
+    let layout = layout_for(actual, max_succ);
+    let nonce = actual.get(layout.index(Column::Nonce), 0).as_int() as u32;
+    let expected = StarkraAir::build_trace(steps.to_vec(), cfg.clone(), nonce);
+    let expected = match layout.encoding {
+        NeighborEncoding::Product => expected,
+        NeighborEncoding::OneHot => StarkraAir::add_selectors(expected),
+    };
+    let headers = layout.headers();
+    let width = actual.width().min(expected.width()).min(headers.len());
+    let cell = |t: &TraceTable<BaseElement>, c: usize, r: usize| (r < t.length()).then(|| t.get(c, r));
+
+    let mut diffs = Vec::new();
+    for row in 0..actual.length().max(expected.length()) {
+        for (c, name) in headers.iter().enumerate().take(width) {
+            let (e, a) = (cell(&expected, c, row), cell(actual, c, row));
+            if e != a {
+                diffs.push(CellDiff { row, column: name.clone(), expected: e, actual: a });
+            }
+        }
+    }
+    diffs
+}
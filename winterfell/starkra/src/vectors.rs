@@ -0,0 +1,198 @@
+// vectors.rs
+//! Conformance test vectors: fixed inputs with a pinned outcome, so the
+//! prover, the verifier and any re-implementation can be checked against
+//! the same expectations.
+//!
+//! A vector directory holds one subdirectory per vector with:
+//! - `graph.cfg`: the CFG, in the adjacency list format of `Cfg::from_file`;
+//! - `exec.path`: the execution path, with an `initial_node=.. final_node=..`
+//!   header giving the claimed endpoints;
+//! - `expected.json`: `{"valid": bool, "start": n, "end": n, "rejected_at": stage}`,
+//!   where `start`/`end` are checked for valid vectors and the optional
+//!   `rejected_at` pins the [`Stage`] an invalid one must fail at.
+//!
+//! Vectors are proven with small fixed options ([`vector_options`]) and
+//! verified against the claimed endpoints, not the ones the trace ends at.
+//! Run them with a release build: winterfell's debug builds assert that
+//! every constraint reaches its declared degree, which short paths often
+//! do not.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use winterfell::{AcceptableOptions, BatchingMethod, FieldExtension, ProofOptions, math::fields::f64::BaseElement};
+
+use crate::{
+    air::{PublicInputs, StarkraAir},
+    cfg::{Cfg, NodeId},
+    error::StarkraError,
+    exe_path::{parse_execution_path_file, validate_path},
+    prover::prove_path,
+    verifier::verify_path,
+};
+
+pub const CFG_FILE: &str = "graph.cfg";
+pub const PATH_FILE: &str = "exec.path";
+pub const EXPECTED_FILE: &str = "expected.json";
+
+/// Nonce every vector is proven with.
+const VECTOR_NONCE: u32 = 7;
+
+/// Where a vector was rejected, in the order the runner checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// The CFG or path file is malformed, or the path has no header.
+    Parse,
+    /// `validate_path` rejects the walk.
+    Path,
+    /// The path starts outside the CFG's declared entry points.
+    Entry,
+    /// The prover failed.
+    Prove,
+    /// The proof does not verify for the claimed endpoints.
+    Verify,
+}
+
+/// The pinned outcome of a vector, as read from `expected.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expected {
+    pub valid: bool,
+    pub start: Option<NodeId>,
+    pub end: Option<NodeId>,
+    pub rejected_at: Option<Stage>,
+}
+
+/// What running a vector actually did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted { start: NodeId, end: NodeId },
+    Rejected { stage: Stage, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorReport {
+    pub name: String,
+    pub expected: Expected,
+    pub outcome: Outcome,
+}
+
+impl VectorReport {
+    /// Whether `outcome` matches `expected`.
+    pub fn passed(&self) -> bool {
+        let e = &self.expected;
+        match self.outcome {
+            Outcome::Accepted { start, end } => {
+                e.valid
+                    && e.start.is_none_or(|s| s == start)
+                    && e.end.is_none_or(|n| n == end)
+                    && e.rejected_at.is_none()
+            }
+            Outcome::Rejected { stage, .. } => !e.valid && e.rejected_at.is_none_or(|s| s == stage),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Parse => "parse",
+            Stage::Path => "path",
+            Stage::Entry => "entry",
+            Stage::Prove => "prove",
+            Stage::Verify => "verify",
+        })
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Accepted { start, end } => write!(f, "accepted ({} -> {})", start, end),
+            Outcome::Rejected { stage, reason } => write!(f, "rejected at {}: {}", stage, reason),
+        }
+    }
+}
+
+/// `PASS name` or `FAIL name: <outcome>`.
+impl fmt::Display for VectorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            write!(f, "PASS {}", self.name)
+        } else {
+            write!(f, "FAIL {}: {}", self.name, self.outcome)
+        }
+    }
+}
+
+/// Proof options for vectors over `cfg`: few queries and the smallest
+/// blowup the constraints allow. Vectors pin behavior, not security.
+pub fn vector_options(cfg: &Cfg) -> ProofOptions {
+    let blowup = StarkraAir::constraint_info(cfg.max_successors()).min_blowup();
+    ProofOptions::new(
+        16,
+        blowup,
+        0,
+        FieldExtension::None,
+        4,
+        31,
+        BatchingMethod::Linear,
+        BatchingMethod::Linear,
+    )
+}
+
+/// Run every vector under `dir`, in name order.
+pub fn run_vectors(dir: &str) -> Result<Vec<VectorReport>, StarkraError> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map_err(|e| StarkraError::Io(format!("Failed to read '{}': {}", dir, e)))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names.iter().map(|name| run_vector(&Path::new(dir).join(name))).collect()
+}
+
+/// Run the single vector in `dir`. Only a missing or malformed
+/// `expected.json` is an error; a broken input is a `Parse` rejection.
+pub fn run_vector(dir: &Path) -> Result<VectorReport, StarkraError> {
+    let expected_path = dir.join(EXPECTED_FILE);
+    let text = fs::read_to_string(&expected_path)
+        .map_err(|e| StarkraError::Io(format!("Failed to read '{}': {}", expected_path.display(), e)))?;
+    let expected = serde_json::from_str(&text)
+        .map_err(|e| StarkraError::Parse(format!("'{}': {}", expected_path.display(), e)))?;
+    let outcome = match prove_and_verify(dir) {
+        Ok((start, end)) => Outcome::Accepted { start, end },
+        Err((stage, reason)) => Outcome::Rejected { stage, reason },
+    };
+    let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok(VectorReport { name, expected, outcome })
+}
+
+fn at<E: fmt::Display>(stage: Stage) -> impl FnOnce(E) -> (Stage, String) {
+    move |e| (stage, e.to_string())
+}
+
+fn prove_and_verify(dir: &Path) -> Result<(NodeId, NodeId), (Stage, String)> {
+    let cfg = Cfg::from_file(&dir.join(CFG_FILE).to_string_lossy()).map_err(at(Stage::Parse))?;
+    let (steps, start, end) =
+        parse_execution_path_file(&dir.join(PATH_FILE).to_string_lossy()).map_err(at(Stage::Parse))?;
+    let (start, end) = start
+        .zip(end)
+        .ok_or((Stage::Parse, "missing initial_node/final_node header".to_string()))?;
+    validate_path(&steps, &cfg).map_err(at(Stage::Path))?;
+
+    let options = vector_options(&cfg);
+    let (proof, proven) = prove_path(steps, &cfg, VECTOR_NONCE, options.clone()).map_err(|e| match e {
+        StarkraError::IllegalEntry(_) => (Stage::Entry, e.to_string()),
+        e => (Stage::Prove, e.to_string()),
+    })?;
+
+    // the claim under test is the header's, not what the trace ends at
+    let claimed = PublicInputs { start: BaseElement::new(start), end: BaseElement::new(end), ..proven };
+    verify_path(proof, claimed, &cfg, &AcceptableOptions::OptionSet(vec![options])).map_err(at(Stage::Verify))?;
+    Ok((start, end))
+}
@@ -0,0 +1,393 @@
+// verifier.rs
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+use winterfell::{
+    AcceptableOptions, Proof, VerifierError,
+    crypto::{DefaultRandomCoin, MerkleTree, VectorCommitment, hashers::Blake3_256},
+    math::fields::f64::BaseElement,
+    verify,
+};
+
+use crate::{
+    air::{PublicInputs, StarkraAir, TraceLayout},
+    cfg::{Cfg, NodeId},
+    error::StarkraError,
+    proof_io::{check_header, decode_proof, decode_proof_body},
+    sequence::PrefixOpening,
+};
+
+/// Hash function shared by the prover and the verifier.
+pub type HashFn = Blake3_256<BaseElement>;
+/// Random coin shared by the prover and the verifier.
+pub type RandCoin = DefaultRandomCoin<HashFn>;
+/// Vector commitment used unless a prover is built for another one.
+pub type DefaultVC = MerkleTree<HashFn>;
+
+/// Phases reported by [`verify_path_with_progress`], in the order they run.
+///
+/// Winterfell does not expose hooks inside `verify` (e.g. per FRI layer),
+/// so the STARK check itself is a single phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyPhase {
+    /// Checking the proof's trace width against the CFG layout, and its
+    /// length against `max_verify_rows`.
+    Layout,
+    /// Checking the public inputs (the CFG commitment, if any, matches,
+    /// endpoints are connected in the CFG, segment nonces are distinct,
+    /// the step count, waypoints and prefix fit the trace).
+    PublicInputs,
+    /// Running the STARK verifier.
+    Stark,
+    /// Verification finished, successfully or not.
+    Done,
+}
+
+/// A verifier's acceptance policy: winterfell's [`AcceptableOptions`] for
+/// the proof's security, plus limits on what the verifier is willing to
+/// work through. A plain `&AcceptableOptions` converts into one without
+/// limits, so every `verify_*` function takes either.
+#[derive(Clone, Copy)]
+pub struct Acceptable<'a> {
+    pub options: &'a AcceptableOptions,
+    /// Longest trace (in rows, padding included) a proof may claim. Checked
+    /// against the trace info before the STARK check, whose cost grows with
+    /// the trace length; distinct from the prover's `--max-rows`, which
+    /// bounds traces the prover builds.
+    pub max_verify_rows: Option<usize>,
+}
+
+impl<'a> Acceptable<'a> {
+    pub fn new(options: &'a AcceptableOptions) -> Self {
+        Self { options, max_verify_rows: None }
+    }
+
+    /// Reject proofs over traces longer than `max_rows` rows with
+    /// `TraceTooLong`.
+    pub fn with_max_verify_rows(mut self, max_rows: usize) -> Self {
+        self.max_verify_rows = Some(max_rows);
+        self
+    }
+}
+
+impl<'a> From<&'a AcceptableOptions> for Acceptable<'a> {
+    fn from(options: &'a AcceptableOptions) -> Self {
+        Self::new(options)
+    }
+}
+
+/// Verify a proof that a path over `cfg` satisfies `pub_inputs`.
+///
+/// The verifier rebuilds the AIR from the proof's own trace info, so the
+/// proof's trace width is first checked against the layout `cfg` implies;
+/// a proof built for a different CFG shape is rejected up front. A proof
+/// that carries a CFG commitment must also commit to `cfg`.
+///
+/// The AIR never reads the CFG: the commitment is compared with
+/// `cfg.commitment()` and nothing more. It binds the proof to the edges
+/// of `cfg` only if `cfg` is one the caller trusts (e.g. extracted from
+/// the binary), not one that came with the proof.
+///
+/// `acceptable` is the caller's security policy. A proof whose options fall
+/// short of it fails with `Verification`, naming the required and actual
+/// security (e.g. `InsufficientConjecturedSecurity(128, 95)`). A proof
+/// over more rows than its `max_verify_rows` fails with `TraceTooLong`
+/// before any other check.
+///
+/// `VerifyOutcome::from` sorts the error into a soundness failure, a
+/// malformed proof or bad public inputs.
+pub fn verify_path<'a>(
+    proof: Proof,
+    pub_inputs: PublicInputs,
+    cfg: &Cfg,
+    acceptable: impl Into<Acceptable<'a>>,
+) -> Result<(), StarkraError> {
+    verify_path_with_progress(proof, pub_inputs, cfg, acceptable, |_| {})
+}
+
+/// Like [`verify_path`], but calls `on_phase` as each [`VerifyPhase`]
+/// starts. `Done` is always the last call, including on early errors.
+pub fn verify_path_with_progress<'a>(
+    proof: Proof,
+    pub_inputs: PublicInputs,
+    cfg: &Cfg,
+    acceptable: impl Into<Acceptable<'a>>,
+    on_phase: impl FnMut(VerifyPhase),
+) -> Result<(), StarkraError> {
+    verify_path_with_vc::<DefaultVC>(proof, pub_inputs, cfg, acceptable, on_phase)
+}
+
+/// `verify_path_with_progress` for a proof made by a
+/// `StarkraProver<VC>` with a non-default vector commitment.
+pub fn verify_path_with_vc<'a, VC: VectorCommitment<HashFn>>(
+    proof: Proof,
+    pub_inputs: PublicInputs,
+    cfg: &Cfg,
+    acceptable: impl Into<Acceptable<'a>>,
+    mut on_phase: impl FnMut(VerifyPhase),
+) -> Result<(), StarkraError> {
+    let acceptable = acceptable.into();
+    let result = (|| {
+        on_phase(VerifyPhase::Layout);
+        let length = proof.trace_info().length();
+        if let Some(max) = acceptable.max_verify_rows
+            && length > max
+        {
+            return Err(StarkraError::TraceTooLong { real_len: length, max });
+        }
+        let layout = TraceLayout::with_encoding(cfg.max_successors(), pub_inputs.neighbor_encoding);
+        let (expected, actual) = (layout.width, proof.trace_info().width());
+        if actual != expected {
+            return Err(StarkraError::WidthMismatch { expected, actual });
+        }
+        pub_inputs.check_blowup(&layout, proof.options().blowup_factor())?;
+
+        on_phase(VerifyPhase::PublicInputs);
+        if pub_inputs.cfg_commitment.is_some_and(|c| c != cfg.commitment()) {
+            return Err(StarkraError::CfgMismatch);
+        }
+        check_entry_policies(&pub_inputs, cfg)?;
+        check_endpoints(&pub_inputs, cfg)?;
+        pub_inputs.check_distinct_nonces()?;
+        pub_inputs.check_segments(proof.trace_info().length())?;
+        pub_inputs.check_forbidden_endpoints()?;
+        pub_inputs.check_step_count(proof.trace_info().length())?;
+        pub_inputs.check_waypoints(proof.trace_info().length())?;
+        pub_inputs.check_prefix(proof.trace_info().length())?;
+
+        on_phase(VerifyPhase::Stark);
+        verify::<StarkraAir, HashFn, RandCoin, VC>(proof, pub_inputs, acceptable.options)
+        .map_err(StarkraError::Verification)
+    })();
+    on_phase(VerifyPhase::Done);
+    result
+}
+
+/// Verify an encoded proof (the `proof_io` file format, which stores the
+/// public inputs the proof was made with) against the public inputs the
+/// caller claims it proves.
+///
+/// A winterfell proof only verifies for the public inputs it was made
+/// with, so verifying against a wrong claim fails either way. The gap is
+/// on the caller's side: verifying a file against the inputs stored in it
+/// succeeds, whatever statement the caller had in mind. This fails with
+/// `PublicInputMismatch` before any verification unless the stored inputs
+/// equal `claimed`, then verifies against `claimed`.
+pub fn verify_path_strict<'a>(
+    bytes: &[u8],
+    claimed: &PublicInputs,
+    cfg: &Cfg,
+    acceptable: impl Into<Acceptable<'a>>,
+) -> Result<(), StarkraError> {
+    let (proof, committed) = decode_proof(bytes)?;
+    if committed != *claimed {
+        return Err(StarkraError::PublicInputMismatch);
+    }
+    verify_path(proof, committed, cfg, acceptable)
+}
+
+/// What verifying an encoded proof found, for callers that must tell a
+/// corrupt or mismatched file apart from a proof that fails the STARK
+/// check (e.g. to alert on the latter only).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    /// The STARK verifier rejected a well-formed proof: a commitment,
+    /// out-of-domain or FRI check failed, or the proof falls short of the
+    /// security policy. Flipped bytes inside the proof's query data land
+    /// here too; they cannot be told apart from a forged proof.
+    InvalidProof(VerifierError),
+    /// The bytes are not a proof of this CFG's shape: a bad header or
+    /// version, a proof body that does not deserialize, or a trace width
+    /// other than the CFG layout's.
+    MalformedProof(String),
+    /// The public inputs do not decode, or cannot describe a path over the
+    /// CFG (see `verify_path`'s checks), so the STARK check never ran.
+    BadPublicInputs(String),
+}
+
+impl VerifyOutcome {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+}
+
+/// Classify the result of `verify_path` and friends.
+impl From<Result<(), StarkraError>> for VerifyOutcome {
+    fn from(result: Result<(), StarkraError>) -> Self {
+        let e = match result {
+            Ok(()) => return Self::Valid,
+            Err(e) => e,
+        };
+        match e {
+            StarkraError::Verification(
+                VerifierError::ProofDeserializationError(_)
+                | VerifierError::InconsistentBaseField
+                | VerifierError::UnsupportedFieldExtension(_),
+            )
+            | StarkraError::Parse(_)
+            | StarkraError::Io(_)
+            | StarkraError::WidthMismatch { .. }
+            | StarkraError::InvalidWidth(_)
+            | StarkraError::BlowupTooSmall { .. } => Self::MalformedProof(e.to_string()),
+            StarkraError::Verification(e) => Self::InvalidProof(e),
+            StarkraError::NonceReuse(_)
+            | StarkraError::SegmentRow { .. }
+            | StarkraError::ForbiddenEndpoint(_)
+            | StarkraError::IllegalEntry(_)
+            | StarkraError::UnreachableEndpoints { .. }
+            | StarkraError::CfgMismatch
+            | StarkraError::MissingCfgCommitment
+            | StarkraError::EntryPolicyMismatch
+            | StarkraError::PublicInputMismatch
+            | StarkraError::TraceTooLong { .. }
+            | StarkraError::StepCount { .. }
+            | StarkraError::Waypoint { .. }
+            | StarkraError::DuplicateWaypoint(_)
+            | StarkraError::PrefixLength { .. }
+            | StarkraError::PrefixMismatch { .. }
+            | StarkraError::PrefixOpening
+            | StarkraError::Divergence { .. }
+            | StarkraError::CoverGap { .. }
+            | StarkraError::Prove(_)
+            | StarkraError::Trace(_)
+            | StarkraError::Path(_) => Self::BadPublicInputs(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for VerifyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyOutcome::Valid => f.write_str("valid proof"),
+            VerifyOutcome::InvalidProof(e) => write!(f, "invalid proof: {}", e),
+            VerifyOutcome::MalformedProof(msg) => write!(f, "malformed proof: {}", msg),
+            VerifyOutcome::BadPublicInputs(msg) => write!(f, "bad public inputs: {}", msg),
+        }
+    }
+}
+
+/// Verify an encoded proof (the `proof_io` file format) against the
+/// public inputs stored with it, and say which kind of failure, if any,
+/// stopped it. Public inputs that do not decode are `BadPublicInputs`,
+/// while a bad header or proof body is `MalformedProof`.
+pub fn verify_encoded<'a>(bytes: &[u8], cfg: &Cfg, acceptable: impl Into<Acceptable<'a>>) -> VerifyOutcome {
+    if let Err(e) = check_header(bytes) {
+        return VerifyOutcome::MalformedProof(e.to_string());
+    }
+    let (pub_inputs, used) = match PublicInputs::read_from(&bytes[5..]) {
+        Ok(decoded) => decoded,
+        Err(e) => return VerifyOutcome::BadPublicInputs(e.to_string()),
+    };
+    let proof = match decode_proof_body(&bytes[5 + used..]) {
+        Ok(proof) => proof,
+        Err(e) => return VerifyOutcome::MalformedProof(e.to_string()),
+    };
+    verify_path(proof, pub_inputs, cfg, acceptable).into()
+}
+
+/// Verify a proof that the trace's first rows are the start of the node
+/// sequence committed to by `pub_inputs.sequence_root` (see
+/// `StarkraProver::with_committed_prefix`): `opening` must open
+/// `pub_inputs.prefix` against the root, then the proof is verified as by
+/// [`verify_path`], whose prefix assertions tie the trace to that prefix.
+///
+/// Fails with `PrefixOpening` before any STARK verification if there is
+/// no root or the prefix does not open against it. [`verify_path`] alone
+/// checks the prefix assertions but not the root.
+pub fn verify_prefix<'a>(
+    proof: Proof,
+    pub_inputs: PublicInputs,
+    cfg: &Cfg,
+    opening: &PrefixOpening,
+    acceptable: impl Into<Acceptable<'a>>,
+) -> Result<(), StarkraError> {
+    let root = pub_inputs.sequence_root.ok_or(StarkraError::PrefixOpening)?;
+    let prefix: Vec<NodeId> = pub_inputs.prefix.iter().map(|n| n.as_int()).collect();
+    if !opening.verify(&root, &prefix) {
+        return Err(StarkraError::PrefixOpening);
+    }
+    verify_path(proof, pub_inputs, cfg, acceptable)
+}
+
+/// Verify a proof against whichever of `cfgs` its public inputs commit to
+/// (see `StarkraProver::with_cfg_commitment`), e.g. the last few versions
+/// of a recompiled binary's CFG. Returns the index of that candidate; the
+/// first one wins if several are identical.
+///
+/// Fails with `MissingCfgCommitment` for a proof without a commitment and
+/// with `CfgMismatch` when no candidate matches it. As for `verify_path`,
+/// a match means something only if every candidate is trusted.
+pub fn verify_path_any<'a>(
+    proof: Proof,
+    pub_inputs: PublicInputs,
+    cfgs: &[Cfg],
+    acceptable: impl Into<Acceptable<'a>>,
+) -> Result<usize, StarkraError> {
+    let commitment = pub_inputs.cfg_commitment.ok_or(StarkraError::MissingCfgCommitment)?;
+    let index = cfgs.iter().position(|cfg| cfg.commitment() == commitment).ok_or(StarkraError::CfgMismatch)?;
+    verify_path(proof, pub_inputs, &cfgs[index], acceptable).map(|()| index)
+}
+
+/// The proof must constrain exactly `cfg`'s entry policies, in node order
+/// as `Cfg::entry_policies` lists them; dropping one would let the path
+/// enter that node any way it likes.
+fn check_entry_policies(pub_inputs: &PublicInputs, cfg: &Cfg) -> Result<(), StarkraError> {
+    let expected = cfg.entry_policies().iter().map(|(&n, &p)| (BaseElement::new(n), p));
+    if !pub_inputs.entry_policies.iter().copied().eq(expected) {
+        return Err(StarkraError::EntryPolicyMismatch);
+    }
+    Ok(())
+}
+
+/// `start` and `end` must be nodes of `cfg`, and for a single session `end`
+/// must be reachable from `start`. Segments of a segmented trace are
+/// independent walks, so the first start and last end need not connect.
+fn check_endpoints(pub_inputs: &PublicInputs, cfg: &Cfg) -> Result<(), StarkraError> {
+    let (start, end) = (pub_inputs.start.as_int(), pub_inputs.end.as_int());
+    let err = StarkraError::UnreachableEndpoints { start, end };
+    if !cfg.contains(start) || !cfg.contains(end) {
+        return Err(err);
+    }
+    if pub_inputs.segment_nonces.is_empty() && !cfg.reaches(start, end) {
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Verify many independent proofs in parallel. Results are in input order.
+///
+/// There is no CFG here, so this only checks what a proof says about
+/// itself: its trace width must be a layout of its neighbor encoding (a
+/// bad item fails with `InvalidWidth` rather than taking the batch down),
+/// its options' blowup must cover its constraints' degrees, its nonces, step count, waypoints and prefix must fit its trace, and
+/// the STARK check must pass. Nothing ties an item to a CFG: there is no
+/// commitment, entry-policy, endpoint or layout-width check against one.
+/// Use [`verify_path`] per item where the CFG matters.
+#[cfg(feature = "std")]
+pub fn verify_batch(
+    items: Vec<(Proof, PublicInputs)>,
+    acceptable: &AcceptableOptions,
+) -> Vec<Result<(), StarkraError>> {
+    items
+        .into_par_iter()
+        .map(|(proof, pub_inputs)| {
+            let (width, length) = (proof.trace_info().width(), proof.trace_info().length());
+            let layout = TraceLayout::try_from_width_with(width, pub_inputs.neighbor_encoding)
+                .ok_or(StarkraError::InvalidWidth(width))?;
+            pub_inputs.check_blowup(&layout, proof.options().blowup_factor())?;
+            pub_inputs.check_distinct_nonces()?;
+            pub_inputs.check_segments(length)?;
+            pub_inputs.check_forbidden_endpoints()?;
+            pub_inputs.check_step_count(length)?;
+            pub_inputs.check_waypoints(length)?;
+            pub_inputs.check_prefix(length)?;
+            verify::<StarkraAir, HashFn, RandCoin, DefaultVC>(proof, pub_inputs, acceptable)
+                .map_err(StarkraError::Verification)
+        })
+        .collect()
+}
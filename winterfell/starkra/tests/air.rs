@@ -0,0 +1,327 @@
+mod common;
+
+use common::*;
+use starkra::{
+    air::{PublicInputs, StarkraAir, TraceLayout, evaluate_assertions},
+    cfg::{Cfg, EntryPolicy},
+    error::StarkraError,
+    mutations::mutations,
+    prover::StarkraProver,
+    verifier::verify_path,
+};
+use std::collections::BTreeMap;
+
+use winterfell::{
+    Air, Prover, Trace,
+    math::{FieldElement, fields::f64::BaseElement},
+};
+
+#[test]
+fn step_count_rejects_a_skipped_index() {
+    let cfg = diamond();
+    let trace = StarkraAir::build_trace(steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n"), cfg.clone(), 7);
+    let prover = StarkraProver::new(options()).with_step_count();
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!(pub_inputs.num_steps, Some(4));
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+
+    let mut skipped = trace.clone();
+    skipped.set(TraceLayout::from(&cfg).step, 2, BaseElement::new(3));
+    let e = StarkraAir::check_trace(&skipped, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(step)"), "{e}");
+
+    let mut short = pub_inputs.clone();
+    short.num_steps = Some(3);
+    assert!(StarkraAir::check_trace(&trace, &short).is_err());
+    let proof = prover.prove(trace).unwrap();
+    assert!(verify_path(proof.clone(), short, &cfg, &acceptable()).is_err());
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+}
+
+#[test]
+fn step_count_pins_where_the_real_rows_end() {
+    let cfg = Cfg::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let trace = StarkraAir::build_trace(vec![jump(0), jump(1), jump(2), jump(3)], cfg.clone(), 7);
+    let prover = StarkraProver::new(options()).with_step_count();
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let cells = |air: &StarkraAir| {
+        let mut cells = BTreeMap::new();
+        for a in air.get_assertions() {
+            cells.insert((a.column(), a.first_step()), trace.get(a.column(), a.first_step()));
+        }
+        cells
+    };
+    let air = StarkraAir::new(trace.info().clone(), pub_inputs.clone(), options());
+    let results = evaluate_assertions(&air, &cells(&air));
+    let end = results.iter().find(|r| r.label == "steps_end").unwrap();
+    assert_eq!((end.row, end.passed()), (4, true));
+
+    // claiming one step fewer misses both the last step index and the drop
+    let mut short = pub_inputs.clone();
+    short.num_steps = Some(3);
+    let air = StarkraAir::new(trace.info().clone(), short, options());
+    let failed: Vec<_> = evaluate_assertions(&air, &cells(&air)).into_iter().filter(|r| !r.passed()).collect();
+    assert_eq!(failed.iter().map(|r| r.label).collect::<Vec<_>>(), ["num_steps", "steps_end"]);
+
+    // hiding row 1 behind `valid = 0` to count three steps breaks the
+    // valid region
+    let l = TraceLayout::from(&cfg);
+    let mut hidden = trace.clone();
+    hidden.set(l.valid, 1, BaseElement::ZERO);
+    hidden.set(l.current, 1, trace.get(l.current, 0));
+    for row in 1..hidden.length() {
+        let step = trace.get(l.step, row) - BaseElement::ONE;
+        hidden.set(l.step, row, step);
+        hidden.set(l.budget, row, step);
+    }
+    let claimed = prover.get_pub_inputs(&hidden);
+    assert_eq!(claimed.num_steps, Some(3));
+    let e = StarkraAir::check_trace(&hidden, &claimed).unwrap_err().to_string();
+    assert!(e.contains("(segment) fails between rows 1 and 2"), "{e}");
+    let proof = prover.prove(hidden).unwrap();
+    assert!(verify_path(proof, claimed, &cfg, &acceptable()).is_err());
+}
+
+#[test]
+fn call_ret_rejects_a_row_with_both_flags_set() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let mut trace = StarkraAir::build_trace(path, cfg.clone(), 9);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+
+    // row 1 is entered by the call; flag it as a ret too
+    trace.set(TraceLayout::from(&cfg).ret, 1, BaseElement::ONE);
+    assert!(StarkraAir::check_trace(&trace, &pub_inputs).is_err());
+    let row = StarkraAir::explain_row(&trace, &pub_inputs, 0).unwrap();
+    assert!(row.iter().any(|&(label, v)| label == "call_ret" && v != BaseElement::ZERO));
+}
+
+#[test]
+fn flags_must_be_boolean() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 9);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let l = TraceLayout::from(&cfg);
+    // row 2 is entered by a jump
+    for (column, label) in [(l.call, "call_bool"), (l.ret, "ret_bool"), (l.valid, "valid_bool")] {
+        let mut mutated = trace.clone();
+        mutated.set(column, 2, BaseElement::new(2));
+        assert!(StarkraAir::check_trace(&mutated, &pub_inputs).is_err(), "{label}");
+        let row = StarkraAir::explain_row(&mutated, &pub_inputs, 1).unwrap();
+        assert!(row.iter().any(|&(name, v)| name == label && v != BaseElement::ZERO), "{label}");
+    }
+}
+
+#[test]
+fn max_steps_at_below_and_above_the_bound() {
+    let cfg = diamond();
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n");
+    let n = path.len();
+    for (max, ok) in [(n, true), (n + 3, true), (n - 1, false)] {
+        let trace = StarkraAir::build_trace_bounded(path.clone(), cfg.clone(), 3, max);
+        let prover = StarkraProver::new(options()).with_max_steps(max);
+        let pub_inputs = prover.get_pub_inputs(&trace);
+        assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs).is_ok(), ok, "max {max}");
+        if ok {
+            let proof = prover.prove(trace).unwrap();
+            let mut tighter = pub_inputs.clone();
+            tighter.max_steps = Some(n - 1);
+            assert!(verify_path(proof.clone(), tighter, &cfg, &acceptable()).is_err());
+            assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn waypoints_satisfied_and_violated() {
+    let cfg = diamond();
+    let trace = StarkraAir::build_trace(steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n"), cfg.clone(), 3);
+    let prover = StarkraProver::new(options()).with_waypoints(vec![(1, 1), (2, 2)]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+    let proof = prover.prove(trace.clone()).unwrap();
+    assert_eq!(verify_path(proof.clone(), pub_inputs.clone(), &cfg, &acceptable()), Ok(()));
+
+    // the path is at 2, not 3, on step 2
+    let mut violated = pub_inputs.clone();
+    violated.waypoints[1].1 = BaseElement::new(3);
+    assert!(StarkraAir::check_trace(&trace, &violated).is_err());
+    assert!(verify_path(proof.clone(), violated, &cfg, &acceptable()).is_err());
+
+    let mut out_of_range = pub_inputs;
+    out_of_range.waypoints = vec![(trace.length(), BaseElement::ZERO)];
+    assert!(matches!(
+        verify_path(proof, out_of_range, &cfg, &acceptable()),
+        Err(StarkraError::Waypoint { .. })
+    ));
+}
+
+#[test]
+fn padding_rows_hold_the_end_node() {
+    let cfg = diamond();
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n");
+    let n = path.len();
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 3);
+    assert!(trace.length() > n + 1);
+    let mut tampered = trace.clone();
+    tampered.set(TraceLayout::from(&cfg).current, n + 1, BaseElement::new(77));
+
+    let prover = StarkraProver::new(options());
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&tampered, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(padding)"), "{e}");
+
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+}
+
+#[test]
+fn stack_top_cannot_change_on_a_jump_row() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 9);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let l = TraceLayout::from(&cfg);
+    // row 2 is entered by a jump, inside the call
+    let mut moved = trace.clone();
+    moved.set(l.stack, 2, trace.get(l.stack, 2) + BaseElement::ONE);
+    let e = StarkraAir::check_trace(&moved, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(stack_hold)"), "{e}");
+
+    // half flags would zero `1 - call - ret`; booleanity rejects them
+    let half = BaseElement::new(2).inv();
+    moved.set(l.call, 2, half);
+    moved.set(l.ret, 2, half);
+    assert!(StarkraAir::check_trace(&moved, &pub_inputs).is_err());
+    let row = StarkraAir::explain_row(&moved, &pub_inputs, 1).unwrap();
+    assert!(row.iter().any(|&(label, v)| label == "call_bool" && v != BaseElement::ZERO));
+    assert!(row.iter().any(|&(label, v)| label == "stack_hold" && v == BaseElement::ZERO));
+}
+
+#[test]
+fn entering_a_call_only_node_by_a_jump_is_rejected() {
+    let cfg = call_loop().with_entry_policies([(2, EntryPolicy::CALL_ONLY)]);
+    let path = vec![jump(0), jump(1), jump(2), jump(3)];
+    let violations = cfg.check_entry_policy(&path).unwrap_err();
+    assert_eq!(violations.iter().map(|v| v.step).collect::<Vec<_>>(), vec![2]);
+
+    let pub_inputs = PublicInputs::from_path(&cfg, &path, 9).unwrap();
+    let trace = StarkraAir::build_trace(path.clone(), cfg.clone(), 9);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(entry) fails between rows 1 and 2"), "{e}");
+    let proof = StarkraProver::new(options()).with_path_inputs(pub_inputs.clone()).prove(trace).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+
+    // the same walk with jumps allowed into 2
+    let open = call_loop().with_entry_policies([(2, EntryPolicy { call: true, ret: false, jump: true })]);
+    let pub_inputs = PublicInputs::from_path(&open, &path, 9).unwrap();
+    let trace = StarkraAir::build_trace(path, open, 9);
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+}
+
+#[test]
+fn every_mutation_is_rejected() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 9);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let mut labels = Vec::new();
+    for (mutated, label) in mutations(&trace, &TraceLayout::from(&cfg)) {
+        assert!(StarkraAir::check_trace(&mutated, &pub_inputs).is_err(), "{label} not rejected");
+        labels.push(label);
+    }
+    assert!(labels.contains(&"call_ret") && labels.contains(&"call_flag") && labels.contains(&"stack_top"));
+}
+
+#[test]
+fn forbidden_node_on_the_path_is_rejected() {
+    let cfg = diamond();
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n");
+    let trace = StarkraAir::build_trace_avoiding(path, cfg.clone(), 4, &[2]);
+    let prover = StarkraProver::new(options()).with_forbidden(vec![2]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(forbidden) fails between rows 2 and 3"), "{e}");
+    let proof = prover.prove(trace).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+
+    // 0 -> 1 -> 3 goes around it
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 3\n");
+    let trace = StarkraAir::build_trace_avoiding(path, cfg.clone(), 4, &[2]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+}
+
+#[test]
+fn forbidden_end_on_the_last_row_is_rejected() {
+    // eight steps fill the trace, so the path ends on its last row
+    let cfg = Cfg::from_edges((0..7u64).map(|i| (i, i + 1)));
+    let path: Vec<_> = (0..8).map(jump).collect();
+    let trace = StarkraAir::build_trace_avoiding(path, cfg.clone(), 4, &[7]);
+    assert_eq!(trace.length(), 8);
+    let prover = StarkraProver::new(options()).with_forbidden(vec![7]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    // no transition constraint sees the last row's node
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Err(StarkraError::ForbiddenEndpoint(7)));
+}
+
+#[test]
+fn a_row_hidden_by_valid_zero_is_rejected() {
+    // every walk from 0 to 3 passes 1
+    let cfg = Cfg::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let path = vec![jump(0), jump(1), jump(2), jump(3)];
+    let mut trace = StarkraAir::build_trace_avoiding(path, cfg.clone(), 4, &[1]);
+    let l = TraceLayout::from(&cfg);
+    // row 1 turned into a gap repeating node 0, the step counter shifted
+    // down over it
+    trace.set(l.valid, 1, BaseElement::ZERO);
+    trace.set(l.current, 1, trace.get(l.current, 0));
+    for row in 1..trace.length() {
+        let step = trace.get(l.step, row) - BaseElement::ONE;
+        trace.set(l.step, row, step);
+        trace.set(l.budget, row, step);
+    }
+    let prover = StarkraProver::new(options()).with_forbidden(vec![1]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(segment) fails between rows 1 and 2"), "{e}");
+    let proof = StarkraProver::new(options()).with_forbidden(vec![1]).prove(trace).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+}
+
+#[test]
+fn over_budget_walk_cannot_end_in_the_padding() {
+    // 0 -> 3 takes four steps; claim at most two
+    let cfg = Cfg::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let prover = StarkraProver::new(options()).with_max_steps(2);
+    let mut trace = StarkraAir::build_trace(vec![jump(0)], cfg.clone(), 4);
+    let l = TraceLayout::from(&cfg);
+    // one real row, then padding claiming to end at 3
+    for row in 1..trace.length() {
+        trace.set(l.current, row, BaseElement::new(4));
+    }
+    StarkraAir::fill_budget(&mut trace, 2);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!((pub_inputs.start, pub_inputs.end), (BaseElement::ZERO, BaseElement::new(3)));
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(padding) fails between rows 0 and 1"), "{e}");
+    let proof = prover.prove(trace).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+
+    // the real walk fits a bound of four, not three
+    let path: Vec<_> = (0..4).map(jump).collect();
+    for (max, ok) in [(4, true), (3, false)] {
+        let prover = StarkraProver::new(options()).with_max_steps(max);
+        let mut trace = StarkraAir::build_trace(path.clone(), cfg.clone(), 4);
+        StarkraAir::fill_budget(&mut trace, max);
+        let pub_inputs = prover.get_pub_inputs(&trace);
+        let proof = prover.prove(trace).unwrap();
+        assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_ok(), ok, "max_steps {max}");
+    }
+}
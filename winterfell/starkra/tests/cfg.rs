@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use starkra::cfg::{Cfg, MAX_NODE_ID};
+
+#[test]
+fn sparse_ids_cost_nothing_for_the_gaps() {
+    let far = MAX_NODE_ID;
+    let mid = (u32::MAX as u64) << 8;
+    let cfg = Cfg::from_adjacency(vec![(0, vec![mid]), (mid, vec![far]), (far, vec![])]);
+    assert_eq!(cfg.len(), 3);
+    assert_eq!(cfg.nodes().collect::<Vec<_>>(), vec![0, mid, far]);
+    assert!(cfg.contains(far) && !cfg.contains(far - 1));
+    assert_eq!(cfg.predecessors(far), &[mid]);
+    assert_eq!(cfg.to_string(), "Cfg { nodes: 3, edges: 2, max_out: 1 }");
+
+    assert!(cfg.reaches(0, far));
+    assert_eq!(cfg.shortest_path(0, far), Some(vec![0, mid, far]));
+    assert_eq!(cfg.simple_paths(0, far, 3), vec![vec![0, mid, far]]);
+    assert!(cfg.is_dag());
+    assert_eq!(cfg.sccs().len(), 3);
+    assert_eq!(cfg.dominators(0), BTreeMap::from([(0, 0), (mid, 0), (far, mid)]));
+    assert_eq!(cfg.post_dominators_all()[&0], mid);
+    assert_eq!(cfg.unreachable_from([mid]), vec![0]);
+    assert_eq!(cfg.back_edges(0), vec![]);
+
+    let (contracted, chains) = cfg.contract_chains();
+    assert_eq!(contracted.len(), 1);
+    assert_eq!(chains[&0], vec![0, mid, far]);
+    let (live, mapping) = cfg.remove_unreachable(mid);
+    assert_eq!(live.len(), 2);
+    assert_eq!(mapping[&far], 1);
+}
+
+#[test]
+fn parses_a_one_edge_cfg_at_the_top_of_the_id_range() {
+    let cfg: Cfg = format!("0 {}\n", MAX_NODE_ID).parse().unwrap();
+    assert_eq!(cfg.len(), 2);
+    assert_eq!(cfg.dangling_targets(), vec![MAX_NODE_ID]);
+    assert_eq!(cfg.exits(), vec![MAX_NODE_ID]);
+}
+
+#[test]
+fn is_dag_on_acyclic_self_loop_and_cycle() {
+    assert!(Cfg::from_adjacency(Vec::<(u64, Vec<u64>)>::new()).is_dag());
+    assert!(Cfg::from_adjacency(vec![(0, vec![1, 2]), (1, vec![3]), (2, vec![3]), (3, vec![])]).is_dag());
+    assert!(!Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![1])]).is_dag());
+    assert!(!Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2]), (2, vec![3]), (3, vec![1]), (5, vec![0])]).is_dag());
+    // iterative, so a long chain does not overflow the stack
+    assert!(Cfg::from_edges((0..200_000u64).map(|i| (i, i + 1))).is_dag());
+}
+
+#[test]
+fn active_nodes_skip_gaps_and_isolated_nodes() {
+    // 50 is declared but has no edges
+    let cfg = Cfg::from_adjacency(vec![(3, vec![7]), (7, vec![100]), (50, vec![]), (100, vec![3])]);
+    assert_eq!(cfg.len(), 4);
+    assert_eq!(cfg.active_node_ids().collect::<Vec<_>>(), vec![3, 7, 100]);
+    assert_eq!(cfg.active_nodes(), 3);
+    assert!(cfg.contains(50) && !cfg.contains(4));
+    let stats = cfg.stats();
+    assert_eq!((stats.nodes, stats.active_nodes), (4, 3));
+    assert_eq!(Cfg::from_adjacency(vec![(5, vec![])]).active_nodes(), 0);
+}
+
+#[test]
+fn canonicalize_makes_line_order_irrelevant() {
+    let mut a: Cfg = "0 3 1 2\n1 3\n2 1\n".parse().unwrap();
+    let mut b: Cfg = "2 1\n0 2 3 1\n1 3\n".parse().unwrap();
+    assert_ne!(a, b);
+    assert!(!a.is_canonical() && !b.is_canonical());
+    a.canonicalize();
+    b.canonicalize();
+    assert_eq!(a, b);
+    assert!(a.is_canonical());
+    assert_eq!(a.successors(0), &[1, 2, 3]);
+    assert_eq!(a.commitment(), b.commitment());
+}
@@ -0,0 +1,46 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use starkra::{
+    cfg::{Cfg, NodeId},
+    exe_path::{JmpType, Step, parse_execution_path},
+};
+use winterfell::{AcceptableOptions, BatchingMethod, FieldExtension, ProofOptions};
+
+/// Fast, insecure options: 16 queries at blowup 16.
+pub fn options() -> ProofOptions {
+    ProofOptions::new(16, 16, 0, FieldExtension::None, 4, 31, BatchingMethod::Linear, BatchingMethod::Linear)
+}
+
+/// Accepts the insecure `options()`.
+pub fn acceptable() -> AcceptableOptions {
+    AcceptableOptions::MinConjecturedSecurity(0)
+}
+
+/// `0 -> 1 -> {2, 3}, 2 -> 3`
+pub fn diamond() -> Cfg {
+    Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2, 3]), (2, vec![3]), (3, vec![])])
+}
+
+/// `0 -> {1, 3}, 1 -> 2, 2 -> 3, 3 -> {0, 1}`: `0` calls `1` returning to
+/// `3`, and `2` returns.
+pub fn call_loop() -> Cfg {
+    Cfg::from_adjacency(vec![(0, vec![1, 3]), (1, vec![2]), (2, vec![3]), (3, vec![0, 1])])
+}
+
+/// Steps of a path in the text format, first node included.
+pub fn steps(text: &str) -> Vec<Step> {
+    parse_execution_path(text).unwrap().0
+}
+
+pub fn jump(n: NodeId) -> Step {
+    Step { jmp_type: JmpType::Jump, addrs: vec![n] }
+}
+
+pub fn call(target: NodeId, ret: NodeId) -> Step {
+    Step { jmp_type: JmpType::Call, addrs: vec![target, ret] }
+}
+
+pub fn ret(n: NodeId) -> Step {
+    Step { jmp_type: JmpType::Ret, addrs: vec![n] }
+}
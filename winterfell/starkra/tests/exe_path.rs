@@ -0,0 +1,140 @@
+mod common;
+
+use common::*;
+use starkra::{
+    air::StarkraAir,
+    cfg::{Cfg, MAX_NODE_ID},
+    error::{PathError, StarkraError},
+    exe_path::{JmpType, ShadowStack, Step, cover_paths, parse_execution_path, parse_execution_path_binary, simulate, to_binary, validate_path, validate_return_sites},
+    prover::{StarkraProver, prove_path},
+    verifier::verify_path,
+};
+use winterfell::Prover;
+
+#[test]
+fn tail_recursive_loop_stays_at_constant_depth() {
+    // 0 calls 1 returning to 4; 1 -> 2 tail-calls 1 again, then 3 returns
+    let cfg = Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2]), (2, vec![1, 3]), (3, vec![4]), (4, vec![])]);
+    let path = steps("initial_node=0 final_node=4\ncall 1 4\njump 2\ntailcall 1\njump 2\ntailcall 1\njump 2\njump 3\nret 4\n");
+    assert_eq!(path[3], Step { jmp_type: JmpType::TailCall, addrs: vec![1] });
+
+    let mut stack = ShadowStack::new();
+    let depths: Vec<usize> = path
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            stack.apply(i, step);
+            stack.depth()
+        })
+        .collect();
+    assert_eq!(depths, vec![0, 1, 1, 1, 1, 1, 1, 1, 0]);
+    assert_eq!(simulate(&cfg, &path).unwrap().depth, 0);
+
+    let trace = StarkraAir::build_trace(path.clone(), cfg.clone(), 5);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+    let (proof, pub_inputs) = prove_path(path, &cfg, 5, options()).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+}
+
+#[test]
+fn tail_call_to_a_non_successor_is_rejected() {
+    let cfg = Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2]), (2, vec![1, 3]), (3, vec![4]), (4, vec![])]);
+    let path = steps("initial_node=0 final_node=4\ncall 1 4\njump 2\ntailcall 4\n");
+    assert!(validate_path(&path, &cfg).is_err());
+}
+
+#[test]
+fn cover_paths_keeps_a_joined_path_starting_with_a_call() {
+    let cfg = call_loop();
+    // the second path calls 1 returning to 3, and returns there
+    let first = vec![jump(0), jump(3)];
+    let second = vec![call(1, 3), jump(2), ret(3)];
+    let walk = cover_paths(&cfg, &[first, second]).unwrap();
+    assert_eq!(walk, vec![jump(0), jump(3), call(1, 3), jump(2), ret(3)]);
+    assert_eq!(validate_path(&walk, &cfg), Ok(()));
+
+    let (proof, pub_inputs) = prove_path(walk, &cfg, 4, options()).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+}
+
+#[test]
+fn binary_node_ids_above_the_maximum_are_parse_errors() {
+    let at_max = to_binary(&[jump(MAX_NODE_ID)], Some(0), Some(MAX_NODE_ID));
+    assert!(parse_execution_path_binary(&at_max).is_ok());
+    for (steps, initial, last) in [
+        (vec![jump(MAX_NODE_ID + 1)], Some(0), None),
+        (vec![jump(1)], Some(u64::MAX), None),
+        (vec![jump(1)], Some(0), Some(MAX_NODE_ID + 1)),
+    ] {
+        let bytes = to_binary(&steps, initial, last);
+        assert!(matches!(parse_execution_path_binary(&bytes), Err(StarkraError::Parse(_))));
+    }
+}
+
+#[test]
+fn binary_round_trip_matches_the_text_parser() {
+    let text = "initial_node=0 final_node=4\ncall 1 4\njump 2\ntailcall 1\njump 2\njump 3\nret 4\n";
+    let parsed = parse_execution_path(text).unwrap();
+    let (steps, initial, last) = parsed.clone();
+    assert_eq!(parse_execution_path_binary(&to_binary(&steps, initial, last)).unwrap(), parsed);
+    assert_eq!(parse_execution_path_binary(&to_binary(&steps, None, None)).unwrap(), (steps, None, None));
+
+    // both parsers refuse the first ID past the maximum
+    let over = MAX_NODE_ID + 1;
+    assert!(parse_execution_path(&format!("initial_node=0\njump {}\n", over)).is_err());
+    assert!(parse_execution_path_binary(&to_binary(&[jump(over)], Some(0), None)).is_err());
+}
+
+#[test]
+fn step_display_round_trips_through_the_parser() {
+    let text = "call 10 14\njump 11\ntailcall 12\nret 14";
+    let (parsed, _, _) = parse_execution_path(text).unwrap();
+    let lines: Vec<String> = parsed.iter().map(|s| s.to_string()).collect();
+    assert_eq!(lines.join("\n"), text);
+    for (line, step) in text.lines().zip(&parsed) {
+        assert_eq!(line.parse::<Step>().as_ref(), Ok(step));
+    }
+    assert!("ret 1 2".parse::<Step>().is_err());
+    assert!("halt 1".parse::<Step>().is_err());
+    assert!(format!("jump {}", MAX_NODE_ID + 1).parse::<Step>().is_err());
+}
+
+#[test]
+fn call_to_a_non_successor_is_rejected() {
+    // 0 calls 1 returning to 3; 2 is reachable, but not from 0
+    let cfg = call_loop();
+    assert_eq!(validate_path(&[jump(0), call(1, 3), jump(2), ret(3)], &cfg), Ok(()));
+
+    let bad = vec![jump(0), call(2, 3), ret(3)];
+    assert_eq!(validate_path(&bad, &cfg), Err(PathError::IllegalCallTarget { step: 1, caller: 0, target: 2 }));
+    let trace = StarkraAir::build_trace(bad.clone(), cfg.clone(), 1);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(neighbors)"), "{e}");
+    let (proof, pub_inputs) = prove_path(bad, &cfg, 1, options()).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+
+    // the stricter check also wants the return address to be one
+    let bad = vec![jump(0), call(1, 2), ret(2)];
+    assert_eq!(validate_path(&bad, &cfg), Ok(()));
+    assert_eq!(validate_return_sites(&bad, &cfg), Err(PathError::IllegalReturnAddress { step: 1, caller: 0, ret_addr: 2 }));
+}
+
+#[test]
+fn ret_must_match_both_the_stack_and_an_edge() {
+    // 0 calls 1 returning to 3, but 2 only has an edge to 4
+    let cfg = Cfg::from_adjacency(vec![(0, vec![1, 3]), (1, vec![2]), (2, vec![4]), (3, vec![]), (4, vec![])]);
+    let prover = StarkraProver::new(options());
+    for (bad, expected, constraint) in [
+        (vec![jump(0), call(1, 3), jump(2), ret(3)], PathError::IllegalReturnEdge { step: 3, from: 2, to: 3 }, "(neighbors)"),
+        (vec![jump(0), call(1, 3), jump(2), ret(4)], PathError::ReturnMismatch { step: 3, expected: Some(3), actual: 4 }, "(stack)"),
+    ] {
+        assert_eq!(validate_path(&bad, &cfg), Err(expected));
+        let trace = StarkraAir::build_trace(bad.clone(), cfg.clone(), 1);
+        let e = StarkraAir::check_trace(&trace, &prover.get_pub_inputs(&trace)).unwrap_err().to_string();
+        assert!(e.contains(constraint), "{e}");
+        let (proof, pub_inputs) = prove_path(bad, &cfg, 1, options()).unwrap();
+        assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+    }
+}
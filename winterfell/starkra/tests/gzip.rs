@@ -0,0 +1,35 @@
+mod common;
+
+use std::{fs, io::Write};
+
+use common::*;
+use flate2::{Compression, write::GzEncoder};
+use starkra::{cfg::Cfg, exe_path::parse_execution_path_file, prover::prove_path, verifier::verify_path};
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn gzipped_inputs_load_like_the_plain_files() {
+    let dir = std::env::temp_dir().join(format!("starkra-gzip-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (cfg_gz, path_gz) = (dir.join("graph.cfg.gz"), dir.join("exec.path.gz"));
+    fs::write(&cfg_gz, gzip(&fs::read("vectors/call_ret/graph.cfg").unwrap())).unwrap();
+    fs::write(&path_gz, gzip(&fs::read("vectors/call_ret/exec.path").unwrap())).unwrap();
+    // detected by the magic bytes, whatever the name
+    let renamed = dir.join("exec.path");
+    fs::copy(&path_gz, &renamed).unwrap();
+
+    let cfg = Cfg::from_file(cfg_gz.to_str().unwrap()).unwrap();
+    assert_eq!(cfg, Cfg::from_file("vectors/call_ret/graph.cfg").unwrap());
+    let parsed = parse_execution_path_file(path_gz.to_str().unwrap()).unwrap();
+    assert_eq!(parsed, parse_execution_path_file("vectors/call_ret/exec.path").unwrap());
+    assert_eq!(parse_execution_path_file(renamed.to_str().unwrap()).unwrap(), parsed);
+
+    let (proof, pub_inputs) = prove_path(parsed.0, &cfg, 1, options()).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::*;
+use starkra::{
+    air::{Direction, PublicInputs, StarkraAir, TraceLayout, encode_node},
+    cfg::{Cfg, EntryPolicy},
+    error::{PathError, StarkraError},
+    exe_path::{reverse_walk, validate_path},
+    prover::{StarkraProver, prove_deterministic, prove_path},
+    verifier::{verify_batch, verify_path},
+};
+use winterfell::{BatchingMethod, FieldExtension, ProofOptions, Prover, math::FieldElement};
+
+#[test]
+fn deterministic_proofs_are_byte_identical() {
+    let cfg = diamond();
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n");
+    // grinding on, so the proof-of-work nonce is part of the proof
+    let options = ProofOptions::new(16, 16, 8, FieldExtension::None, 4, 31, BatchingMethod::Linear, BatchingMethod::Linear);
+    let proofs: Vec<Vec<u8>> = (0..2)
+        .map(|_| {
+            let trace = StarkraAir::build_trace(path.clone(), cfg.clone(), 3);
+            prove_deterministic(&StarkraProver::new(options.clone()), trace).unwrap().to_bytes()
+        })
+        .collect();
+    assert_eq!(proofs[0], proofs[1]);
+}
+
+#[test]
+fn zero_edge_cfg_proves_only_a_single_node_path() {
+    let cfg = Cfg::from_adjacency(vec![(0, vec![]), (1, vec![])]);
+    assert_eq!(cfg.max_successors(), 0);
+    assert_eq!(TraceLayout::from(&cfg).width, TraceLayout::FIXED_COLUMNS);
+
+    let (proof, pub_inputs) = prove_path(vec![jump(0)], &cfg, 1, options()).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+
+    let e = prove_path(vec![jump(0), jump(0)], &cfg, 1, options()).unwrap_err();
+    assert!(matches!(e, StarkraError::Path(PathError::IllegalEdge { step: 1, from: 0, to: 0 })), "{e}");
+    // built anyway, the trace fails the neighbor check
+    let trace = StarkraAir::build_trace(vec![jump(0), jump(0)], cfg, 1);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(neighbors)"), "{e}");
+}
+
+#[test]
+fn entry_degree_beyond_the_largest_blowup_is_an_error() {
+    // 130 call-only nodes: an `entry` constraint of degree 134, which no
+    // blowup up to 128 covers
+    let chain = Cfg::from_edges((0..130u64).map(|i| (i, i + 1)));
+    let cfg = chain.clone().with_entry_policies((1..=130).map(|n| (n, EntryPolicy::CALL_ONLY)));
+    let e = prove_path(vec![jump(0)], &cfg, 1, options()).unwrap_err();
+    assert_eq!(e, StarkraError::BlowupTooSmall { required: 256, actual: 16 });
+
+    // claimed on a proof made without them, the verifier errors rather than panics
+    let (proof, mut pub_inputs) = prove_path(vec![jump(0), jump(1)], &chain, 1, options()).unwrap();
+    pub_inputs.entry_policies = PublicInputs::from_path(&cfg, &[jump(0)], 1).unwrap().entry_policies;
+    assert_eq!(verify_path(proof.clone(), pub_inputs.clone(), &cfg, &acceptable()), Err(e.clone()));
+    assert_eq!(verify_batch(vec![(proof, pub_inputs)], &acceptable()), vec![Err(e)]);
+}
+
+#[test]
+fn from_path_agrees_with_the_prover() {
+    let cfg = call_loop().with_entry_policies([(2, EntryPolicy { call: true, ret: false, jump: true })]);
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let direct = PublicInputs::from_path(&cfg, &path, 9).unwrap();
+    let (proof, proved) = prove_path(path.clone(), &cfg, 9, options()).unwrap();
+    assert_eq!(proved, direct);
+
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 9);
+    let via = StarkraProver::new(options()).with_path_inputs(direct.clone()).get_pub_inputs(&trace);
+    assert_eq!(via, direct);
+    // derived from the trace alone: the same statement, less the CFG's
+    let derived = StarkraProver::new(options()).get_pub_inputs(&trace);
+    assert_eq!((derived.start, derived.end, derived.nonce), (direct.start, direct.end, direct.nonce));
+    assert_eq!(verify_path(proof, proved, &cfg, &acceptable()), Ok(()));
+    assert!(PublicInputs::from_path(&cfg, &[], 9).is_err());
+}
+
+#[test]
+fn node_zero_is_a_real_node_distinct_from_padding() {
+    // 0 is entered, left and returned to; 1 has an unused neighbor slot
+    let cfg = Cfg::from_adjacency(vec![(2, vec![0, 1]), (0, vec![1, 3]), (1, vec![3]), (3, vec![0])]);
+    let path = vec![jump(2), jump(0), jump(1), jump(3), jump(0)];
+    let (proof, pub_inputs) = prove_path(path.clone(), &cfg, 4, options()).unwrap();
+    assert_eq!(pub_inputs.end, FieldElement::ZERO);
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Ok(()));
+
+    let l = TraceLayout::from(&cfg);
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 4);
+    assert_eq!(trace.get(l.current, 1), encode_node(0));
+    // node 1's second slot is empty, which no node encodes to
+    assert_eq!(trace.get(l.neighbors_start + 1, 2), FieldElement::ZERO);
+
+    // a jump into that empty slot is not a jump to node 0
+    let bad = vec![jump(2), jump(1), jump(0)];
+    assert_eq!(validate_path(&bad, &cfg), Err(PathError::IllegalEdge { step: 2, from: 1, to: 0 }));
+    let trace = StarkraAir::build_trace(bad.clone(), cfg.clone(), 4);
+    let pub_inputs = StarkraProver::new(options()).get_pub_inputs(&trace);
+    let e = StarkraAir::check_trace(&trace, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(neighbors)"), "{e}");
+    let (proof, pub_inputs) = prove_path(bad, &cfg, 4, options()).unwrap();
+    assert!(verify_path(proof, pub_inputs, &cfg, &acceptable()).is_err());
+}
+
+#[test]
+fn declared_entries_restrict_the_start_and_undeclared_allow_any() {
+    let undeclared: Cfg = "0 1\n1 2\n2\n".parse().unwrap();
+    let declared: Cfg = "entry 0\n0 1\n1 2\n2\n".parse().unwrap();
+    assert_eq!(undeclared.entry_set(), None);
+    assert_eq!(declared.entry_set().map(|e| e.iter().copied().collect::<Vec<_>>()), Some(vec![0]));
+    assert_eq!(declared, undeclared.clone().with_entries([0]));
+
+    for start in [0, 1] {
+        let path: Vec<_> = (start..3).map(jump).collect();
+        let (proof, pub_inputs) = prove_path(path.clone(), &undeclared, 1, options()).unwrap();
+        assert_eq!(verify_path(proof, pub_inputs, &undeclared, &acceptable()), Ok(()));
+        let result = prove_path(path, &declared, 1, options());
+        if start == 0 {
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(result.unwrap_err(), StarkraError::IllegalEntry(1));
+        }
+    }
+}
+
+#[test]
+fn forward_and_reversed_paths_prove_the_same_fact() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(1)];
+    let (proof, forward) = prove_path(path.clone(), &cfg, 1, options()).unwrap();
+    assert_eq!(verify_path(proof, forward.clone(), &cfg, &acceptable()), Ok(()));
+
+    let listed: Vec<_> = path.into_iter().rev().collect();
+    let reversed = cfg.reversed();
+    let bound = PublicInputs::from_path(&reversed, &reverse_walk(&listed), 1).unwrap();
+    let trace = StarkraAir::build_trace_in(listed, cfg.clone(), 1, Direction::Reverse);
+    let prover = StarkraProver::new(options()).with_path_inputs(bound);
+    let reverse = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof.clone(), reverse.clone(), &reversed, &acceptable()), Ok(()));
+    // 0 reaches 1 either way; the reversed proof states it from the end
+    assert_eq!((reverse.start, reverse.end), (forward.end, forward.start));
+    // and is bound to the reversed CFG
+    assert!(verify_path(proof, reverse, &cfg, &acceptable()).is_err());
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::*;
+use starkra::{
+    air::{Column, NeighborEncoding, StarkraAir, TraceBuilder, TraceLayout, trace_get},
+    cfg::Cfg,
+    prover::prove_path,
+    verifier::verify_path,
+};
+use winterfell::{
+    Trace, TraceTable,
+    math::{FieldElement, fields::f64::BaseElement},
+};
+
+#[test]
+fn trace_get_reads_one_hot_traces_by_their_layout() {
+    let cfg = diamond();
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 3\n");
+    let trace = StarkraAir::add_selectors(StarkraAir::build_trace(path, cfg.clone(), 7));
+    let layout = TraceLayout::with_encoding(cfg.max_successors(), NeighborEncoding::OneHot);
+    assert_eq!(trace.width(), layout.width);
+
+    // node columns hold `n + 1`
+    assert_eq!(trace_get(&trace, &layout, Column::Current, 2), BaseElement::new(4));
+    assert_eq!(trace_get(&trace, &layout, Column::Step, 2), BaseElement::new(2));
+    // 1 -> 3 takes the second neighbor slot of node 1
+    assert_eq!(trace_get(&trace, &layout, Column::Taken, 1), BaseElement::ONE);
+    assert_eq!(trace_get(&trace, &layout, Column::Selector(1), 1), BaseElement::ONE);
+    assert_eq!(trace_get(&trace, &layout, Column::Selector(0), 1), BaseElement::ZERO);
+}
+
+fn same(a: &TraceTable<BaseElement>, b: &TraceTable<BaseElement>) -> bool {
+    a.length() == b.length() && a.width() == b.width() && (0..a.width()).all(|c| a.get_column(c) == b.get_column(c))
+}
+
+#[test]
+fn trace_builder_matches_build_trace() {
+    let cfg = call_loop();
+    let path = vec![jump(0), call(1, 3), jump(2), ret(3), jump(0), call(1, 3), jump(2), ret(3), jump(0)];
+    let mut builder = TraceBuilder::new(cfg.clone(), 9);
+    for (i, step) in path.iter().enumerate() {
+        builder.push_step(step.clone());
+        if i == 4 {
+            assert!(same(&builder.snapshot(), &StarkraAir::build_trace(path[..=i].to_vec(), cfg.clone(), 9)));
+        }
+    }
+    assert_eq!(builder.len(), path.len());
+    assert!(same(&builder.finish(), &StarkraAir::build_trace(path.clone(), cfg.clone(), 9)));
+
+    let mut builder = TraceBuilder::new(cfg.clone(), 9).with_forbidden(vec![5]);
+    for step in &path {
+        builder.push_step(step.clone());
+    }
+    assert!(same(&builder.finish(), &StarkraAir::build_trace_avoiding(path, cfg, 9, &[5])));
+}
+
+#[test]
+fn edge_multiplicity_matches_the_trace_transitions() {
+    // 0 -> 1, then the loop 1 -> 2 -> 1 four times, then 1 -> 3
+    let cfg = Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2, 3]), (2, vec![1]), (3, vec![])]);
+    let mut path = vec![jump(0)];
+    for _ in 0..4 {
+        path.extend([jump(1), jump(2)]);
+    }
+    path.extend([jump(1), jump(3)]);
+    let counts = cfg.edge_multiplicity(&path);
+    assert_eq!((counts[&(0, 1)], counts[&(1, 2)], counts[&(2, 1)], counts[&(1, 3)]), (1, 4, 4, 1));
+
+    // what a counter over real transitions `2 -> 1` would accumulate
+    let trace = StarkraAir::build_trace(path, cfg.clone(), 1);
+    let l = TraceLayout::from(&cfg);
+    let real = |r: usize| trace.get(l.valid, r) == BaseElement::ONE;
+    // node columns hold `n + 1`
+    let back_edges = (0..trace.length() - 1)
+        .filter(|&r| real(r) && real(r + 1))
+        .filter(|&r| trace.get(l.current, r) == BaseElement::new(3) && trace.get(l.current, r + 1) == BaseElement::new(2))
+        .count();
+    assert_eq!(back_edges, counts[&(2, 1)]);
+}
+
+#[test]
+fn out_of_order_neighbors_canonicalize_to_the_same_trace() {
+    let sorted = Cfg::from_adjacency(vec![(0, vec![1, 2, 3]), (1, vec![3]), (2, vec![3]), (3, vec![])]);
+    let mut shuffled = Cfg::from_adjacency(vec![(0, vec![3, 1, 2]), (1, vec![3]), (2, vec![3]), (3, vec![])]);
+    let path = vec![jump(0), jump(2), jump(3)];
+    let before = StarkraAir::build_trace(path.clone(), shuffled.clone(), 1);
+    let e = StarkraAir::check_neighbor_order(&before).unwrap_err().to_string();
+    assert!(e.contains("row 0: neighbor slot 1 holds 2 after 4"), "{e}");
+    assert!(!same(&before, &StarkraAir::build_trace(path.clone(), sorted.clone(), 1)));
+
+    // either order proves and verifies against either, as the commitment
+    // does not depend on it
+    for (prove_cfg, verify_cfg) in [(&shuffled, &sorted), (&sorted, &shuffled)] {
+        let (proof, pub_inputs) = prove_path(path.clone(), prove_cfg, 1, options()).unwrap();
+        assert_eq!(verify_path(proof, pub_inputs, verify_cfg, &acceptable()), Ok(()));
+    }
+
+    shuffled.canonicalize();
+    let after = StarkraAir::build_trace(path.clone(), shuffled, 1);
+    assert_eq!(StarkraAir::check_neighbor_order(&after), Ok(()));
+    assert!(same(&after, &StarkraAir::build_trace(path, sorted, 1)));
+}
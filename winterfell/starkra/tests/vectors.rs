@@ -0,0 +1,9 @@
+use starkra::vectors::run_vectors;
+
+#[test]
+fn every_conformance_vector_passes() {
+    let reports = run_vectors("vectors").unwrap();
+    assert!(!reports.is_empty());
+    let failed: Vec<String> = reports.iter().filter(|r| !r.passed()).map(|r| r.to_string()).collect();
+    assert!(failed.is_empty(), "{}", failed.join("\n"));
+}
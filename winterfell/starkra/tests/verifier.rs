@@ -0,0 +1,151 @@
+mod common;
+
+use common::*;
+use starkra::{
+    air::{NeighborEncoding, StarkraAir, TraceLayout},
+    cfg::Cfg,
+    divergence::{prove_divergence, verify_divergence},
+    error::StarkraError,
+    proof_io::encode_proof,
+    prover::{StarkraProver, prove_path},
+    sequence::SequenceCommitment,
+    verifier::{Acceptable, VerifyOutcome, verify_batch, verify_encoded, verify_path, verify_prefix},
+};
+use winterfell::{AcceptableOptions, Proof, Prover, Trace, math::fields::f64::BaseElement};
+
+#[test]
+fn batch_rejects_bad_widths_per_item() {
+    // `diamond` with an edge back to 0, for an odd number of neighbor slots
+    let cfg = Cfg::from_adjacency(vec![(0, vec![1]), (1, vec![2, 3, 0]), (2, vec![3]), (3, vec![])]);
+    let path = steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n");
+    let (proof, pub_inputs) = prove_path(path, &cfg, 7, options()).unwrap();
+
+    // a serialized proof starts with its trace info, main width first
+    let mut bytes = proof.to_bytes();
+    bytes[0] = 5;
+    let narrow = Proof::from_bytes(&bytes).unwrap();
+    let mut one_hot = pub_inputs.clone();
+    one_hot.neighbor_encoding = NeighborEncoding::OneHot;
+
+    let results = verify_batch(
+        vec![(proof.clone(), pub_inputs.clone()), (narrow, pub_inputs.clone()), (proof.clone(), one_hot)],
+        &acceptable(),
+    );
+    assert_eq!(results[0], Ok(()));
+    assert_eq!(results[1], Err(StarkraError::InvalidWidth(5)));
+    // 12 + max_succ 3 = 15 columns: no one-hot layout is that wide
+    assert_eq!(results[2], Err(StarkraError::InvalidWidth(15)));
+}
+
+#[test]
+fn verify_outcomes_tell_failures_apart() {
+    let cfg = diamond();
+    let (proof, pub_inputs) = prove_path(steps("initial_node=0 final_node=3\njump 1\njump 3\n"), &cfg, 3, options()).unwrap();
+    let bytes = encode_proof(&proof, &pub_inputs);
+    assert_eq!(verify_encoded(&bytes, &cfg, &acceptable()), VerifyOutcome::Valid);
+
+    let mut bad_header = bytes.clone();
+    bad_header[0] = b'X';
+    assert!(matches!(verify_encoded(&bad_header, &cfg, &acceptable()), VerifyOutcome::MalformedProof(_)));
+    let truncated = &bytes[..bytes.len() - 10];
+    assert!(matches!(verify_encoded(truncated, &cfg, &acceptable()), VerifyOutcome::MalformedProof(_)));
+    let wider = Cfg::from_adjacency(vec![(0, vec![1, 2, 3, 4, 5])]);
+    assert!(matches!(verify_encoded(&bytes, &wider, &acceptable()), VerifyOutcome::MalformedProof(_)));
+
+    // the public inputs start right after the 5-byte header
+    assert!(matches!(verify_encoded(&bytes[..6], &cfg, &acceptable()), VerifyOutcome::BadPublicInputs(_)));
+    let mut unreachable = pub_inputs.clone();
+    unreachable.end = BaseElement::new(10_000);
+    let outcome = verify_encoded(&encode_proof(&proof, &unreachable), &cfg, &acceptable());
+    assert!(matches!(outcome, VerifyOutcome::BadPublicInputs(_)));
+
+    // a plausible but wrong claim is a soundness failure
+    let mut wrong_nonce = pub_inputs;
+    wrong_nonce.nonce = BaseElement::new(4);
+    let outcome = verify_encoded(&encode_proof(&proof, &wrong_nonce), &cfg, &acceptable());
+    assert!(matches!(outcome, VerifyOutcome::InvalidProof(_)));
+    let strict = AcceptableOptions::MinConjecturedSecurity(128);
+    assert!(matches!(verify_encoded(&bytes, &cfg, &strict), VerifyOutcome::InvalidProof(_)));
+}
+
+#[test]
+fn prefix_deviating_from_the_committed_sequence_fails() {
+    let cfg = diamond();
+    let committed = SequenceCommitment::new(&[0, 1, 2, 3]);
+    let opening = committed.open_prefix(3).unwrap();
+    let prover = StarkraProver::new(options()).with_committed_prefix(committed.root(), 3);
+
+    let trace = StarkraAir::build_trace(steps("initial_node=0 final_node=2\njump 1\njump 2\n"), cfg.clone(), 1);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_prefix(proof, pub_inputs, &cfg, &opening, &acceptable()), Ok(()));
+
+    // 0 -> 1 -> 3 is a valid walk, but not the committed one
+    let trace = StarkraAir::build_trace(steps("initial_node=0 final_node=3\njump 1\njump 3\n"), cfg.clone(), 1);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof.clone(), pub_inputs.clone(), &cfg, &acceptable()), Ok(()));
+    assert_eq!(verify_prefix(proof, pub_inputs, &cfg, &opening, &acceptable()), Err(StarkraError::PrefixOpening));
+}
+
+#[test]
+fn max_verify_rows_caps_the_trace_length() {
+    let cfg = diamond();
+    let (proof, pub_inputs) = prove_path(steps("initial_node=0 final_node=3\njump 1\njump 2\njump 3\n"), &cfg, 2, options()).unwrap();
+    let (length, acceptable) = (proof.trace_info().length(), acceptable());
+    let at = Acceptable::new(&acceptable).with_max_verify_rows(length);
+    assert_eq!(verify_path(proof.clone(), pub_inputs.clone(), &cfg, at), Ok(()));
+
+    let below = Acceptable::new(&acceptable).with_max_verify_rows(length - 1);
+    let e = verify_path(proof, pub_inputs, &cfg, below).unwrap_err();
+    assert_eq!(e, StarkraError::TraceTooLong { real_len: length, max: length - 1 });
+    assert!(matches!(VerifyOutcome::from(Err(e)), VerifyOutcome::BadPublicInputs(_)));
+}
+
+#[test]
+fn divergence_halves_with_segments_are_rejected() {
+    let cfg = diamond();
+    let (a, b) = (vec![jump(0), jump(1), jump(2), jump(3)], vec![jump(0), jump(1), jump(3)]);
+    let (proof, inputs) = prove_divergence(a, b, &cfg, 1, 1, options()).unwrap();
+    assert_eq!(verify_divergence(proof.clone(), inputs.clone(), &cfg, &acceptable()), Ok(()));
+
+    // a segment starting on the row after the divergence step
+    let mut segmented = inputs;
+    segmented.a.segment_nonces = vec![(2, BaseElement::new(9))];
+    let e = verify_divergence(proof, segmented, &cfg, &acceptable());
+    assert_eq!(e, Err(StarkraError::SegmentRow { row: 2, limit: 8 }));
+}
+
+#[test]
+fn segment_nonces_are_pinned_and_distinct() {
+    let cfg = diamond();
+    let (first, second) = (vec![jump(0), jump(1), jump(3)], vec![jump(0), jump(1), jump(2), jump(3)]);
+    let prover = StarkraProver::new(options());
+    let trace = StarkraAir::build_segmented_trace(vec![(first.clone(), 5), (second.clone(), 6)], cfg.clone(), &[]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!(pub_inputs.segment_nonces, vec![(4, BaseElement::new(6))]);
+    assert_eq!(StarkraAir::check_trace(&trace, &pub_inputs), Ok(()));
+
+    // the second segment's rows cannot be bound to another nonce
+    let l = TraceLayout::from(&cfg);
+    let mut rebound = trace.clone();
+    for row in 6..trace.length() {
+        rebound.set(l.nonce, row, BaseElement::new(7));
+    }
+    let e = StarkraAir::check_trace(&rebound, &pub_inputs).unwrap_err().to_string();
+    assert!(e.contains("(nonce) fails between rows 5 and 6"), "{e}");
+    // nor can the segment start elsewhere
+    let mut moved = pub_inputs.clone();
+    moved.segment_nonces = vec![(5, BaseElement::new(6))];
+    assert!(StarkraAir::check_trace(&trace, &moved).is_err());
+
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof.clone(), pub_inputs, &cfg, &acceptable()), Ok(()));
+    assert!(verify_path(proof, moved, &cfg, &acceptable()).is_err());
+
+    // a nonce reused across segments is rejected before the STARK check
+    let trace = StarkraAir::build_segmented_trace(vec![(first, 5), (second, 5)], cfg.clone(), &[]);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    let proof = prover.prove(trace).unwrap();
+    assert_eq!(verify_path(proof, pub_inputs, &cfg, &acceptable()), Err(StarkraError::NonceReuse(5)));
+}